@@ -1,9 +1,13 @@
 use std::{
-    io::{Read, Write},
+    io::Write,
     net::TcpStream,
     time::Duration,
 };
 
+use super::super::hexDump::{annotated_dump, hex_dump, Column, DumpFormat};
+use super::httpResponse::{read_response, HttpResponse};
+use super::tcpHeader::parse_tcp_packet;
+
 /// Gera uma requisição HTTP GET completa
 pub fn generate_get_request(host: &str, path: &str) -> String {
     format!(
@@ -16,30 +20,38 @@ pub fn generate_get_request(host: &str, path: &str) -> String {
     )
 }
 
-/// Analisa um pacote TCP bit a bit
+/// Analisa bit a bit um pacote IPv4/IPv6 + TCP bruto (ex.: capturado com tcpdump)
 pub fn analyze_packet(packet: &[u8]) {
     println!("🔍 Análise Detalhada do Pacote ({} bytes)", packet.len());
     println!("{:-<60}", "");
 
-    // Cabeçalho TCP (primeiros 20 bytes)
-    if packet.len() >= 20 {
-        let tcp_header = &packet[..20];
-        println!("📦 Cabeçalho TCP:");
-        print_hex(tcp_header);
-        print_binary(tcp_header);
-        print_decimal(tcp_header);
-    }
+    let parsed = match parse_tcp_packet(packet) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("⚠️ Pacote não decodificado: {}", e);
+            print!("{}", hex_dump(packet, &DumpFormat::default()));
+            return;
+        }
+    };
+
+    println!("🌐 {}", parsed.ip);
+    println!("📦 Cabeçalho TCP:");
+    println!("{}", parsed.tcp);
+
+    // Uma palavra de 32 bits por linha, como nos diagramas da RFC 793
+    let format = DumpFormat::new(&[Column::Offset, Column::Hex, Column::Binary, Column::Decimal])
+        .with_width(4);
+    println!("🔣 Campos do cabeçalho:");
+    print!("{}", annotated_dump(parsed.header, &parsed.tcp.field_ranges(), &format));
 
-    // Dados HTTP
-    if packet.len() > 20 {
-        let http_data = &packet[20..];
-        println!("📄 Dados HTTP:");
-        print_ascii(http_data);
+    if !parsed.payload.is_empty() {
+        println!("📄 Dados ({} bytes):", parsed.payload.len());
+        print_ascii(parsed.payload);
     }
 }
 
-/// Executa uma requisição GET e analisa a resposta
-pub fn execute_get(host: &str, port: u16, path: &str) -> std::io::Result<()> {
+/// Executa uma requisição GET e retorna a resposta decodificada
+pub fn execute_get(host: &str, port: u16, path: &str) -> std::io::Result<HttpResponse> {
     let request = generate_get_request(host, path);
     let mut stream = TcpStream::connect((host, port))?;
     stream.set_read_timeout(Some(Duration::from_secs(5)))?;
@@ -47,42 +59,22 @@ pub fn execute_get(host: &str, port: u16, path: &str) -> std::io::Result<()> {
     println!("🚀 Enviando GET para {}{}", host, path);
     stream.write_all(request.as_bytes())?;
 
-    let mut response = Vec::new();
-    stream.read_to_end(&mut response)?;
-
-    println!("\n📥 Resposta recebida:");
-    analyze_packet(&response);
-
-    Ok(())
-}
-
-// Funções auxiliares de análise
-fn print_hex(data: &[u8]) {
-    println!("🔢 Hexadecimal:");
-    for chunk in data.chunks(16) {
-        print!("   ");
-        for byte in chunk {
-            print!("{:02X} ", byte);
-        }
-        println!();
+    // O socket entrega só os dados: os cabeçalhos IP/TCP já foram removidos pelo kernel
+    let response = read_response(&mut stream)?;
+    println!("\n📥 Resposta: {}", response);
+    for (name, value) in &response.headers {
+        println!("   {}: {}", name, value);
     }
-}
-
-fn print_binary(data: &[u8]) {
-    println!("🔣 Binário:");
-    for byte in data {
-        println!("   {:08b}", byte);
+    if !response.body.is_empty() {
+        println!("📄 Corpo ({} bytes):", response.body.len());
+        print_ascii(&response.body);
     }
-}
 
-fn print_decimal(data: &[u8]) {
-    println!("🔢 Decimal:");
-    for (i, byte) in data.iter().enumerate() {
-        println!("   Byte {}: {}", i, byte);
-    }
+    Ok(response)
 }
 
+// Funções auxiliares de análise
 fn print_ascii(data: &[u8]) {
     println!("📝 ASCII:");
     println!("{}", String::from_utf8_lossy(data));
-}
\ No newline at end of file
+}
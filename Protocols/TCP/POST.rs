@@ -1,9 +1,13 @@
 use std::{
-    io::{Read, Write},
+    io::Write,
     net::TcpStream,
     time::Duration,
 };
 
+use super::super::hexDump::{hex_dump, DumpFormat};
+use super::httpResponse::{read_response, HttpResponse};
+use super::tcpHeader::{parse_tcp_packet, ACK, FIN, PSH, RST, SYN, URG};
+
 /// Gera uma requisição HTTP POST completa
 pub fn generate_post_request(host: &str, path: &str, data: &str) -> String {
     format!(
@@ -19,33 +23,48 @@ pub fn generate_post_request(host: &str, path: &str, data: &str) -> String {
     )
 }
 
-/// Analisa um pacote TCP bit a bit (versão avançada)
+/// Analisa bit a bit um pacote IPv4/IPv6 + TCP bruto (versão avançada)
 pub fn analyze_packet(packet: &[u8]) {
     println!("🔍 Análise Detalhada do Pacote ({} bytes)", packet.len());
     println!("{:-<60}", "");
 
-    // Análise de flags TCP
-    if packet.len() >= 14 {
-        let flags = packet[13];
-        println!("🚩 Flags TCP:");
-        println!("   URG: {}", (flags & 0b00100000) != 0);
-        println!("   ACK: {}", (flags & 0b00010000) != 0);
-        println!("   PSH: {}", (flags & 0b00001000) != 0);
-        println!("   RST: {}", (flags & 0b00000100) != 0);
-        println!("   SYN: {}", (flags & 0b00000010) != 0);
-        println!("   FIN: {}", (flags & 0b00000001) != 0);
+    let parsed = match parse_tcp_packet(packet) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("⚠️ Pacote não decodificado: {}", e);
+            print!("{}", hex_dump(packet, &DumpFormat::default()));
+            return;
+        }
+    };
+    let tcp = &parsed.tcp;
+
+    println!("🌐 {}", parsed.ip);
+    println!(
+        "🔌 Portas: {} -> {} | seq {} | ack {} | janela {}",
+        tcp.src_port, tcp.dst_port, tcp.seq, tcp.ack, tcp.window
+    );
+
+    println!("🚩 Flags TCP:");
+    for (flag, name) in [(URG, "URG"), (ACK, "ACK"), (PSH, "PSH"), (RST, "RST"), (SYN, "SYN"), (FIN, "FIN")] {
+        println!("   {}: {}", name, tcp.has_flag(flag));
     }
 
-    // Dados HTTP
-    if packet.len() > 20 {
-        let http_data = &packet[20..];
+    if !tcp.options.is_empty() {
+        println!("⚙️ Opções TCP:");
+        for option in &tcp.options {
+            println!("   {}", option);
+        }
+    }
+
+    // Dados da aplicação
+    if !parsed.payload.is_empty() {
         println!("\n📄 Dados HTTP:");
-        print_ascii_with_offsets(http_data);
+        print!("{}", hex_dump(parsed.payload, &DumpFormat::default()));
     }
 }
 
-/// Executa uma requisição POST e analisa a resposta
-pub fn execute_post(host: &str, port: u16, path: &str, data: &str) -> std::io::Result<()> {
+/// Executa uma requisição POST e retorna a resposta decodificada
+pub fn execute_post(host: &str, port: u16, path: &str, data: &str) -> std::io::Result<HttpResponse> {
     let request = generate_post_request(host, path, data);
     let mut stream = TcpStream::connect((host, port))?;
     stream.set_read_timeout(Some(Duration::from_secs(5)))?;
@@ -53,29 +72,16 @@ pub fn execute_post(host: &str, port: u16, path: &str, data: &str) -> std::io::R
     println!("🚀 Enviando POST para {}{}", host, path);
     stream.write_all(request.as_bytes())?;
 
-    let mut response = Vec::new();
-    stream.read_to_end(&mut response)?;
-
-    println!("\n📥 Resposta recebida:");
-    analyze_packet(&response);
+    // O socket entrega só os dados: os cabeçalhos IP/TCP já foram removidos pelo kernel
+    let response = read_response(&mut stream)?;
+    println!("\n📥 Resposta: {}", response);
+    for (name, value) in &response.headers {
+        println!("   {}: {}", name, value);
+    }
+    if !response.body.is_empty() {
+        println!("📄 Corpo ({} bytes):", response.body.len());
+        print!("{}", hex_dump(&response.body, &DumpFormat::default()));
+    }
 
-    Ok(())
+    Ok(response)
 }
-
-// Funções auxiliares avançadas
-fn print_ascii_with_offsets(data: &[u8]) {
-    println!("{:8}   {:48}   {}", "Offset", "Hexadecimal", "ASCII");
-    println!("{:-<8}   {:-<48}   {:-<16}", "", "", "");
-
-    for (i, chunk) in data.chunks(16).enumerate() {
-        let offset = i * 16;
-        let hex: String = chunk.iter()
-            .map(|b| format!("{:02X} ", b))
-            .collect();
-        let ascii: String = chunk.iter()
-            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
-            .collect();
-
-        println!("{:08X}   {:48}   {}", offset, hex, ascii);
-    }
-}
\ No newline at end of file
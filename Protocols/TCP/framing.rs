@@ -0,0 +1,417 @@
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+};
+
+/// Maior quadro aceito por padrão, sem contar o delimitador ou o prefixo
+pub const DEFAULT_MAX_FRAME: usize = 64 * 1024;
+
+/// Bytes do prefixo de tamanho
+const LENGTH_PREFIX_LEN: usize = 4;
+
+/// Como as requisições são delimitadas na conexão
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameMode {
+    Line,           // Terminada por "\n"; "\r\n" também é aceito
+    LengthPrefixed, // Tamanho u32 em ordem de rede seguido do conteúdo
+}
+
+impl FrameMode {
+    /// Modo indicado pelo primeiro byte da conexão
+    ///
+    /// Um prefixo de tamanho abaixo de 16 MB sempre começa com 0x00, que
+    /// nunca inicia uma linha de comando.
+    pub fn detect(first_byte: u8) -> Self {
+        if first_byte == 0 {
+            FrameMode::LengthPrefixed
+        } else {
+            FrameMode::Line
+        }
+    }
+}
+
+impl fmt::Display for FrameMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameMode::Line => write!(f, "linhas"),
+            FrameMode::LengthPrefixed => write!(f, "prefixo de tamanho"),
+        }
+    }
+}
+
+/// Erro ao ler um quadro
+#[derive(Debug)]
+pub enum FrameError {
+    Io(io::Error),
+    LineTooLong { limit: usize },                    // Linha descartada até o "\n"
+    FrameTooLarge { size: usize, limit: usize },     // Conteúdo descartado
+    Truncated { expected: usize, available: usize }, // Conexão fechada no meio do quadro
+}
+
+impl FrameError {
+    /// O quadro foi descartado por inteiro e a leitura pode continuar no próximo
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, FrameError::LineTooLong { .. } | FrameError::FrameTooLarge { .. })
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "erro de E/S: {}", e),
+            FrameError::LineTooLong { limit } => {
+                write!(f, "linha excede o limite de {} bytes", limit)
+            }
+            FrameError::FrameTooLarge { size, limit } => {
+                write!(f, "quadro de {} bytes excede o limite de {} bytes", size, limit)
+            }
+            FrameError::Truncated { expected, available } => write!(
+                f,
+                "quadro truncado: {} bytes esperados, {} recebidos",
+                expected, available
+            ),
+        }
+    }
+}
+
+impl std::error::Error for FrameError {}
+
+impl From<io::Error> for FrameError {
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+impl From<FrameError> for io::Error {
+    fn from(e: FrameError) -> Self {
+        match e {
+            FrameError::Io(e) => e,
+            e => io::Error::new(io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Leitor de quadros sobre um fluxo de bytes
+///
+/// Um `read` pode trazer parte de um quadro ou vários quadros seguidos; o
+/// buffer interno guarda o que sobrou para a próxima chamada de `read_frame`.
+pub struct FrameReader<R> {
+    reader: BufReader<R>,
+    mode: Option<FrameMode>, // `None`: detectado no primeiro byte recebido
+    max_frame: usize,
+    bytes_read: usize, // Total consumido do fluxo, incluindo delimitadores e prefixos
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            reader: BufReader::new(inner),
+            mode: None,
+            max_frame: DEFAULT_MAX_FRAME,
+            bytes_read: 0,
+        }
+    }
+
+    /// Usa `mode` em vez de detectar pelo primeiro byte
+    pub fn with_mode(mut self, mode: FrameMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    pub fn with_max_frame(mut self, max_frame: usize) -> Self {
+        self.max_frame = max_frame;
+        self
+    }
+
+    pub fn mode(&self) -> Option<FrameMode> {
+        self.mode
+    }
+
+    pub fn max_frame(&self) -> usize {
+        self.max_frame
+    }
+
+    pub fn bytes_read(&self) -> usize {
+        self.bytes_read
+    }
+
+    /// Há um quadro completo no buffer, legível sem bloquear
+    pub fn has_buffered_frame(&self) -> bool {
+        let buffered = self.reader.buffer();
+        match self.mode {
+            Some(FrameMode::Line) => buffered.contains(&b'\n'),
+            Some(FrameMode::LengthPrefixed) => match buffered.get(..LENGTH_PREFIX_LEN) {
+                Some(prefix) => {
+                    let size = u32::from_be_bytes(prefix.try_into().expect("4 bytes")) as usize;
+                    buffered.len() - LENGTH_PREFIX_LEN >= size
+                }
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Próximo quadro, sem delimitador ou prefixo; `None` quando a conexão fecha entre quadros
+    ///
+    /// Uma linha final sem "\n" antes do fechamento também é um quadro. Quadros
+    /// acima de `max_frame` são descartados sem serem guardados em memória e
+    /// retornam um erro recuperável.
+    pub fn read_frame(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => {
+                let Some(&first) = self.reader.fill_buf()?.first() else {
+                    return Ok(None);
+                };
+                let mode = FrameMode::detect(first);
+                self.mode = Some(mode);
+                mode
+            }
+        };
+        match mode {
+            FrameMode::Line => self.read_line(),
+            FrameMode::LengthPrefixed => self.read_prefixed(),
+        }
+    }
+
+    fn read_line(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let mut line = Vec::new();
+        let mut too_long = false;
+
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                if !too_long && line.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            let (content, used, found) = match buf.iter().position(|&b| b == b'\n') {
+                Some(end) => (&buf[..end], end + 1, true),
+                None => (buf, buf.len(), false),
+            };
+            if !too_long && line.len() + content.len() > self.max_frame {
+                too_long = true;
+                line = Vec::new();
+            }
+            if !too_long {
+                line.extend_from_slice(content);
+            }
+            self.reader.consume(used);
+            self.bytes_read += used;
+            if found {
+                break;
+            }
+        }
+
+        if too_long {
+            return Err(FrameError::LineTooLong { limit: self.max_frame });
+        }
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    fn read_prefixed(&mut self) -> Result<Option<Vec<u8>>, FrameError> {
+        let mut prefix = [0u8; LENGTH_PREFIX_LEN];
+        match self.read_up_to(&mut prefix)? {
+            0 => return Ok(None),
+            LENGTH_PREFIX_LEN => {}
+            available => {
+                return Err(FrameError::Truncated {
+                    expected: LENGTH_PREFIX_LEN,
+                    available,
+                })
+            }
+        }
+
+        let size = u32::from_be_bytes(prefix) as usize;
+        if size > self.max_frame {
+            // Descarta o conteúdo para manter a leitura alinhada ao próximo quadro
+            let skipped = io::copy(&mut (&mut self.reader).take(size as u64), &mut io::sink())? as usize;
+            self.bytes_read += skipped;
+            if skipped < size {
+                return Err(FrameError::Truncated {
+                    expected: size,
+                    available: skipped,
+                });
+            }
+            return Err(FrameError::FrameTooLarge {
+                size,
+                limit: self.max_frame,
+            });
+        }
+
+        let mut payload = vec![0u8; size];
+        let available = self.read_up_to(&mut payload)?;
+        if available < size {
+            return Err(FrameError::Truncated {
+                expected: size,
+                available,
+            });
+        }
+        Ok(Some(payload))
+    }
+
+    /// Preenche `buf` até o fim ou até a conexão fechar; retorna quantos bytes leu
+    fn read_up_to(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.bytes_read += filled;
+        Ok(filled)
+    }
+}
+
+/// Escreve `payload` como um quadro no modo indicado
+///
+/// No modo de linha, um conteúdo com várias linhas chega ao cliente como
+/// várias linhas; use o prefixo de tamanho para delimitá-lo sem ambiguidade.
+pub fn write_frame<W: Write>(writer: &mut W, mode: FrameMode, payload: &[u8]) -> io::Result<()> {
+    match mode {
+        FrameMode::Line => {
+            writer.write_all(payload)?;
+            writer.write_all(b"\n")
+        }
+        FrameMode::LengthPrefixed => {
+            let size = u32::try_from(payload.len()).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("quadro de {} bytes não cabe no prefixo de tamanho", payload.len()),
+                )
+            })?;
+            writer.write_all(&size.to_be_bytes())?;
+            writer.write_all(payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entrega um pedaço por chamada de `read`, como uma conexão TCP lenta
+    struct Chunked {
+        chunks: Vec<Vec<u8>>,
+        next: usize,
+    }
+
+    impl Chunked {
+        fn new(chunks: &[&[u8]]) -> Self {
+            Self {
+                chunks: chunks.iter().map(|chunk| chunk.to_vec()).collect(),
+                next: 0,
+            }
+        }
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some(chunk) = self.chunks.get_mut(self.next) else {
+                return Ok(0);
+            };
+            let len = chunk.len().min(buf.len());
+            buf[..len].copy_from_slice(&chunk[..len]);
+            chunk.drain(..len);
+            if chunk.is_empty() {
+                self.next += 1;
+            }
+            Ok(len)
+        }
+    }
+
+    fn prefixed(payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_frame(&mut out, FrameMode::LengthPrefixed, payload).unwrap();
+        out
+    }
+
+    fn frame(reader: &mut FrameReader<impl Read>) -> Option<Vec<u8>> {
+        reader.read_frame().unwrap()
+    }
+
+    #[test]
+    fn linha_dividida_entre_leituras() {
+        let mut reader = FrameReader::new(Chunked::new(&[b"PI", b"NG\r", b"\nECHO a", b" b\n"]));
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"PING"[..]));
+        assert_eq!(reader.mode(), Some(FrameMode::Line));
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"ECHO a b"[..]));
+        assert_eq!(frame(&mut reader), None);
+        assert_eq!(reader.bytes_read(), 15);
+    }
+
+    #[test]
+    fn prefixo_e_conteudo_divididos_entre_leituras() {
+        let data = prefixed(b"STATS");
+        let mut reader = FrameReader::new(Chunked::new(&[&data[..2], &data[2..5], &data[5..]]));
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"STATS"[..]));
+        assert_eq!(reader.mode(), Some(FrameMode::LengthPrefixed));
+        assert_eq!(frame(&mut reader), None);
+    }
+
+    #[test]
+    fn varios_quadros_em_uma_leitura() {
+        let mut reader = FrameReader::new(Chunked::new(&[b"PING\nTIME UTC\nHELP"]));
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"PING"[..]));
+        assert!(reader.has_buffered_frame());
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"TIME UTC"[..]));
+        assert!(!reader.has_buffered_frame());
+        // Linha final sem "\n" antes do fechamento também é um quadro
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"HELP"[..]));
+        assert_eq!(frame(&mut reader), None);
+
+        let data = [prefixed(b"a"), prefixed(b""), prefixed(b"bc")].concat();
+        let mut reader = FrameReader::new(&data[..]);
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"a"[..]));
+        assert!(reader.has_buffered_frame());
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b""[..]));
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"bc"[..]));
+        assert_eq!(frame(&mut reader), None);
+    }
+
+    #[test]
+    fn linha_longa_descartada_e_leitura_continua() {
+        let chunks: [&[u8]; 3] = [b"ECHO 12", b"3456789", b"0\nPING\n"];
+        let mut reader = FrameReader::new(Chunked::new(&chunks)).with_max_frame(8);
+        let error = reader.read_frame().unwrap_err();
+        assert!(matches!(error, FrameError::LineTooLong { limit: 8 }), "{}", error);
+        assert!(error.is_recoverable());
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"PING"[..]));
+        assert_eq!(reader.bytes_read(), 21);
+    }
+
+    #[test]
+    fn prefixo_acima_do_limite_e_pulado() {
+        let data = [prefixed(&[b'x'; 100]), prefixed(b"PING")].concat();
+        let mut reader =
+            FrameReader::new(Chunked::new(&[&data[..50], &data[50..]])).with_max_frame(16);
+        let error = reader.read_frame().unwrap_err();
+        assert!(matches!(error, FrameError::FrameTooLarge { size: 100, limit: 16 }), "{}", error);
+        assert!(error.is_recoverable());
+        assert_eq!(frame(&mut reader).as_deref(), Some(&b"PING"[..]));
+        assert_eq!(reader.bytes_read(), data.len());
+    }
+
+    #[test]
+    fn conexao_fecha_no_meio_do_quadro() {
+        let truncated = |data: &[u8]| match FrameReader::new(data).read_frame() {
+            Err(FrameError::Truncated { expected, available }) => (expected, available),
+            other => panic!("esperado quadro truncado, obtido {:?}", other),
+        };
+        assert_eq!(truncated(&[0, 0]), (4, 2));
+        assert_eq!(truncated(&[0, 0, 0, 5, b'a', b'b']), (5, 2));
+
+        // Um quadro grande demais que termina antes do fim não é recuperável
+        let mut reader = FrameReader::new(&[0u8, 0, 1, 0, b'x'][..]).with_max_frame(16);
+        let error = reader.read_frame().unwrap_err();
+        assert!(matches!(error, FrameError::Truncated { expected: 256, available: 1 }), "{}", error);
+        assert!(!error.is_recoverable());
+    }
+}
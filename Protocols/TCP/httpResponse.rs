@@ -0,0 +1,634 @@
+use std::{borrow::Cow, fmt, io::Read};
+
+/// Maior resposta aceita por `read_response` (linha de status, cabeçalhos e corpo)
+pub const DEFAULT_MAX_RESPONSE: usize = 16 * 1024 * 1024;
+
+/// Erro ao ler ou decodificar uma resposta HTTP/1.1
+#[derive(Debug)]
+pub enum HttpError {
+    Io(std::io::Error),
+    Incomplete(&'static str),  // Conexão fechada antes do fim da parte indicada
+    InvalidStatusLine(String),
+    InvalidHeader(String),
+    InvalidContentLength(String),
+    InvalidChunk(String),
+    Truncated { expected: usize, available: usize }, // Corpo menor que o Content-Length
+    TooLarge { limit: usize },                       // Resposta passaria do limite de bytes
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::Io(e) => write!(f, "erro de E/S: {}", e),
+            HttpError::Incomplete(part) => write!(f, "resposta incompleta: {} não terminou", part),
+            HttpError::InvalidStatusLine(line) => write!(f, "linha de status inválida: {:?}", line),
+            HttpError::InvalidHeader(line) => write!(f, "cabeçalho inválido: {:?}", line),
+            HttpError::InvalidContentLength(value) => {
+                write!(f, "Content-Length inválido: {:?}", value)
+            }
+            HttpError::InvalidChunk(line) => write!(f, "chunk inválido: {:?}", line),
+            HttpError::Truncated { expected, available } => write!(
+                f,
+                "corpo truncado: Content-Length {} mas só {} bytes recebidos",
+                expected, available
+            ),
+            HttpError::TooLarge { limit } => {
+                write!(f, "resposta excede o limite de {} bytes", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl From<std::io::Error> for HttpError {
+    fn from(e: std::io::Error) -> Self {
+        HttpError::Io(e)
+    }
+}
+
+impl From<HttpError> for std::io::Error {
+    fn from(e: HttpError) -> Self {
+        match e {
+            HttpError::Io(e) => e,
+            e => std::io::Error::new(std::io::ErrorKind::InvalidData, e),
+        }
+    }
+}
+
+/// Resposta HTTP/1.1 decodificada
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub version: String, // Ex.: "HTTP/1.1"
+    pub status: u16,
+    pub reason: String,
+    pub headers: Vec<(String, String)>, // Na ordem recebida, nomes como enviados
+    pub trailers: Vec<(String, String)>, // Campos após o último chunk
+    pub body: Vec<u8>,                  // Já sem a codificação chunked
+}
+
+impl HttpResponse {
+    /// Primeiro valor do cabeçalho `name` (sem diferenciar maiúsculas)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Todos os valores do cabeçalho `name`, na ordem recebida
+    pub fn header_values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    pub fn is_redirect(&self) -> bool {
+        (300..400).contains(&self.status)
+    }
+
+    /// Destino de um redirecionamento (cabeçalho Location)
+    pub fn location(&self) -> Option<&str> {
+        self.header("Location")
+    }
+
+    /// Corpo como texto; bytes inválidos em UTF-8 viram U+FFFD
+    pub fn body_text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+}
+
+impl fmt::Display for HttpResponse {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {}", self.version, self.status, self.reason)
+    }
+}
+
+/// Decodifica uma resposta completa (a conexão já foi fechada)
+///
+/// Respostas 1xx intermediárias (ex.: `100 Continue`) são descartadas e a
+/// resposta final é retornada.
+pub fn parse_response(raw: &[u8]) -> Result<HttpResponse, HttpError> {
+    let mut parser = ResponseParser::new(usize::MAX);
+    parser.feed(raw)?;
+    match parser.advance(true)? {
+        Some(response) => Ok(response),
+        None => Err(HttpError::Incomplete("resposta")),
+    }
+}
+
+/// Lê de `reader` até ter uma resposta completa, com até `DEFAULT_MAX_RESPONSE` bytes
+///
+/// Com Content-Length ou chunked, para assim que o corpo termina, sem esperar
+/// o servidor fechar a conexão; sem nenhum dos dois, lê até o fim do stream.
+pub fn read_response<R: Read>(reader: &mut R) -> Result<HttpResponse, HttpError> {
+    read_response_limited(reader, DEFAULT_MAX_RESPONSE)
+}
+
+/// Como `read_response`, recusando respostas com mais de `max_size` bytes
+///
+/// Um Content-Length ou tamanho de chunk acima do limite é recusado antes de
+/// os dados chegarem.
+pub fn read_response_limited<R: Read>(
+    reader: &mut R,
+    max_size: usize,
+) -> Result<HttpResponse, HttpError> {
+    let mut parser = ResponseParser::new(max_size);
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            return parser
+                .advance(true)?
+                .ok_or(HttpError::Incomplete("resposta"));
+        }
+        parser.feed(&buf[..n])?;
+        if let Some(response) = parser.advance(false)? {
+            return Ok(response);
+        }
+    }
+}
+
+/// Como o corpo da resposta final é delimitado
+#[derive(Debug)]
+enum Body {
+    Empty,
+    Length(usize),
+    Chunked {
+        data: Vec<u8>,
+        trailers: Option<Vec<(String, String)>>, // `Some` após o chunk de tamanho zero
+    },
+    UntilClose,
+}
+
+/// Decodificador incremental: cada byte recebido é examinado uma única vez
+///
+/// `pos` aponta para o que ainda não foi consumido: o início dos cabeçalhos,
+/// a próxima linha de tamanho de chunk ou o início do corpo.
+struct ResponseParser {
+    raw: Vec<u8>,
+    max_size: usize,
+    pos: usize,
+    scan: usize, // Início da próxima linha ainda não vista nos cabeçalhos
+    head: Option<(HttpResponse, Body)>,
+}
+
+impl ResponseParser {
+    fn new(max_size: usize) -> Self {
+        Self {
+            raw: Vec::new(),
+            max_size,
+            pos: 0,
+            scan: 0,
+            head: None,
+        }
+    }
+
+    fn feed(&mut self, data: &[u8]) -> Result<(), HttpError> {
+        if self.raw.len() + data.len() > self.max_size {
+            return Err(HttpError::TooLarge { limit: self.max_size });
+        }
+        self.raw.extend_from_slice(data);
+        Ok(())
+    }
+
+    /// Resposta final, se já completa; `eof` indica que não chegarão mais bytes
+    fn advance(&mut self, eof: bool) -> Result<Option<HttpResponse>, HttpError> {
+        loop {
+            if self.head.is_none() && !self.read_head(eof)? {
+                return Ok(None);
+            }
+            let (response, body) = self.head.as_mut().expect("cabeçalhos lidos acima");
+            // 1xx (exceto 101 Switching Protocols) precede a resposta final
+            if (100..200).contains(&response.status) && response.status != 101 {
+                self.head = None;
+                continue;
+            }
+
+            let done = match body {
+                Body::Empty => true,
+                Body::Length(length) => {
+                    let available = self.raw.len() - self.pos;
+                    if available >= *length {
+                        response.body = self.raw[self.pos..self.pos + *length].to_vec();
+                        true
+                    } else if eof {
+                        return Err(HttpError::Truncated { expected: *length, available });
+                    } else {
+                        false
+                    }
+                }
+                Body::Chunked { data, trailers } => {
+                    let done = read_chunks(&self.raw, &mut self.pos, data, trailers, self.max_size)?;
+                    if !done && eof {
+                        let part = if trailers.is_some() { "trailers" } else { "corpo chunked" };
+                        return Err(HttpError::Incomplete(part));
+                    }
+                    if done {
+                        response.body = std::mem::take(data);
+                        response.trailers = trailers.take().unwrap_or_default();
+                    }
+                    done
+                }
+                Body::UntilClose => {
+                    if eof {
+                        response.body = self.raw[self.pos..].to_vec();
+                    }
+                    eof
+                }
+            };
+            return Ok(if done { self.head.take().map(|(response, _)| response) } else { None });
+        }
+    }
+
+    /// Lê a linha de status e os cabeçalhos assim que a linha em branco chega
+    fn read_head(&mut self, eof: bool) -> Result<bool, HttpError> {
+        let mut end = None;
+        let mut pos = self.scan.max(self.pos);
+        while let Some(line) = next_line(&self.raw, &mut pos) {
+            self.scan = pos;
+            if line.is_empty() && pos - line_len(&self.raw, pos) > self.pos {
+                end = Some(pos);
+                break;
+            }
+        }
+        let head_complete = end.is_some();
+        if !head_complete && !eof {
+            return Ok(false);
+        }
+        // Com o fim do stream, `parse_head` aponta a parte que ficou incompleta
+        let Some((response, body_pos)) = parse_head(&self.raw, self.pos, eof)? else {
+            return Ok(false);
+        };
+        let body = self.body_kind(&response, body_pos)?;
+        self.pos = body_pos;
+        self.scan = body_pos;
+        self.head = Some((response, body));
+        Ok(true)
+    }
+
+    fn body_kind(&self, response: &HttpResponse, body_pos: usize) -> Result<Body, HttpError> {
+        let chunked = response
+            .header_values("Transfer-Encoding")
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .filter(|coding| !coding.is_empty())
+            .last()
+            .is_some_and(|coding| coding.eq_ignore_ascii_case("chunked"));
+        let has_transfer_encoding = response.header("Transfer-Encoding").is_some();
+
+        let no_body = (100..200).contains(&response.status) || matches!(response.status, 204 | 304);
+        Ok(if no_body {
+            // Sem corpo por definição (RFC 9112, seção 6.3)
+            Body::Empty
+        } else if chunked {
+            Body::Chunked { data: Vec::new(), trailers: None }
+        } else if let (false, Some(length)) = (has_transfer_encoding, content_length(response)?) {
+            if length > self.max_size.saturating_sub(body_pos) {
+                return Err(HttpError::TooLarge { limit: self.max_size });
+            }
+            Body::Length(length)
+        } else {
+            // Corpo delimitado pelo fechamento da conexão
+            Body::UntilClose
+        })
+    }
+}
+
+/// Tamanho da linha que termina em `end` (incluindo o LF), para saber onde ela começou
+fn line_len(raw: &[u8], end: usize) -> usize {
+    if end >= 2 && raw[end - 2] == b'\r' {
+        2
+    } else {
+        1
+    }
+}
+
+/// Consome os chunks completos a partir de `pos`; `true` quando os trailers terminaram
+///
+/// `pos` só avança sobre chunks inteiros, então a próxima chamada continua de
+/// onde esta parou sem reler os anteriores.
+fn read_chunks(
+    raw: &[u8],
+    pos: &mut usize,
+    data: &mut Vec<u8>,
+    trailers: &mut Option<Vec<(String, String)>>,
+    max_size: usize,
+) -> Result<bool, HttpError> {
+    loop {
+        if let Some(fields) = trailers {
+            let mut next = *pos;
+            match next_line(raw, &mut next) {
+                Some([]) => {
+                    *pos = next;
+                    return Ok(true);
+                }
+                Some(line) => push_field(fields, line)?,
+                None => return Ok(false),
+            }
+            *pos = next;
+            continue;
+        }
+
+        let mut next = *pos;
+        let Some(line) = next_line(raw, &mut next) else {
+            return Ok(false);
+        };
+        let text = String::from_utf8_lossy(line);
+        // Extensões após ';' são ignoradas
+        let size = text.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| HttpError::InvalidChunk(text.to_string()))?;
+        if size > max_size.saturating_sub(data.len()) {
+            return Err(HttpError::TooLarge { limit: max_size });
+        }
+
+        if size == 0 {
+            *pos = next;
+            *trailers = Some(Vec::new());
+            continue;
+        }
+
+        if raw.len() - next < size {
+            return Ok(false);
+        }
+        let chunk = &raw[next..next + size];
+        next += size;
+        match next_line(raw, &mut next) {
+            Some([]) => {}
+            Some(extra) => {
+                return Err(HttpError::InvalidChunk(String::from_utf8_lossy(extra).to_string()))
+            }
+            None => return Ok(false),
+        }
+        data.extend_from_slice(chunk);
+        *pos = next;
+    }
+}
+
+/// Linha de status e cabeçalhos a partir de `start`; retorna a posição do corpo
+fn parse_head(raw: &[u8], start: usize, eof: bool) -> Result<Option<(HttpResponse, usize)>, HttpError> {
+    let mut pos = start;
+    let status_line = match next_line(raw, &mut pos) {
+        Some(line) => line,
+        None if eof => return Err(HttpError::Incomplete("linha de status")),
+        None => return Ok(None),
+    };
+    let mut response = parse_status_line(status_line)?;
+
+    loop {
+        let line = match next_line(raw, &mut pos) {
+            Some(line) => line,
+            None if eof => return Err(HttpError::Incomplete("cabeçalhos")),
+            None => return Ok(None),
+        };
+        if line.is_empty() {
+            return Ok(Some((response, pos)));
+        }
+        push_field(&mut response.headers, line)?;
+    }
+}
+
+fn parse_status_line(line: &[u8]) -> Result<HttpResponse, HttpError> {
+    let text = String::from_utf8_lossy(line);
+    let invalid = || HttpError::InvalidStatusLine(text.to_string());
+
+    let mut parts = text.splitn(3, ' ');
+    let version = parts.next().filter(|v| v.starts_with("HTTP/")).ok_or_else(invalid)?;
+    let code = parts.next().ok_or_else(invalid)?;
+    if code.len() != 3 || !code.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    Ok(HttpResponse {
+        version: version.to_string(),
+        status: code.parse().map_err(|_| invalid())?,
+        reason: parts.next().unwrap_or_default().trim().to_string(),
+        headers: Vec::new(),
+        trailers: Vec::new(),
+        body: Vec::new(),
+    })
+}
+
+/// Adiciona um campo "Nome: valor"; linhas iniciadas por espaço continuam o anterior
+fn push_field(fields: &mut Vec<(String, String)>, line: &[u8]) -> Result<(), HttpError> {
+    let text = String::from_utf8_lossy(line);
+    if text.starts_with([' ', '\t']) {
+        let (_, value) = fields
+            .last_mut()
+            .ok_or_else(|| HttpError::InvalidHeader(text.to_string()))?;
+        value.push(' ');
+        value.push_str(text.trim());
+        return Ok(());
+    }
+    let (name, value) = text
+        .split_once(':')
+        .filter(|(name, _)| !name.is_empty() && !name.ends_with([' ', '\t']))
+        .ok_or_else(|| HttpError::InvalidHeader(text.to_string()))?;
+    fields.push((name.to_string(), value.trim().to_string()));
+    Ok(())
+}
+
+/// Content-Length declarado; valores repetidos precisam ser iguais
+fn content_length(response: &HttpResponse) -> Result<Option<usize>, HttpError> {
+    let mut length = None;
+    for value in response.header_values("Content-Length").flat_map(|v| v.split(',')) {
+        let value = value.trim();
+        let parsed = value
+            .parse::<usize>()
+            .map_err(|_| HttpError::InvalidContentLength(value.to_string()))?;
+        match length {
+            Some(previous) if previous != parsed => {
+                return Err(HttpError::InvalidContentLength(value.to_string()))
+            }
+            _ => length = Some(parsed),
+        }
+    }
+    Ok(length)
+}
+
+/// Próxima linha a partir de `pos`, sem o CRLF (ou LF isolado); avança `pos`
+fn next_line<'a>(raw: &'a [u8], pos: &mut usize) -> Option<&'a [u8]> {
+    let rest = raw.get(*pos..)?;
+    let end = rest.iter().position(|&b| b == b'\n')?;
+    *pos += end + 1;
+    let line = &rest[..end];
+    Some(line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+
+    /// Entrega um pedaço por `read`; depois do último, a conexão segue aberta sem dados
+    struct Pieces<'a> {
+        pieces: Vec<&'a [u8]>,
+    }
+
+    impl Read for Pieces<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.pieces.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::WouldBlock, "servidor não fechou"));
+            }
+            let piece = self.pieces.remove(0);
+            buf[..piece.len()].copy_from_slice(piece);
+            Ok(piece.len())
+        }
+    }
+
+    fn parse(raw: &str) -> Result<HttpResponse, HttpError> {
+        parse_response(raw.as_bytes())
+    }
+
+    #[test]
+    fn linha_de_status() {
+        let response = parse("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert_eq!((response.version.as_str(), response.status), ("HTTP/1.1", 404));
+        assert_eq!(response.reason, "Not Found");
+        assert_eq!(response.to_string(), "HTTP/1.1 404 Not Found");
+
+        let response = parse("HTTP/1.0 200\r\n\r\nok").unwrap();
+        assert_eq!((response.status, response.reason.as_str()), (200, ""));
+
+        for line in ["HTTP/1.1 20 OK", "HTTP/1.1 abc OK", "ICY 200 OK", ""] {
+            let raw = format!("{}\r\n\r\n", line);
+            assert!(matches!(parse(&raw), Err(HttpError::InvalidStatusLine(_))), "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn cabecalho_continuado_em_linha_dobrada() {
+        let response =
+            parse("HTTP/1.1 200 OK\r\nX-Longo: parte um\r\n   parte dois\r\n\tparte tres\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+        assert_eq!(response.header("x-longo"), Some("parte um parte dois parte tres"));
+        assert_eq!(response.header("Content-Length"), Some("0"));
+
+        assert!(matches!(
+            parse("HTTP/1.1 200 OK\r\n continua o nada\r\n\r\n"),
+            Err(HttpError::InvalidHeader(_))
+        ));
+        assert!(matches!(
+            parse("HTTP/1.1 200 OK\r\nNome : valor\r\n\r\n"),
+            Err(HttpError::InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn respostas_1xx_sao_descartadas() {
+        let response = parse(
+            "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 103 Early Hints\r\nLink: </a.css>\r\n\r\n\
+             HTTP/1.1 201 Created\r\nContent-Length: 2\r\n\r\nok",
+        )
+        .unwrap();
+        assert_eq!(response.status, 201);
+        assert_eq!(response.header("Link"), None);
+        assert_eq!(response.body, b"ok");
+    }
+
+    #[test]
+    fn corpo_chunked_com_trailers() {
+        let response = parse(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: gzip, chunked\r\n\r\n\
+             4\r\nWiki\r\n5;ext=1\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: amanhã\r\n\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.body_text(), "Wikipedia in\r\n\r\nchunks.");
+        assert_eq!(response.trailers, vec![("Expires".to_string(), "amanhã".to_string())]);
+    }
+
+    #[test]
+    fn corpo_por_content_length() {
+        let response = parse("HTTP/1.1 200 OK\r\nContent-Length: 5\r\n\r\nhelloEXTRA").unwrap();
+        assert_eq!(response.body, b"hello");
+
+        let response = parse("HTTP/1.1 200 OK\r\nContent-Length: 3, 3\r\n\r\nabc").unwrap();
+        assert_eq!(response.body, b"abc");
+        assert!(matches!(
+            parse("HTTP/1.1 200 OK\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\nabcd"),
+            Err(HttpError::InvalidContentLength(_))
+        ));
+
+        // 204/304 não têm corpo mesmo com Content-Length
+        let response = parse("HTTP/1.1 304 Not Modified\r\nContent-Length: 10\r\n\r\n").unwrap();
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn corpo_ate_o_fechamento() {
+        let raw: &[u8] = b"HTTP/1.0 200 OK\r\nServer: antigo\r\n\r\nsem tamanho\nnem chunks";
+        assert_eq!(parse_response(raw).unwrap().body_text(), "sem tamanho\nnem chunks");
+        let response = read_response(&mut &raw[..]).unwrap();
+        assert_eq!(response.body_text(), "sem tamanho\nnem chunks");
+    }
+
+    #[test]
+    fn corpo_truncado() {
+        assert!(matches!(
+            parse("HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nabcd"),
+            Err(HttpError::Truncated { expected: 10, available: 4 })
+        ));
+        let chunked = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        assert!(matches!(
+            parse(&format!("{}5\r\nab", chunked)),
+            Err(HttpError::Incomplete("corpo chunked"))
+        ));
+        assert!(matches!(
+            parse(&format!("{}2\r\nab\r\n0\r\nX: 1\r\n", chunked)),
+            Err(HttpError::Incomplete("trailers"))
+        ));
+        assert!(matches!(parse("HTTP/1.1 200 OK\r\nServer: x"), Err(HttpError::Incomplete("cabeçalhos"))));
+        assert!(matches!(parse("HTTP/1.1 200"), Err(HttpError::Incomplete("linha de status"))));
+    }
+
+    #[test]
+    fn tamanho_de_chunk_invalido() {
+        let chunked = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n";
+        for body in ["zz\r\n", "-1\r\n", "\r\n", "2\r\nabc\r\n0\r\n\r\n"] {
+            let raw = format!("{}{}", chunked, body);
+            assert!(matches!(parse(&raw), Err(HttpError::InvalidChunk(_))), "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn resposta_dividida_entre_leituras() {
+        let raw = b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                    3\r\nabc\r\n2\r\nde\r\n0\r\nX-Fim: sim\r\n\r\n";
+        // Um byte por leitura: cada fronteira possível cai no meio de alguma parte
+        let mut reader = Pieces { pieces: raw.chunks(1).collect() };
+        let response = read_response(&mut reader).unwrap();
+        assert_eq!(response.body, b"abcde");
+        assert_eq!(response.trailers[0].1, "sim");
+
+        let raw = b"HTTP/1.1 200 OK\r\nContent-Length: 6\r\n\r\nabcdef";
+        for split in 1..raw.len() {
+            let mut reader = Pieces { pieces: vec![&raw[..split], &raw[split..]] };
+            assert_eq!(read_response(&mut reader).unwrap().body, b"abcdef", "{}", split);
+        }
+    }
+
+    #[test]
+    fn resposta_acima_do_limite() {
+        // Recusada pelo Content-Length, antes de o corpo chegar
+        let mut reader = Pieces { pieces: vec![b"HTTP/1.1 200 OK\r\nContent-Length: 1000000\r\n\r\n"] };
+        assert!(matches!(
+            read_response_limited(&mut reader, 1024),
+            Err(HttpError::TooLarge { limit: 1024 })
+        ));
+
+        let mut reader = Pieces {
+            pieces: vec![b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\nFFFFFFFF\r\n"],
+        };
+        assert!(matches!(read_response_limited(&mut reader, 1024), Err(HttpError::TooLarge { .. })));
+
+        let body = vec![b'x'; 2048];
+        let raw = [&b"HTTP/1.0 200 OK\r\n\r\n"[..], &body].concat();
+        assert!(matches!(read_response_limited(&mut &raw[..], 1024), Err(HttpError::TooLarge { .. })));
+        assert_eq!(read_response(&mut &raw[..]).unwrap().body.len(), 2048);
+    }
+}
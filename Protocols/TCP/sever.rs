@@ -1,6 +1,6 @@
 use std::{
     net::{TcpListener, TcpStream},
-    io::{Read, Write},
+    io::{BufWriter, Write},
     thread,
     time::{Duration, Instant},
     sync::{
@@ -10,6 +10,12 @@ use std::{
     collections::HashMap,
 };
 
+use super::super::commands::{parse_command_line, CommandRegistry};
+use super::framing::{write_frame, FrameError, FrameMode, FrameReader};
+
+/// Maior requisição aceita, sem contar o "\n" ou o prefixo de tamanho
+const MAX_REQUEST_SIZE: usize = 4096;
+
 // Contadores atômicos
 static CONNECTION_COUNT: AtomicUsize = AtomicUsize::new(0);
 static TOTAL_BYTES: AtomicUsize = AtomicUsize::new(0);
@@ -21,6 +27,7 @@ fn main() -> std::io::Result<()> {
 
     // Shared state para estatísticas
     let stats = Arc::new(ServerStats::new());
+    let commands = Arc::new(CommandRegistry::with_builtins());
 
     for stream in listener.incoming() {
         match stream {
@@ -31,12 +38,13 @@ fn main() -> std::io::Result<()> {
                 stream.set_nodelay(true)?;
 
                 let stats = Arc::clone(&stats);
+                let commands = Arc::clone(&commands);
                 thread::spawn(move || {
                     let conn_id = CONNECTION_COUNT.fetch_add(1, Ordering::SeqCst);
                     let peer_addr = stream.peer_addr().unwrap();
                     println!("🔌 Nova conexão #{} de {}", conn_id, peer_addr);
 
-                    if let Err(e) = handle_connection(stream, conn_id, stats) {
+                    if let Err(e) = handle_connection(stream, conn_id, stats, commands) {
                         eprintln!("❌ Erro na conexão {}: {}", conn_id, e);
                     }
                 });
@@ -50,27 +58,34 @@ fn main() -> std::io::Result<()> {
 }
 
 /// Manipulador de conexões TCP
+///
+/// Cada requisição é um quadro: uma linha ou um conteúdo com prefixo de
+/// tamanho, conforme o primeiro byte da conexão. Quadros divididos entre
+/// segmentos são remontados, e vários quadros em um segmento são respondidos
+/// em ordem, no mesmo modo, com um único envio.
 fn handle_connection(
-    mut stream: TcpStream,
+    stream: TcpStream,
     conn_id: usize,
     stats: Arc<ServerStats>,
+    commands: Arc<CommandRegistry>,
 ) -> std::io::Result<()> {
-    let mut buffer = [0; 1024];
+    let mut reader = FrameReader::new(stream.try_clone()?).with_max_frame(MAX_REQUEST_SIZE);
+    let mut writer = BufWriter::new(stream);
     let mut request_history = Vec::new();
 
     loop {
-        match stream.read(&mut buffer) {
-            Ok(0) => {
-                println!("🚪 Conexão #{} encerrada pelo cliente", conn_id);
-                break;
-            }
-            Ok(bytes_read) => {
-                TOTAL_BYTES.fetch_add(bytes_read, Ordering::SeqCst);
+        let consumed = reader.bytes_read();
+        let result = reader.read_frame();
+        let bytes_read = reader.bytes_read() - consumed;
+        TOTAL_BYTES.fetch_add(bytes_read, Ordering::SeqCst);
+
+        let response = match result {
+            Ok(Some(frame)) => {
                 stats.record_request(bytes_read);
 
-                let request = String::from_utf8_lossy(&buffer[..bytes_read]);
+                let request = String::from_utf8_lossy(&frame);
                 request_history.push(request.to_string());
-                
+
                 println!(
                     "📥 Conexão #{}: {} bytes\n{}",
                     conn_id,
@@ -79,11 +94,20 @@ fn handle_connection(
                 );
 
                 // Processamento da requisição
-                let response = process_request(&request);
-                stream.write_all(response.as_bytes())?;
-                stream.flush()?;
+                process_request(&request, &commands, &stats)
             }
-            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+            Ok(None) => {
+                println!("🚪 Conexão #{} encerrada pelo cliente", conn_id);
+                break;
+            }
+            Err(e) if e.is_recoverable() => {
+                stats.record_request(bytes_read);
+                eprintln!("⚠️ Conexão #{}: requisição descartada: {}", conn_id, e);
+                e.to_string()
+            }
+            Err(FrameError::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+            {
                 println!("⏳ Conexão #{} inativa (timeout)", conn_id);
                 break;
             }
@@ -91,6 +115,13 @@ fn handle_connection(
                 eprintln!("⚠️ Erro na conexão #{}: {}", conn_id, e);
                 break;
             }
+        };
+
+        let mode = reader.mode().unwrap_or(FrameMode::Line);
+        write_frame(&mut writer, mode, response.as_bytes())?;
+        // Requisições já recebidas em sequência são respondidas juntas
+        if !reader.has_buffered_frame() {
+            writer.flush()?;
         }
     }
 
@@ -98,17 +129,18 @@ fn handle_connection(
     Ok(())
 }
 
-/// Processador de requisições TCP
-fn process_request(request: &str) -> String {
-    let request = request.trim();
-    match request {
-        "PING" => "PONG\n".to_string(),
-        "TIME" => {
-            let time = chrono::Local::now().format("%H:%M:%S").to_string();
-            format!("{}\n", time)
-        },
-        "STATS" => "Servidor em operação\n".to_string(),
-        _ => format!("Comando não reconhecido: '{}'\n", request)
+/// Processador de requisições TCP: executa o comando pelo registro compartilhado
+fn process_request(request: &str, commands: &CommandRegistry, stats: &ServerStats) -> String {
+    let line = match parse_command_line(request) {
+        Ok(line) => line,
+        Err(e) => return e.to_string(),
+    };
+    if commands.get(&line.name).is_some() {
+        stats.record_command(&line.name);
+    }
+    match commands.execute(&line, stats) {
+        Ok(output) => output,
+        Err(e) => e.to_string(),
     }
 }
 
@@ -137,6 +169,10 @@ impl ServerStats {
         self.bytes_received.fetch_add(bytes, Ordering::SeqCst);
     }
 
+    fn record_command(&self, name: &str) {
+        *self.endpoint_stats.lock().entry(name.to_string()).or_insert(0) += 1;
+    }
+
     fn requests_per_second(&self) -> f64 {
         let elapsed = self.start_time.elapsed().as_secs_f64();
         self.requests.load(Ordering::SeqCst) as f64 / elapsed
@@ -150,6 +186,14 @@ impl ServerStats {
 
 impl std::fmt::Display for ServerStats {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut commands: Vec<String> = self
+            .endpoint_stats
+            .lock()
+            .iter()
+            .map(|(name, count)| format!("{}={}", name, count))
+            .collect();
+        commands.sort();
+
         write!(
             f,
             "⏱️ Tempo de atividade: {:.2}s\n\
@@ -157,13 +201,15 @@ impl std::fmt::Display for ServerStats {
              📊 Bytes recebidos: {}\n\
              📡 Requisições: {}\n\
              🚀 Taxa de requisições: {:.2}/s\n\
-             💾 Taxa de dados: {:.2} KB/s",
+             💾 Taxa de dados: {:.2} KB/s\n\
+             🔧 Comandos: {}",
             self.start_time.elapsed().as_secs_f64(),
             CONNECTION_COUNT.load(Ordering::SeqCst),
             self.bytes_received.load(Ordering::SeqCst),
             self.requests.load(Ordering::SeqCst),
             self.requests_per_second(),
-            self.bytes_per_second() / 1024.0
+            self.bytes_per_second() / 1024.0,
+            if commands.is_empty() { "nenhum".to_string() } else { commands.join(", ") }
         )
     }
 }
@@ -0,0 +1,397 @@
+use std::{fmt, ops::Range};
+
+use super::super::ipHeader::{be16, be32, parse_ip_packet, require, IpHeader, PacketError, PROTO_TCP};
+
+// Bits de controle do TCP (byte 13, mais NS no byte 12)
+pub const FIN: u16 = 0x001;
+pub const SYN: u16 = 0x002;
+pub const RST: u16 = 0x004;
+pub const PSH: u16 = 0x008;
+pub const ACK: u16 = 0x010;
+pub const URG: u16 = 0x020;
+pub const ECE: u16 = 0x040;
+pub const CWR: u16 = 0x080;
+pub const NS: u16 = 0x100;
+
+const FLAG_NAMES: [(u16, &str); 9] = [
+    (NS, "NS"),
+    (CWR, "CWR"),
+    (ECE, "ECE"),
+    (URG, "URG"),
+    (ACK, "ACK"),
+    (PSH, "PSH"),
+    (RST, "RST"),
+    (SYN, "SYN"),
+    (FIN, "FIN"),
+];
+
+/// Opção do cabeçalho TCP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TcpOption {
+    EndOfList,
+    Nop,
+    Mss(u16),
+    WindowScale(u8),
+    SackPermitted,
+    Sack(Vec<(u32, u32)>), // Blocos (borda esquerda, borda direita)
+    Timestamps { value: u32, echo_reply: u32 },
+    Unknown { kind: u8, data: Vec<u8> },
+}
+
+impl fmt::Display for TcpOption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TcpOption::EndOfList => write!(f, "EOL"),
+            TcpOption::Nop => write!(f, "NOP"),
+            TcpOption::Mss(mss) => write!(f, "MSS {}", mss),
+            TcpOption::WindowScale(shift) => write!(f, "WS {} (x{})", shift, 1u32 << shift.min(&14)),
+            TcpOption::SackPermitted => write!(f, "SACK permitido"),
+            TcpOption::Sack(blocks) => {
+                let blocks: Vec<String> = blocks
+                    .iter()
+                    .map(|(left, right)| format!("{}-{}", left, right))
+                    .collect();
+                write!(f, "SACK {}", blocks.join(" "))
+            }
+            TcpOption::Timestamps { value, echo_reply } => {
+                write!(f, "TS val {} ecr {}", value, echo_reply)
+            }
+            TcpOption::Unknown { kind, data } => {
+                write!(f, "opção {} ({} bytes)", kind, data.len())
+            }
+        }
+    }
+}
+
+/// Cabeçalho TCP decodificado (RFC 9293)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub seq: u32,
+    pub ack: u32,
+    pub data_offset: u8, // Em palavras de 32 bits (5-15)
+    pub flags: u16,      // Combinação de FIN, SYN, ..., NS
+    pub window: u16,
+    pub checksum: u16,
+    pub urgent_pointer: u16,
+    pub options: Vec<TcpOption>,
+}
+
+impl TcpHeader {
+    /// Tamanho do cabeçalho em bytes, incluindo opções
+    pub fn header_len(&self) -> usize {
+        usize::from(self.data_offset) * 4
+    }
+
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+
+    /// Nomes das flags ligadas, da mais significativa para a menos
+    pub fn flag_names(&self) -> Vec<&'static str> {
+        FLAG_NAMES
+            .iter()
+            .filter(|(flag, _)| self.has_flag(*flag))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    pub fn mss(&self) -> Option<u16> {
+        self.options.iter().find_map(|option| match option {
+            TcpOption::Mss(mss) => Some(*mss),
+            _ => None,
+        })
+    }
+
+    pub fn window_scale(&self) -> Option<u8> {
+        self.options.iter().find_map(|option| match option {
+            TcpOption::WindowScale(shift) => Some(*shift),
+            _ => None,
+        })
+    }
+
+    /// Campos do cabeçalho e suas posições em bytes, para os dumps anotados
+    ///
+    /// Campos que dividem um byte (data offset e flags) aparecem juntos.
+    pub fn field_ranges(&self) -> Vec<(&'static str, Range<usize>)> {
+        let mut fields = vec![
+            ("Porta de origem", 0..2),
+            ("Porta de destino", 2..4),
+            ("Número de sequência", 4..8),
+            ("Número de confirmação", 8..12),
+            ("Data offset/flags", 12..14),
+            ("Janela", 14..16),
+            ("Checksum", 16..18),
+            ("Ponteiro urgente", 18..20),
+        ];
+        if self.header_len() > 20 {
+            fields.push(("Opções", 20..self.header_len()));
+        }
+        fields
+    }
+}
+
+impl fmt::Display for TcpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "   Porta de origem: {}", self.src_port)?;
+        writeln!(f, "   Porta de destino: {}", self.dst_port)?;
+        writeln!(f, "   Sequência: {}", self.seq)?;
+        writeln!(f, "   Confirmação: {}", self.ack)?;
+        writeln!(
+            f,
+            "   Data offset: {} ({} bytes)",
+            self.data_offset,
+            self.header_len()
+        )?;
+        writeln!(f, "   Flags: [{}] (0x{:03X})", self.flag_names().join(", "), self.flags)?;
+        writeln!(f, "   Janela: {}", self.window)?;
+        writeln!(f, "   Checksum: 0x{:04X}", self.checksum)?;
+        write!(f, "   Ponteiro urgente: {}", self.urgent_pointer)?;
+        if !self.options.is_empty() {
+            let options: Vec<String> = self.options.iter().map(|option| option.to_string()).collect();
+            write!(f, "\n   Opções: {}", options.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Pacote IP com segmento TCP decodificado
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TcpPacket<'a> {
+    pub ip: IpHeader,
+    pub tcp: TcpHeader,
+    pub header: &'a [u8],  // Bytes do cabeçalho TCP, incluindo opções
+    pub payload: &'a [u8], // Dados da aplicação
+}
+
+/// Decodifica um pacote IPv4/IPv6 bruto contendo um segmento TCP
+pub fn parse_tcp_packet(packet: &[u8]) -> Result<TcpPacket<'_>, PacketError> {
+    let (ip, segment) = parse_ip_packet(packet)?;
+    if ip.protocol() != PROTO_TCP {
+        return Err(PacketError::UnexpectedProtocol {
+            expected: PROTO_TCP,
+            found: ip.protocol(),
+        });
+    }
+    let tcp = parse_tcp_header(segment)?;
+    let (header, payload) = segment.split_at(tcp.header_len());
+    Ok(TcpPacket {
+        ip,
+        tcp,
+        header,
+        payload,
+    })
+}
+
+/// Decodifica um cabeçalho TCP no início de `segment`
+pub fn parse_tcp_header(segment: &[u8]) -> Result<TcpHeader, PacketError> {
+    require(segment, 20, "cabeçalho TCP")?;
+    let data_offset = segment[12] >> 4;
+    let header_len = usize::from(data_offset) * 4;
+    if header_len < 20 {
+        return Err(PacketError::InvalidHeaderLength("TCP", header_len));
+    }
+    require(segment, header_len, "cabeçalho TCP")?;
+
+    Ok(TcpHeader {
+        src_port: be16(segment, 0),
+        dst_port: be16(segment, 2),
+        seq: be32(segment, 4),
+        ack: be32(segment, 8),
+        data_offset,
+        flags: u16::from(segment[12] & 0x01) << 8 | u16::from(segment[13]),
+        window: be16(segment, 14),
+        checksum: be16(segment, 16),
+        urgent_pointer: be16(segment, 18),
+        options: parse_options(&segment[20..header_len]),
+    })
+}
+
+/// Decodifica as opções; uma opção com tamanho inconsistente encerra a
+/// leitura e o restante é guardado como `Unknown`
+fn parse_options(mut data: &[u8]) -> Vec<TcpOption> {
+    let mut options = Vec::new();
+    while let Some(&kind) = data.first() {
+        match kind {
+            0 => {
+                options.push(TcpOption::EndOfList);
+                break;
+            }
+            1 => {
+                options.push(TcpOption::Nop);
+                data = &data[1..];
+                continue;
+            }
+            _ => {}
+        }
+
+        let len = data.get(1).map_or(0, |&len| usize::from(len));
+        if len < 2 || len > data.len() {
+            options.push(TcpOption::Unknown {
+                kind,
+                data: data[1..].to_vec(),
+            });
+            break;
+        }
+        let body = &data[2..len];
+        options.push(match (kind, body.len()) {
+            (2, 2) => TcpOption::Mss(be16(body, 0)),
+            (3, 1) => TcpOption::WindowScale(body[0]),
+            (4, 0) => TcpOption::SackPermitted,
+            (5, n) if n % 8 == 0 => TcpOption::Sack(
+                body.chunks(8)
+                    .map(|block| (be32(block, 0), be32(block, 4)))
+                    .collect(),
+            ),
+            (8, 8) => TcpOption::Timestamps {
+                value: be32(body, 0),
+                echo_reply: be32(body, 4),
+            },
+            _ => TcpOption::Unknown {
+                kind,
+                data: body.to_vec(),
+            },
+        });
+        data = &data[len..];
+    }
+    options
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::super::ipHeader::{transport_checksum, PROTO_UDP};
+
+    // IPv4 192.168.0.1 -> 10.0.0.2, IHL 6, total 83 bytes
+    const IPV4: [u8; 24] = [
+        0x46, 0x00, 0x00, 0x53, 0x12, 0x34, 0x40, 0x00, 0x40, 0x06, 0xc8, 0xc1, 0xc0, 0xa8, 0x00, 0x01,
+        0x0a, 0x00, 0x00, 0x02, 0x94, 0x04, 0x00, 0x00,
+    ];
+
+    // SYN/ACK 40000 -> 443, data offset 14, checksum 0xd4d6, dados "oi!"
+    const SEGMENTO: [u8; 59] = [
+        0x9c, 0x40, 0x01, 0xbb, 0x00, 0x00, 0x03, 0xe8, 0x00, 0x00, 0x00, 0x00, 0xe0, 0x12, 0xff, 0xff,
+        0xd4, 0xd6, 0x00, 0x00, //
+        0x02, 0x04, 0x05, 0xb4, // MSS 1460
+        0x01, // NOP
+        0x03, 0x03, 0x07, // WS 7
+        0x04, 0x02, // SACK permitido
+        0x08, 0x0a, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, // TS 1/2
+        0x01, 0x01, // NOP NOP
+        0x05, 0x0a, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x20, 0x00, // SACK 4096-8192
+        0x00, 0x00, 0x00, 0x00, // EOL e preenchimento
+        b'o', b'i', b'!',
+    ];
+
+    fn packet() -> Vec<u8> {
+        [&IPV4[..], &SEGMENTO].concat()
+    }
+
+    /// Cabeçalho fixo com o data offset informado, seguido de `options`
+    fn header_with(data_offset: u8, options: &[u8]) -> Vec<u8> {
+        let mut segment = SEGMENTO[..20].to_vec();
+        segment[12] = data_offset << 4;
+        segment.extend_from_slice(options);
+        segment
+    }
+
+    #[test]
+    fn pacote_com_todas_as_opcoes() {
+        let packet = packet();
+        let parsed = parse_tcp_packet(&packet).unwrap();
+        let tcp = &parsed.tcp;
+
+        assert_eq!((tcp.src_port, tcp.dst_port, tcp.seq, tcp.ack), (40000, 443, 1000, 0));
+        assert_eq!((tcp.data_offset, tcp.header_len(), tcp.window), (14, 56, 65535));
+        assert_eq!(tcp.flag_names(), ["ACK", "SYN"]);
+        assert!(tcp.has_flag(SYN) && !tcp.has_flag(FIN));
+        assert_eq!((tcp.mss(), tcp.window_scale()), (Some(1460), Some(7)));
+        assert_eq!(
+            tcp.options,
+            vec![
+                TcpOption::Mss(1460),
+                TcpOption::Nop,
+                TcpOption::WindowScale(7),
+                TcpOption::SackPermitted,
+                TcpOption::Timestamps { value: 1, echo_reply: 2 },
+                TcpOption::Nop,
+                TcpOption::Nop,
+                TcpOption::Sack(vec![(4096, 8192)]),
+                TcpOption::EndOfList,
+            ]
+        );
+        assert_eq!(parsed.header, &SEGMENTO[..56]);
+        assert_eq!(parsed.payload, b"oi!");
+        assert_eq!(tcp.field_ranges().last(), Some(&("Opções", 20..56)));
+    }
+
+    #[test]
+    fn opcoes_isoladas() {
+        let cases: [(&[u8], Vec<TcpOption>); 5] = [
+            (
+                &[0x01, 0x01, 0x01, 0x00],
+                vec![TcpOption::Nop, TcpOption::Nop, TcpOption::Nop, TcpOption::EndOfList],
+            ),
+            (
+                &[0x05, 0x12, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0, 0],
+                vec![TcpOption::Sack(vec![(1, 2), (3, 4)]), TcpOption::EndOfList],
+            ),
+            // Tamanho que não bate com o tipo vira Unknown e a leitura segue
+            (&[0x02, 0x03, 0x05, 0x01], vec![TcpOption::Unknown { kind: 2, data: vec![5] }, TcpOption::Nop]),
+            // Tamanho além do fim encerra a leitura
+            (&[0x01, 0x08, 0x09, 0x01], vec![TcpOption::Nop, TcpOption::Unknown { kind: 8, data: vec![9, 1] }]),
+            (&[0x1e, 0x01, 0x00, 0x00], vec![TcpOption::Unknown { kind: 30, data: vec![1, 0, 0] }]),
+        ];
+        for (options, expected) in cases {
+            let data_offset = (5 + options.len() / 4) as u8;
+            let header = parse_tcp_header(&header_with(data_offset, options)).unwrap();
+            assert_eq!(header.options, expected, "{:02x?}", options);
+        }
+    }
+
+    #[test]
+    fn data_offset_invalido_ou_truncado() {
+        assert_eq!(
+            parse_tcp_header(&header_with(4, &[])),
+            Err(PacketError::InvalidHeaderLength("TCP", 16))
+        );
+        assert_eq!(
+            parse_tcp_header(&header_with(15, &[0; 8])),
+            Err(PacketError::Truncated { layer: "cabeçalho TCP", needed: 60, available: 28 })
+        );
+        assert_eq!(
+            parse_tcp_header(&SEGMENTO[..19]),
+            Err(PacketError::Truncated { layer: "cabeçalho TCP", needed: 20, available: 19 })
+        );
+        assert_eq!(
+            parse_tcp_header(&SEGMENTO[..40]),
+            Err(PacketError::Truncated { layer: "cabeçalho TCP", needed: 56, available: 40 })
+        );
+
+        // Datagrama UDP não é aceito como TCP
+        let mut packet = packet();
+        packet[9] = PROTO_UDP;
+        assert_eq!(
+            parse_tcp_packet(&packet),
+            Err(PacketError::UnexpectedProtocol { expected: PROTO_TCP, found: PROTO_UDP })
+        );
+    }
+
+    #[test]
+    fn checksum_do_segmento() {
+        let packet = packet();
+        let parsed = parse_tcp_packet(&packet).unwrap();
+        let segment = &packet[24..];
+        assert_eq!(parsed.tcp.checksum, 0xd4d6);
+        assert_eq!(transport_checksum(&parsed.ip, PROTO_TCP, segment), 0);
+
+        let mut corrupted = segment.to_vec();
+        corrupted[56] = b'O';
+        assert_ne!(transport_checksum(&parsed.ip, PROTO_TCP, &corrupted), 0);
+
+        let mut zeroed = segment.to_vec();
+        zeroed[16..18].fill(0);
+        assert_eq!(transport_checksum(&parsed.ip, PROTO_TCP, &zeroed), 0xd4d6);
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use super::wireFormat::{decode, Fragment, UdpMessage, WireError, DEFAULT_MAX_MESSAGE};
+
+/// Menor pedaço aceito em um fragmento
+const MIN_FRAGMENT_DATA: usize = 1;
+
+/// Mensagem ainda incompleta
+struct Pending {
+    count: u16,
+    total_len: u32,
+    chunks: BTreeMap<u16, Vec<u8>>, // Por índice; só os fragmentos que já chegaram
+    bytes: usize,                   // Soma dos pedaços recebidos
+    started: Instant,
+}
+
+/// Remonta mensagens fragmentadas, por remetente e id de requisição
+pub struct Reassembler {
+    pending: HashMap<(SocketAddr, u32), Pending>,
+    max_message_size: usize,
+    timeout: Duration,  // Mensagens incompletas são descartadas após este tempo
+    max_pending: usize, // Limite de mensagens incompletas simultâneas
+}
+
+impl Default for Reassembler {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_MESSAGE)
+    }
+}
+
+impl Reassembler {
+    pub fn new(max_message_size: usize) -> Self {
+        Self {
+            pending: HashMap::new(),
+            max_message_size,
+            timeout: Duration::from_secs(10),
+            max_pending: 256,
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Mensagens incompletas no momento
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Adiciona um fragmento; retorna a mensagem quando todos chegaram
+    ///
+    /// Fragmentos repetidos são ignorados. Uma mensagem maior que
+    /// `max_message_size` ou com fragmentos incoerentes é descartada com erro.
+    /// Um fragmento com quantidade zero ou maior que o tamanho declarado é
+    /// recusado. Os pedaços são guardados à medida que chegam, então a memória
+    /// de uma mensagem cresce com os dados recebidos, não com a quantidade declarada.
+    pub fn push(
+        &mut self,
+        from: SocketAddr,
+        fragment: &Fragment,
+        now: Instant,
+    ) -> Result<Option<UdpMessage>, WireError> {
+        let key = (from, fragment.request_id);
+        let total_len = fragment.total_len as usize;
+        if total_len > self.max_message_size {
+            self.pending.remove(&key);
+            return Err(WireError::MessageTooLarge {
+                size: total_len,
+                max: self.max_message_size,
+            });
+        }
+        // Cada fragmento traz ao menos um byte, então a quantidade não passa do
+        // tamanho declarado (já limitado acima)
+        let max_count = total_len.div_ceil(MIN_FRAGMENT_DATA);
+        if fragment.count == 0 || usize::from(fragment.count) > max_count {
+            self.pending.remove(&key);
+            return Err(WireError::InvalidFragment("quantidade de fragmentos incoerente com o tamanho"));
+        }
+        if fragment.data.len() < MIN_FRAGMENT_DATA {
+            self.pending.remove(&key);
+            return Err(WireError::InvalidFragment("fragmento sem dados"));
+        }
+        if fragment.index >= fragment.count {
+            self.pending.remove(&key);
+            return Err(WireError::InvalidFragment("índice fora da quantidade de fragmentos"));
+        }
+
+        if !self.pending.contains_key(&key) {
+            self.make_room(now);
+            self.pending.insert(
+                key,
+                Pending {
+                    count: fragment.count,
+                    total_len: fragment.total_len,
+                    chunks: BTreeMap::new(),
+                    bytes: 0,
+                    started: now,
+                },
+            );
+        }
+        let pending = self.pending.get_mut(&key).expect("inserido acima");
+        if pending.count != fragment.count || pending.total_len != fragment.total_len {
+            self.pending.remove(&key);
+            return Err(WireError::InvalidFragment("quantidade ou tamanho divergente entre fragmentos"));
+        }
+
+        if pending.chunks.contains_key(&fragment.index) {
+            return Ok(None);
+        }
+        pending.bytes += fragment.data.len();
+        if pending.bytes > total_len {
+            self.pending.remove(&key);
+            return Err(WireError::InvalidFragment("fragmentos excedem o tamanho declarado"));
+        }
+        pending.chunks.insert(fragment.index, fragment.data.to_vec());
+        if pending.chunks.len() < usize::from(pending.count) {
+            return Ok(None);
+        }
+
+        let pending = self.pending.remove(&key).expect("presente acima");
+        if pending.bytes != total_len {
+            return Err(WireError::Truncated {
+                field: "mensagem remontada",
+                needed: total_len,
+                available: pending.bytes,
+            });
+        }
+        let encoded: Vec<u8> = pending.chunks.into_values().flatten().collect();
+        let message = decode(&encoded)?;
+        if message.request_id != Some(fragment.request_id) || message.verb != fragment.verb {
+            return Err(WireError::InvalidFragment("mensagem remontada não corresponde aos fragmentos"));
+        }
+        Ok(Some(message))
+    }
+
+    /// Descarta mensagens incompletas há mais de `timeout`; retorna quantas
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let before = self.pending.len();
+        let timeout = self.timeout;
+        self.pending
+            .retain(|_, pending| now.saturating_duration_since(pending.started) < timeout);
+        before - self.pending.len()
+    }
+
+    /// Abre espaço para uma nova mensagem: expira as velhas e, se preciso, a mais antiga
+    fn make_room(&mut self, now: Instant) {
+        if self.pending.len() < self.max_pending {
+            return;
+        }
+        self.expire(now);
+        if self.pending.len() >= self.max_pending {
+            let oldest = self
+                .pending
+                .iter()
+                .min_by_key(|(_, pending)| pending.started)
+                .map(|(key, _)| *key);
+            if let Some(oldest) = oldest {
+                self.pending.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::wireFormat::{decode_datagram, encode_fragments, Datagram, Verb};
+
+    fn client(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn message(request_id: u32, body_len: usize) -> UdpMessage {
+        let body: Vec<u8> = (0..body_len).map(|i| (i % 251) as u8).collect();
+        UdpMessage::post("localhost", "/upload", &body).with_request_id(request_id)
+    }
+
+    /// Datagramas de `message` em pedaços de até 200 bytes
+    fn datagrams(message: &UdpMessage) -> Vec<Vec<u8>> {
+        encode_fragments(message, 200, DEFAULT_MAX_MESSAGE).unwrap()
+    }
+
+    fn fragment(datagram: &[u8]) -> Fragment<'_> {
+        match decode_datagram(datagram).unwrap() {
+            Datagram::Fragment(fragment) => fragment,
+            Datagram::Message(_) => panic!("esperado fragmento"),
+        }
+    }
+
+    fn forged(count: u16, total_len: u32, data: &[u8]) -> Fragment<'_> {
+        Fragment {
+            request_id: 9,
+            verb: Verb::Post,
+            index: 0,
+            count,
+            total_len,
+            data,
+        }
+    }
+
+    #[test]
+    fn remonta_fora_de_ordem_e_ignora_repetidos() {
+        let original = message(7, 1000);
+        let datagrams = datagrams(&original);
+        assert!(datagrams.len() > 3);
+
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        let (last, rest) = datagrams.split_first().unwrap();
+        for datagram in rest.iter().rev().chain(rest.iter().take(2)) {
+            assert_eq!(reassembler.push(client(1), &fragment(datagram), now), Ok(None));
+        }
+        assert_eq!(reassembler.pending(), 1);
+        assert_eq!(reassembler.push(client(1), &fragment(last), now), Ok(Some(original)));
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn remetentes_diferentes_nao_se_misturam() {
+        let datagrams = datagrams(&message(7, 500));
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        for datagram in &datagrams[1..] {
+            reassembler.push(client(1), &fragment(datagram), now).unwrap();
+        }
+        assert_eq!(reassembler.push(client(2), &fragment(&datagrams[0]), now), Ok(None));
+        assert_eq!(reassembler.pending(), 2);
+    }
+
+    #[test]
+    fn quantidade_ou_tamanho_divergente_descarta_a_mensagem() {
+        let datagrams = datagrams(&message(7, 500));
+        let now = Instant::now();
+
+        for change in [|f: &mut Fragment| f.count += 1, |f: &mut Fragment| f.total_len -= 1] {
+            let mut reassembler = Reassembler::default();
+            reassembler.push(client(1), &fragment(&datagrams[0]), now).unwrap();
+            let mut divergent = fragment(&datagrams[1]);
+            change(&mut divergent);
+            assert_eq!(
+                reassembler.push(client(1), &divergent, now),
+                Err(WireError::InvalidFragment("quantidade ou tamanho divergente entre fragmentos"))
+            );
+            assert_eq!(reassembler.pending(), 0);
+        }
+    }
+
+    #[test]
+    fn quantidade_incoerente_e_recusada() {
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        let incoherent = Err(WireError::InvalidFragment("quantidade de fragmentos incoerente com o tamanho"));
+
+        // 65535 fragmentos para uma mensagem de 10 bytes, ou nenhum fragmento
+        assert_eq!(reassembler.push(client(1), &forged(u16::MAX, 10, b"x"), now), incoherent);
+        assert_eq!(reassembler.push(client(1), &forged(0, 10, b"x"), now), incoherent);
+        assert_eq!(
+            reassembler.push(client(1), &forged(2, 10, b""), now),
+            Err(WireError::InvalidFragment("fragmento sem dados"))
+        );
+        assert_eq!(
+            reassembler.push(client(1), &forged(2, u32::MAX, b"x"), now),
+            Err(WireError::MessageTooLarge { size: u32::MAX as usize, max: DEFAULT_MAX_MESSAGE })
+        );
+        assert_eq!(reassembler.pending(), 0);
+
+        // O limite é o tamanho declarado: um byte por fragmento ainda é aceito
+        assert_eq!(reassembler.push(client(1), &forged(10, 10, b"x"), now), Ok(None));
+        assert_eq!(reassembler.pending(), 1);
+    }
+
+    #[test]
+    fn quantidade_grande_guarda_so_os_fragmentos_recebidos() {
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        reassembler.push(client(1), &forged(u16::MAX, u32::from(u16::MAX), b"x"), now).unwrap();
+
+        let pending = &reassembler.pending[&(client(1), 9)];
+        assert_eq!(pending.chunks.len(), 1);
+        assert_eq!(pending.bytes, 1);
+    }
+
+    #[test]
+    fn mensagens_incompletas_expiram() {
+        let datagrams = datagrams(&message(7, 500));
+        let mut reassembler = Reassembler::default().with_timeout(Duration::from_secs(5));
+        let start = Instant::now();
+        reassembler.push(client(1), &fragment(&datagrams[0]), start).unwrap();
+
+        assert_eq!(reassembler.expire(start + Duration::from_secs(4)), 0);
+        assert_eq!(reassembler.expire(start + Duration::from_secs(5)), 1);
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn limite_de_pendentes_descarta_a_mais_antiga() {
+        let mut reassembler = Reassembler::default().with_max_pending(2);
+        let start = Instant::now();
+        let messages: Vec<Vec<Vec<u8>>> = (1..=3).map(|id| datagrams(&message(id, 500))).collect();
+
+        for (offset, datagrams) in messages.iter().enumerate() {
+            let now = start + Duration::from_millis(offset as u64);
+            reassembler.push(client(1), &fragment(&datagrams[0]), now).unwrap();
+        }
+        assert_eq!(reassembler.pending(), 2);
+
+        // A mensagem 1 foi descartada: seus fragmentos restantes recomeçam do zero
+        let later = start + Duration::from_millis(10);
+        for datagram in &messages[0][1..] {
+            assert_eq!(reassembler.push(client(1), &fragment(datagram), later), Ok(None));
+        }
+        // A mensagem 3, mais recente, ainda completa
+        let mut completed = None;
+        for datagram in &messages[2][1..] {
+            completed = reassembler.push(client(1), &fragment(datagram), later).unwrap();
+        }
+        assert_eq!(completed.and_then(|message| message.request_id), Some(3));
+    }
+}
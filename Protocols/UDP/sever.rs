@@ -1,10 +1,20 @@
 use std::{
+    collections::{HashMap, VecDeque},
     net::{SocketAddr, UdpSocket},
     time::{Duration, Instant},
     io,
     sync::atomic::{AtomicUsize, Ordering},
 };
 
+use super::super::commands::CommandRegistry;
+use super::reassembly::Reassembler;
+use super::wireFormat::{
+    decode_datagram, encode, is_wire_format, Datagram, UdpMessage, Verb, DEFAULT_MAX_MESSAGE,
+};
+
+/// Maior mensagem aceita após remontar os fragmentos
+const MAX_MESSAGE_SIZE: usize = DEFAULT_MAX_MESSAGE;
+
 // Contador atômico de pacotes
 static PACKET_COUNT: AtomicUsize = AtomicUsize::new(0);
 
@@ -18,14 +28,20 @@ fn main() -> io::Result<()> {
     println!("🦀 Servidor UDP escutando em 127.0.0.1:8080");
     println!("📊 Modo: Bloqueante | Timeout: 5s");
 
-    let mut buffer = [0u8; 1024]; // Buffer de 1KB
-    let mut stats = ServerStats::new();
+    // Buffer de 64KB: comporta qualquer datagrama UDP, nada é truncado
+    let mut buffer = vec![0u8; 65536];
+    let mut state = ServerState {
+        stats: ServerStats::new(),
+        replies: ReplyCache::new(1024, Duration::from_secs(30)),
+        fragments: Reassembler::new(MAX_MESSAGE_SIZE),
+        commands: CommandRegistry::with_builtins(),
+    };
 
     loop {
         match socket.recv_from(&mut buffer) {
             Ok((bytes_read, addr)) => {
                 PACKET_COUNT.fetch_add(1, Ordering::SeqCst);
-                stats.update(bytes_read);
+                state.stats.update(bytes_read);
                 
                 let received_data = &buffer[..bytes_read];
                 println!("📥 Pacote #{} de {}: {} bytes",
@@ -35,13 +51,17 @@ fn main() -> io::Result<()> {
                 );
 
                 // Processamento do pacote
-                if let Err(e) = handle_packet(&socket, received_data, addr) {
+                if let Err(e) = handle_packet(&socket, received_data, addr, &mut state) {
                     eprintln!("❌ Erro ao processar pacote: {}", e);
                 }
             }
             Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
                 // Timeout ocorreu
                 println!("⏳ Aguardando pacotes...");
+                let expired = state.fragments.expire(Instant::now());
+                if expired > 0 {
+                    println!("🧹 {} mensagens fragmentadas incompletas descartadas", expired);
+                }
                 continue;
             }
             Err(e) => {
@@ -51,26 +71,139 @@ fn main() -> io::Result<()> {
         }
     }
 
-    println!("📊 Estatísticas finais:\n{}", stats);
+    println!("📊 Estatísticas finais:\n{}", state.stats);
     Ok(())
 }
 
+/// Estado do servidor entre pacotes
+struct ServerState {
+    stats: ServerStats,
+    replies: ReplyCache,
+    fragments: Reassembler,
+    commands: CommandRegistry,
+}
+
+impl ServerState {
+    /// Executa uma linha de comando; erros viram a própria resposta
+    fn respond(&self, line: &str) -> Vec<u8> {
+        match self.commands.dispatch(line, &self.stats) {
+            Ok(output) => output.into_bytes(),
+            Err(e) => e.to_string().into_bytes(),
+        }
+    }
+}
+
 /// Manipulador de pacotes UDP
-fn handle_packet(socket: &UdpSocket, data: &[u8], addr: SocketAddr) -> io::Result<()> {
-    // Simples eco com resposta personalizada
-    let response = match data {
-        b"PING" => b"PONG",
-        b"TIME" => {
-            let time = chrono::Local::now().format("%H:%M:%S").to_string();
-            time.as_bytes()
-        },
-        _ => b"Oi, UDP! (resposta padrao)"
+///
+/// Requisições no formato binário com id recebem a resposta no mesmo formato,
+/// com o mesmo id; uma retransmissão já atendida recebe a resposta guardada,
+/// sem processar de novo. Fragmentos são remontados antes do processamento;
+/// uma mensagem grande demais ou malformada recebe `ERROR`. Datagramas crus
+/// são linhas de comando e recebem a resposta crua.
+fn handle_packet(
+    socket: &UdpSocket,
+    data: &[u8],
+    addr: SocketAddr,
+    state: &mut ServerState,
+) -> io::Result<()> {
+    if !is_wire_format(data) {
+        socket.send_to(&state.respond(&String::from_utf8_lossy(data)), addr)?;
+        return Ok(());
+    }
+
+    let request = match decode_datagram(data)? {
+        Datagram::Message(message) => message,
+        Datagram::Fragment(fragment) => {
+            let request_id = fragment.request_id;
+            if let Some(reply) = state.replies.get(addr, request_id) {
+                // Mensagem já atendida retransmitida: responde só no último fragmento
+                if fragment.index + 1 == fragment.count {
+                    socket.send_to(reply, addr)?;
+                }
+                return Ok(());
+            }
+            match state.fragments.push(addr, &fragment, Instant::now()) {
+                Ok(Some(message)) => {
+                    println!("🧩 Requisição #{} remontada: {} fragmentos", request_id, fragment.count);
+                    message
+                }
+                Ok(None) => return Ok(()),
+                Err(e) => {
+                    eprintln!("❌ Requisição #{} de {} recusada: {}", request_id, addr, e);
+                    let reply = encode(&UdpMessage::error(request_id, &e.to_string()))?;
+                    socket.send_to(&reply, addr)?;
+                    state.replies.insert(addr, request_id, reply);
+                    return Ok(());
+                }
+            }
+        }
     };
+    let request_id = match (request.verb, request.request_id) {
+        (Verb::Get | Verb::Post, Some(id)) => id,
+        _ => {
+            // Sem id não há como correlacionar: responde sem guardar
+            socket.send_to(&state.respond(request.path.trim_start_matches('/')), addr)?;
+            return Ok(());
+        }
+    };
+
+    if let Some(reply) = state.replies.get(addr, request_id) {
+        println!("🔁 Requisição #{} repetida por {}: reenviando resposta", request_id, addr);
+        socket.send_to(reply, addr)?;
+        return Ok(());
+    }
 
-    socket.send_to(response, addr)?;
+    // No GET o path é a linha de comando: "/ping" equivale ao datagrama cru "PING"
+    let body = match request.verb {
+        Verb::Post => {
+            format!("POST {}: {} bytes recebidos", request.path, request.body.len()).into_bytes()
+        }
+        _ => state.respond(request.path.trim_start_matches('/')),
+    };
+    let reply = encode(&UdpMessage::reply(request_id, &body))?;
+    socket.send_to(&reply, addr)?;
+    state.replies.insert(addr, request_id, reply);
     Ok(())
 }
 
+/// Respostas recentes por (cliente, id), reenviadas quando a requisição é retransmitida
+struct ReplyCache {
+    entries: HashMap<(SocketAddr, u32), (Instant, Vec<u8>)>,
+    order: VecDeque<(SocketAddr, u32)>, // Ordem de inserção, para descartar as mais antigas
+    capacity: usize,
+    ttl: Duration,
+}
+
+impl ReplyCache {
+    fn new(capacity: usize, ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+            ttl,
+        }
+    }
+
+    fn get(&self, addr: SocketAddr, request_id: u32) -> Option<&[u8]> {
+        self.entries
+            .get(&(addr, request_id))
+            .filter(|(stored, _)| stored.elapsed() < self.ttl)
+            .map(|(_, reply)| reply.as_slice())
+    }
+
+    fn insert(&mut self, addr: SocketAddr, request_id: u32, reply: Vec<u8>) {
+        let key = (addr, request_id);
+        if self.entries.insert(key, (Instant::now(), reply)).is_none() {
+            self.order.push_back(key);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
+}
+
 /// Estrutura para estatísticas do servidor
 struct ServerStats {
     start_time: Instant,
@@ -0,0 +1,345 @@
+use std::{
+    collections::VecDeque,
+    io,
+    net::{SocketAddr, UdpSocket},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use super::wireFormat::{
+    decode, encode_fragments, is_wire_format, UdpMessage, Verb, DEFAULT_MAX_DATAGRAM,
+    DEFAULT_MAX_MESSAGE,
+};
+
+/// Ids respondidos lembrados para reconhecer respostas repetidas
+const ANSWERED_HISTORY: usize = 1024;
+
+/// Tentativas e espera entre retransmissões
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub attempts: u32,             // Envios no total, incluindo o primeiro
+    pub initial_timeout: Duration, // Espera pela resposta após o primeiro envio
+    pub multiplier: u32,           // Fator aplicado à espera a cada retransmissão
+    pub max_timeout: Duration,     // Teto da espera por tentativa
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_timeout: Duration::from_millis(500),
+            multiplier: 2,
+            max_timeout: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(attempts: u32, initial_timeout: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            initial_timeout,
+            ..Self::default()
+        }
+    }
+
+    /// Um único envio, sem retransmissão
+    pub fn once(timeout: Duration) -> Self {
+        Self::new(1, timeout)
+    }
+
+    pub fn with_multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier.max(1);
+        self
+    }
+
+    pub fn with_max_timeout(mut self, max_timeout: Duration) -> Self {
+        self.max_timeout = max_timeout;
+        self
+    }
+
+    /// Espera da tentativa `attempt` (a partir de 0): backoff exponencial com teto
+    pub fn timeout_for(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(attempt);
+        self.initial_timeout
+            .saturating_mul(factor)
+            .min(self.max_timeout)
+            .max(Duration::from_millis(1))
+    }
+}
+
+/// Resposta correlacionada a uma requisição
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpReply {
+    pub request_id: u32,
+    pub message: UdpMessage,
+    pub from: SocketAddr,
+    pub attempts: u32,     // Envios feitos até a resposta chegar
+    pub elapsed: Duration, // Desde o primeiro envio
+    pub duplicates: usize, // Respostas repetidas de requisições já atendidas, descartadas
+    pub stray: usize,      // Sem correlação (outro remetente, id ou formato), descartados
+}
+
+/// Cliente UDP com ids de requisição, retransmissão e descarte de duplicatas
+pub struct UdpClient {
+    socket: UdpSocket,
+    server: SocketAddr,
+    policy: RetryPolicy,
+    next_id: u32,
+    answered: VecDeque<u32>, // Ids já respondidos: respostas tardias são duplicatas
+    max_datagram: usize,     // Mensagens maiores são enviadas em fragmentos
+    max_message_size: usize, // Mensagens maiores são recusadas antes do envio
+}
+
+impl UdpClient {
+    pub fn connect(server_addr: &str, policy: RetryPolicy) -> io::Result<Self> {
+        let server: SocketAddr = server_addr
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let bind = if server.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+        // Ids começam em um valor variável para não colidir com execuções anteriores
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.subsec_nanos())
+            .unwrap_or_default();
+        Ok(Self {
+            socket: UdpSocket::bind(bind)?,
+            server,
+            policy,
+            next_id: seed,
+            answered: VecDeque::with_capacity(ANSWERED_HISTORY),
+            max_datagram: DEFAULT_MAX_DATAGRAM,
+            max_message_size: DEFAULT_MAX_MESSAGE,
+        })
+    }
+
+    pub fn with_max_datagram(mut self, max_datagram: usize) -> Self {
+        self.max_datagram = max_datagram;
+        self
+    }
+
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    pub fn server(&self) -> SocketAddr {
+        self.server
+    }
+
+    pub fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Envia `message` com um novo id e espera a resposta correspondente
+    ///
+    /// Retransmite a mesma requisição (mesmo id) a cada espera esgotada,
+    /// seguindo a `RetryPolicy`; mensagens maiores que `max_datagram` vão em
+    /// fragmentos, todos reenviados a cada tentativa. Falha com `TimedOut` se
+    /// nenhuma tentativa for respondida e com `InvalidData` se o servidor
+    /// recusar a requisição.
+    pub fn request(&mut self, message: UdpMessage) -> io::Result<UdpReply> {
+        let request_id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let packets = encode_fragments(
+            &message.with_request_id(request_id),
+            self.max_datagram,
+            self.max_message_size,
+        )?;
+
+        let start = Instant::now();
+        let mut duplicates = 0;
+        let mut stray = 0;
+        let mut buf = [0u8; 65536];
+
+        for attempt in 0..self.policy.attempts {
+            for packet in &packets {
+                self.socket.send_to(packet, self.server)?;
+            }
+            let deadline = Instant::now() + self.policy.timeout_for(attempt);
+
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                self.socket.set_read_timeout(Some(remaining))?;
+                let (len, from) = match self.socket.recv_from(&mut buf) {
+                    Ok(received) => received,
+                    Err(e) if is_timeout(&e) => break,
+                    Err(e) => return Err(e),
+                };
+
+                let reply = match (from == self.server, is_wire_format(&buf[..len])) {
+                    (true, true) => decode(&buf[..len]).ok(),
+                    _ => None,
+                };
+                match reply.filter(|reply| matches!(reply.verb, Verb::Reply | Verb::Error)) {
+                    Some(reply) if reply.request_id == Some(request_id) => {
+                        if self.answered.len() == ANSWERED_HISTORY {
+                            self.answered.pop_front();
+                        }
+                        self.answered.push_back(request_id);
+                        if reply.verb == Verb::Error {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "servidor recusou a requisição #{}: {}",
+                                    request_id,
+                                    String::from_utf8_lossy(&reply.body)
+                                ),
+                            ));
+                        }
+                        return Ok(UdpReply {
+                            request_id,
+                            message: reply,
+                            from,
+                            attempts: attempt + 1,
+                            elapsed: start.elapsed(),
+                            duplicates,
+                            stray,
+                        });
+                    }
+                    Some(reply) if reply.request_id.is_some_and(|id| self.is_answered(id)) => {
+                        duplicates += 1;
+                    }
+                    _ => stray += 1,
+                }
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::TimedOut,
+            format!(
+                "requisição #{} sem resposta de {} após {} tentativas",
+                request_id, self.server, self.policy.attempts
+            ),
+        ))
+    }
+
+    fn is_answered(&self, request_id: u32) -> bool {
+        self.answered.contains(&request_id)
+    }
+}
+
+/// Espera esgotada: `WouldBlock` no Unix, `TimedOut` no Windows
+fn is_timeout(e: &io::Error) -> bool {
+    matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::wireFormat::encode;
+    use std::thread;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    /// Servidor de teste em loopback; `script` recebe o socket e roda em outra thread
+    fn fake_server(
+        script: impl FnOnce(UdpSocket) + Send + 'static,
+    ) -> (SocketAddr, thread::JoinHandle<()>) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let addr = socket.local_addr().unwrap();
+        (addr, thread::spawn(move || script(socket)))
+    }
+
+    /// Recebe uma requisição e retorna seu id e o remetente
+    fn receive(socket: &UdpSocket) -> (u32, SocketAddr) {
+        let mut buf = [0u8; 2048];
+        let (len, from) = socket.recv_from(&mut buf).unwrap();
+        (decode(&buf[..len]).unwrap().request_id.unwrap(), from)
+    }
+
+    fn send(socket: &UdpSocket, message: &UdpMessage, to: SocketAddr) {
+        socket.send_to(&encode(message).unwrap(), to).unwrap();
+    }
+
+    #[test]
+    fn timeout_for_cresce_ate_o_teto() {
+        let policy = RetryPolicy::default();
+        let waits: Vec<Duration> = (0..6).map(|attempt| policy.timeout_for(attempt)).collect();
+        assert_eq!(waits, [ms(500), ms(1000), ms(2000), ms(4000), ms(8000), ms(8000)]);
+        // Sem overflow em tentativas altas
+        assert_eq!(policy.timeout_for(u32::MAX), ms(8000));
+
+        let policy = RetryPolicy::new(3, ms(300)).with_multiplier(3).with_max_timeout(ms(2000));
+        let waits: Vec<Duration> = (0..3).map(|attempt| policy.timeout_for(attempt)).collect();
+        assert_eq!(waits, [ms(300), ms(900), ms(2000)]);
+    }
+
+    #[test]
+    fn timeout_for_com_piso_e_multiplicador_1() {
+        assert_eq!(RetryPolicy::once(Duration::ZERO).timeout_for(0), ms(1));
+        assert_eq!(RetryPolicy::new(3, ms(2)).with_max_timeout(Duration::ZERO).timeout_for(2), ms(1));
+
+        for multiplier in [0, 1] {
+            let policy = RetryPolicy::new(5, ms(250)).with_multiplier(multiplier);
+            assert_eq!(policy.multiplier, 1);
+            assert!((0..5).all(|attempt| policy.timeout_for(attempt) == ms(250)));
+        }
+        assert_eq!(RetryPolicy::new(0, ms(10)).attempts, 1);
+    }
+
+    #[test]
+    fn retransmite_e_descarta_respostas_sem_correlacao() {
+        let (addr, server) = fake_server(|socket| {
+            // Primeira requisição: atendida normalmente
+            let (answered, client) = receive(&socket);
+            send(&socket, &UdpMessage::reply(answered, b"primeira"), client);
+
+            // Segunda: o primeiro envio se perde; a retransmissão recebe um id
+            // errado, uma repetição da resposta anterior, lixo e só então a resposta
+            let (id, _) = receive(&socket);
+            let (retry, client) = receive(&socket);
+            assert_eq!(retry, id);
+            send(&socket, &UdpMessage::reply(id.wrapping_add(1000), b"outro id"), client);
+            send(&socket, &UdpMessage::reply(answered, b"primeira"), client);
+            socket.send_to(b"nao e wire format", client).unwrap();
+            send(&socket, &UdpMessage::reply(id, b"segunda"), client);
+        });
+
+        let mut client = UdpClient::connect(&addr.to_string(), RetryPolicy::new(3, ms(200))).unwrap();
+        let first = client.request(UdpMessage::get("teste", "/a")).unwrap();
+        assert_eq!((first.attempts, first.duplicates, first.stray), (1, 0, 0));
+        assert_eq!(first.message.body, b"primeira");
+
+        let second = client.request(UdpMessage::get("teste", "/b")).unwrap();
+        assert_eq!(second.request_id, first.request_id.wrapping_add(1));
+        assert_eq!(second.message.body, b"segunda");
+        assert_eq!((second.attempts, second.duplicates, second.stray), (2, 1, 2));
+        assert_eq!(second.from, addr);
+        assert!(second.elapsed >= ms(200));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn resposta_de_erro_vira_invalid_data() {
+        let (addr, server) = fake_server(|socket| {
+            let (id, client) = receive(&socket);
+            send(&socket, &UdpMessage::error(id, "caminho proibido"), client);
+        });
+
+        let policy = RetryPolicy::once(Duration::from_secs(2));
+        let mut client = UdpClient::connect(&addr.to_string(), policy).unwrap();
+        let error = client.request(UdpMessage::get("teste", "/admin")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().ends_with(": caminho proibido"), "{}", error);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn sem_resposta_esgota_as_tentativas() {
+        let (addr, server) = fake_server(|socket| {
+            let first = receive(&socket).0;
+            assert_eq!(receive(&socket).0, first);
+        });
+
+        let mut client = UdpClient::connect(&addr.to_string(), RetryPolicy::new(2, ms(50))).unwrap();
+        let error = client.request(UdpMessage::get("teste", "/")).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        server.join().unwrap();
+    }
+}
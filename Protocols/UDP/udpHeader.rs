@@ -0,0 +1,306 @@
+use std::{fmt, ops::Range};
+
+use super::super::ipHeader::{
+    be16, parse_ip_packet, require, transport_checksum, IpHeader, PacketError, PROTO_UDP,
+};
+
+pub const UDP_HEADER_LEN: usize = 8;
+
+/// Cabeçalho UDP (RFC 768)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UdpHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub length: u16, // Cabeçalho + dados, como declarado no pacote
+    pub checksum: u16,
+}
+
+impl UdpHeader {
+    /// Campos do cabeçalho e suas posições em bytes, para os dumps anotados
+    pub fn field_ranges(&self) -> Vec<(&'static str, Range<usize>)> {
+        vec![
+            ("Porta de origem", 0..2),
+            ("Porta de destino", 2..4),
+            ("Comprimento", 4..6),
+            ("Checksum", 6..8),
+        ]
+    }
+}
+
+impl fmt::Display for UdpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "   Porta de origem: {}", self.src_port)?;
+        writeln!(f, "   Porta de destino: {}", self.dst_port)?;
+        writeln!(f, "   Comprimento: {} bytes", self.length)?;
+        write!(f, "   Checksum: 0x{:04X}", self.checksum)
+    }
+}
+
+/// Resultado da verificação do checksum
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Valid,
+    Invalid { expected: u16 },
+    Absent,       // Zero em IPv4: o remetente não calculou o checksum
+    Missing,      // Zero em IPv6, onde o checksum é obrigatório
+    Unverifiable, // Comprimento declarado inutilizável: faltam bytes para calcular
+    Fragmented,   // Primeiro fragmento: o resto do datagrama está em outros pacotes
+}
+
+impl fmt::Display for ChecksumStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChecksumStatus::Valid => write!(f, "válido"),
+            ChecksumStatus::Invalid { expected } => {
+                write!(f, "inválido (esperado 0x{:04X})", expected)
+            }
+            ChecksumStatus::Absent => write!(f, "ausente (opcional em IPv4)"),
+            ChecksumStatus::Missing => write!(f, "ausente (obrigatório em IPv6)"),
+            ChecksumStatus::Unverifiable => write!(f, "não verificável (comprimento inválido)"),
+            ChecksumStatus::Fragmented => write!(f, "não verificável (datagrama fragmentado)"),
+        }
+    }
+}
+
+/// Divergência entre o comprimento UDP e os bytes entregues pelo IP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthMismatch {
+    TooShort { declared: u16 },                      // Menor que o próprio cabeçalho
+    Truncated { declared: u16, available: usize },   // Faltam bytes
+    Trailing { declared: u16, available: usize },    // Sobram bytes após o datagrama
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LengthMismatch::TooShort { declared } => write!(
+                f,
+                "comprimento declarado {} menor que o cabeçalho ({} bytes)",
+                declared, UDP_HEADER_LEN
+            ),
+            LengthMismatch::Truncated { declared, available } => write!(
+                f,
+                "comprimento declarado {} mas só {} bytes recebidos",
+                declared, available
+            ),
+            LengthMismatch::Trailing { declared, available } => write!(
+                f,
+                "comprimento declarado {} mas {} bytes recebidos ({} extras)",
+                declared,
+                available,
+                available - usize::from(*declared)
+            ),
+        }
+    }
+}
+
+/// Datagrama UDP decodificado de um pacote IP bruto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpDatagram<'a> {
+    pub ip: IpHeader,
+    pub udp: UdpHeader,
+    pub header: &'a [u8],  // Bytes do cabeçalho UDP
+    pub payload: &'a [u8], // Dados dentro do comprimento declarado (ou os disponíveis)
+    pub checksum: ChecksumStatus,
+    pub length_mismatch: Option<LengthMismatch>,
+    pub fragmented: bool, // Primeiro fragmento IPv4 (MF): só o início do datagrama chegou
+}
+
+impl UdpDatagram<'_> {
+    /// Datagrama sem divergências de comprimento nem checksum inválido
+    ///
+    /// Um primeiro fragmento é consistente se o que chegou não contradiz o cabeçalho.
+    pub fn is_consistent(&self) -> bool {
+        self.length_mismatch.is_none()
+            && matches!(
+                self.checksum,
+                ChecksumStatus::Valid | ChecksumStatus::Absent | ChecksumStatus::Fragmented
+            )
+    }
+}
+
+/// Decodifica um pacote IPv4/IPv6 bruto contendo um datagrama UDP
+///
+/// Divergências de comprimento e checksum não são erros: ficam registradas
+/// em `length_mismatch` e `checksum` para o analisador reportar. Em um
+/// primeiro fragmento IPv4 o comprimento declarado é maior que os bytes
+/// recebidos por natureza; isso fica em `fragmented`, não como divergência.
+pub fn parse_udp_packet(packet: &[u8]) -> Result<UdpDatagram<'_>, PacketError> {
+    let (ip, segment) = parse_ip_packet(packet)?;
+    if ip.protocol() != PROTO_UDP {
+        return Err(PacketError::UnexpectedProtocol {
+            expected: PROTO_UDP,
+            found: ip.protocol(),
+        });
+    }
+    let udp = parse_udp_header(segment)?;
+    let declared = usize::from(udp.length);
+    // Fragmentos com deslocamento já foram recusados pelo parse_ip_packet
+    let fragmented = matches!(&ip, IpHeader::V4(header) if header.more_fragments);
+
+    let length_mismatch = if declared < UDP_HEADER_LEN {
+        Some(LengthMismatch::TooShort { declared: udp.length })
+    } else if fragmented && declared > segment.len() {
+        None
+    } else if declared > segment.len() {
+        Some(LengthMismatch::Truncated {
+            declared: udp.length,
+            available: segment.len(),
+        })
+    } else if declared < segment.len() {
+        Some(LengthMismatch::Trailing {
+            declared: udp.length,
+            available: segment.len(),
+        })
+    } else {
+        None
+    };
+
+    // Bytes extras ficam de fora; sem um comprimento utilizável, fica tudo
+    let (datagram, verifiable) = match length_mismatch {
+        None => (segment, true),
+        Some(LengthMismatch::Trailing { .. }) => (&segment[..declared], true),
+        Some(_) => (segment, false),
+    };
+    let checksum = match (&ip, udp.checksum) {
+        (IpHeader::V4(_), 0) => ChecksumStatus::Absent,
+        (IpHeader::V6(_), 0) => ChecksumStatus::Missing,
+        _ if fragmented => ChecksumStatus::Fragmented,
+        _ if !verifiable => ChecksumStatus::Unverifiable,
+        _ => verify_checksum(&ip, datagram),
+    };
+
+    Ok(UdpDatagram {
+        ip,
+        udp,
+        header: &segment[..UDP_HEADER_LEN],
+        payload: &datagram[UDP_HEADER_LEN..],
+        checksum,
+        length_mismatch,
+        fragmented,
+    })
+}
+
+/// Decodifica o cabeçalho UDP no início de `segment`
+pub fn parse_udp_header(segment: &[u8]) -> Result<UdpHeader, PacketError> {
+    require(segment, UDP_HEADER_LEN, "cabeçalho UDP")?;
+    Ok(UdpHeader {
+        src_port: be16(segment, 0),
+        dst_port: be16(segment, 2),
+        length: be16(segment, 4),
+        checksum: be16(segment, 6),
+    })
+}
+
+fn verify_checksum(ip: &IpHeader, datagram: &[u8]) -> ChecksumStatus {
+    if transport_checksum(ip, PROTO_UDP, datagram) == 0 {
+        return ChecksumStatus::Valid;
+    }
+    let mut zeroed = datagram.to_vec();
+    zeroed[6..8].fill(0);
+    // Um checksum calculado como 0 é transmitido como 0xFFFF
+    let expected = match transport_checksum(ip, PROTO_UDP, &zeroed) {
+        0 => 0xFFFF,
+        sum => sum,
+    };
+    ChecksumStatus::Invalid { expected }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    // Consulta DNS "example.com A" de 192.168.1.100:53124 para 8.8.8.8:53
+    const DNS_QUERY: &str = "450000393c1d400040112c7bc0a8016408080808cf840035002574351a2b01000001\
+                             000000000000076578616d706c6503636f6d0000010001";
+    // A mesma consulta no sentido inverso, enviada sem checksum UDP
+    const DNS_NO_CHECKSUM: &str = "45000039000040004011689808080808c0a801640035cf84002500001a2b010000\
+                                   01000000000000076578616d706c6503636f6d0000010001";
+    // Primeiro fragmento (MF, deslocamento 0) de um datagrama de 2008 bytes; só 1480 chegaram aqui
+    const FIRST_FRAGMENT_HEADERS: &str = "450005dc7a1120004011c6f50a0000020a0000099c40232807d8c11a";
+
+    fn first_fragment(flags: u8) -> Vec<u8> {
+        let mut packet = bytes(FIRST_FRAGMENT_HEADERS);
+        packet[6] = flags;
+        packet.extend((0..1472).map(|i| (i * 7 % 256) as u8));
+        packet
+    }
+
+    #[test]
+    fn checksum_valido() {
+        let packet = bytes(DNS_QUERY);
+        let datagram = parse_udp_packet(&packet).unwrap();
+        assert_eq!((datagram.udp.src_port, datagram.udp.dst_port), (53124, 53));
+        assert_eq!(datagram.udp.length, 37);
+        assert_eq!(datagram.checksum, ChecksumStatus::Valid);
+        assert_eq!(datagram.payload.len(), 29);
+        assert!(!datagram.fragmented);
+        assert!(datagram.is_consistent());
+    }
+
+    #[test]
+    fn checksum_alterado_informa_o_esperado() {
+        let mut packet = bytes(DNS_QUERY);
+        packet[27] ^= 0xFF;
+        let datagram = parse_udp_packet(&packet).unwrap();
+        assert_eq!(datagram.checksum, ChecksumStatus::Invalid { expected: 0x7435 });
+        assert!(!datagram.is_consistent());
+    }
+
+    #[test]
+    fn checksum_zero_em_ipv4_e_ausente() {
+        let packet = bytes(DNS_NO_CHECKSUM);
+        let datagram = parse_udp_packet(&packet).unwrap();
+        assert_eq!(datagram.checksum, ChecksumStatus::Absent);
+        assert!(datagram.is_consistent());
+    }
+
+    #[test]
+    fn captura_truncada() {
+        let packet = bytes(DNS_QUERY);
+        assert_eq!(
+            parse_udp_packet(&packet[..40]),
+            Err(PacketError::Truncated {
+                layer: "pacote IPv4",
+                needed: 57,
+                available: 40,
+            })
+        );
+    }
+
+    #[test]
+    fn primeiro_fragmento_nao_e_divergencia() {
+        let packet = first_fragment(0x20);
+        let datagram = parse_udp_packet(&packet).unwrap();
+        assert!(datagram.fragmented);
+        assert_eq!(datagram.udp.length, 2008);
+        assert_eq!(datagram.length_mismatch, None);
+        assert_eq!(datagram.checksum, ChecksumStatus::Fragmented);
+        assert_eq!(datagram.payload.len(), 1472);
+        assert!(datagram.is_consistent());
+
+        // Sem MF os mesmos bytes são um datagrama truncado
+        let packet = first_fragment(0x00);
+        let datagram = parse_udp_packet(&packet).unwrap();
+        assert!(!datagram.fragmented);
+        assert_eq!(
+            datagram.length_mismatch,
+            Some(LengthMismatch::Truncated { declared: 2008, available: 1480 })
+        );
+        assert_eq!(datagram.checksum, ChecksumStatus::Unverifiable);
+    }
+
+    #[test]
+    fn fragmento_seguinte_nao_tem_cabecalho_udp() {
+        let mut packet = first_fragment(0x20);
+        packet[7] = 185; // Deslocamento 1480 / 8
+        assert_eq!(parse_udp_packet(&packet), Err(PacketError::Fragment));
+    }
+}
@@ -0,0 +1,569 @@
+use std::fmt;
+
+/// Identifica um datagrama no formato binário (qualquer versão)
+pub const MAGIC: [u8; 4] = *b"FWUP";
+pub const WIRE_VERSION: u8 = 1;
+
+// Seções opcionais presentes após o cabeçalho fixo
+pub const FLAG_HEADERS: u8 = 0x01;
+pub const FLAG_REQUEST_ID: u8 = 0x02;
+pub const FLAG_FRAGMENT: u8 = 0x04;
+const KNOWN_FLAGS: u8 = FLAG_HEADERS | FLAG_REQUEST_ID | FLAG_FRAGMENT;
+
+/// magic + versão + verbo + flags
+const FIXED_LEN: usize = 7;
+
+/// Cabeçalho de um fragmento: fixo + id + índice + quantidade + tamanho da mensagem
+pub const FRAGMENT_HEADER_LEN: usize = FIXED_LEN + 4 + 2 + 2 + 4;
+
+/// Datagrama que cabe no MTU da maioria dos links, sem fragmentação IP
+pub const DEFAULT_MAX_DATAGRAM: usize = 1200;
+
+/// Maior mensagem aceita após a remontagem
+pub const DEFAULT_MAX_MESSAGE: usize = 1024 * 1024;
+
+/// Operação carregada pela mensagem
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    Get,
+    Post,
+    Reply, // Resposta do servidor, com o id da requisição
+    Error, // Requisição recusada pelo servidor; o corpo traz o motivo
+}
+
+impl Verb {
+    pub fn code(self) -> u8 {
+        match self {
+            Verb::Get => 1,
+            Verb::Post => 2,
+            Verb::Reply => 3,
+            Verb::Error => 4,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(Verb::Get),
+            2 => Some(Verb::Post),
+            3 => Some(Verb::Reply),
+            4 => Some(Verb::Error),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Verb::Get => "GET",
+            Verb::Post => "POST",
+            Verb::Reply => "REPLY",
+            Verb::Error => "ERROR",
+        }
+    }
+}
+
+impl fmt::Display for Verb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Erro ao codificar ou decodificar uma mensagem UDP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireError {
+    Truncated { field: &'static str, needed: usize, available: usize },
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownVerb(u8),
+    UnknownFlags(u8),
+    InvalidUtf8(&'static str),
+    FieldTooLong { field: &'static str, len: usize, max: usize },
+    TrailingBytes(usize),
+    Legacy(&'static str), // Datagrama legado malformado
+    MessageTooLarge { size: usize, max: usize },
+    MissingRequestId,         // Fragmentar exige um id para a remontagem
+    Fragmented,               // Datagrama é um fragmento: use `decode_datagram`
+    InvalidFragment(&'static str),
+}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WireError::Truncated { field, needed, available } => write!(
+                f,
+                "{} truncado: {} bytes necessários, {} disponíveis",
+                field, needed, available
+            ),
+            WireError::BadMagic => write!(f, "assinatura do formato binário ausente"),
+            WireError::UnsupportedVersion(version) => {
+                write!(f, "versão {} do formato não suportada", version)
+            }
+            WireError::UnknownVerb(code) => write!(f, "verbo desconhecido: {}", code),
+            WireError::UnknownFlags(flags) => write!(f, "flags desconhecidas: 0x{:02X}", flags),
+            WireError::InvalidUtf8(field) => write!(f, "{} não é UTF-8 válido", field),
+            WireError::FieldTooLong { field, len, max } => {
+                write!(f, "{} com {} bytes excede o máximo de {}", field, len, max)
+            }
+            WireError::TrailingBytes(count) => write!(f, "{} bytes após o fim da mensagem", count),
+            WireError::Legacy(reason) => write!(f, "datagrama legado inválido: {}", reason),
+            WireError::MessageTooLarge { size, max } => {
+                write!(f, "mensagem de {} bytes excede o máximo de {}", size, max)
+            }
+            WireError::MissingRequestId => write!(f, "mensagem fragmentada sem id de requisição"),
+            WireError::Fragmented => write!(f, "datagrama é um fragmento de mensagem"),
+            WireError::InvalidFragment(reason) => write!(f, "fragmento inválido: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl From<WireError> for std::io::Error {
+    fn from(e: WireError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Mensagem GET/POST trocada pelas ferramentas UDP
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdpMessage {
+    pub verb: Verb,
+    pub request_id: Option<u32>, // Correlaciona requisição e resposta
+    pub path: String,
+    pub host: String,
+    pub headers: Vec<(String, String)>, // Opcionais; até 255
+    pub body: Vec<u8>,                  // Binário arbitrário, pode conter NUL
+}
+
+impl UdpMessage {
+    pub fn get(host: &str, path: &str) -> Self {
+        Self {
+            verb: Verb::Get,
+            request_id: None,
+            path: path.to_string(),
+            host: host.to_string(),
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn post(host: &str, path: &str, body: &[u8]) -> Self {
+        Self {
+            verb: Verb::Post,
+            body: body.to_vec(),
+            ..Self::get(host, path)
+        }
+    }
+
+    /// Resposta à requisição `request_id`; path e host ficam vazios
+    pub fn reply(request_id: u32, body: &[u8]) -> Self {
+        Self {
+            verb: Verb::Reply,
+            request_id: Some(request_id),
+            body: body.to_vec(),
+            ..Self::get("", "")
+        }
+    }
+
+    /// Recusa da requisição `request_id`, com o motivo no corpo
+    pub fn error(request_id: u32, reason: &str) -> Self {
+        Self {
+            verb: Verb::Error,
+            ..Self::reply(request_id, reason.as_bytes())
+        }
+    }
+
+    pub fn with_request_id(mut self, request_id: u32) -> Self {
+        self.request_id = Some(request_id);
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Primeiro valor do cabeçalho `name` (sem diferenciar maiúsculas)
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Codifica no formato binário versionado
+///
+/// ```text
+/// "FWUP" | versão u8 | verbo u8 | flags u8
+/// [FLAG_REQUEST_ID] id: u32
+/// path: u16 + bytes | host: u16 + bytes
+/// [FLAG_HEADERS] quantidade u8, cada um nome: u16 + bytes, valor: u16 + bytes
+/// corpo: u32 + bytes
+/// ```
+///
+/// Inteiros em ordem de rede (big-endian).
+pub fn encode(message: &UdpMessage) -> Result<Vec<u8>, WireError> {
+    let fields = message.path.len() + message.host.len() + message.body.len();
+    let mut out = Vec::with_capacity(FIXED_LEN + 8 + fields);
+    out.extend_from_slice(&MAGIC);
+    out.push(WIRE_VERSION);
+    out.push(message.verb.code());
+    let mut flags = 0;
+    if !message.headers.is_empty() {
+        flags |= FLAG_HEADERS;
+    }
+    if message.request_id.is_some() {
+        flags |= FLAG_REQUEST_ID;
+    }
+    out.push(flags);
+
+    if let Some(id) = message.request_id {
+        out.extend_from_slice(&id.to_be_bytes());
+    }
+    put_u16_field(&mut out, "path", message.path.as_bytes())?;
+    put_u16_field(&mut out, "host", message.host.as_bytes())?;
+    if !message.headers.is_empty() {
+        let count = u8::try_from(message.headers.len()).map_err(|_| WireError::FieldTooLong {
+            field: "lista de cabeçalhos",
+            len: message.headers.len(),
+            max: usize::from(u8::MAX),
+        })?;
+        out.push(count);
+        for (name, value) in &message.headers {
+            put_u16_field(&mut out, "nome de cabeçalho", name.as_bytes())?;
+            put_u16_field(&mut out, "valor de cabeçalho", value.as_bytes())?;
+        }
+    }
+    let len = u32::try_from(message.body.len()).map_err(|_| WireError::FieldTooLong {
+        field: "corpo",
+        len: message.body.len(),
+        max: u32::MAX as usize,
+    })?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(&message.body);
+    Ok(out)
+}
+
+/// Decodifica o formato binário versionado
+pub fn decode(data: &[u8]) -> Result<UdpMessage, WireError> {
+    let mut reader = Reader { data, pos: 0 };
+    let fixed = reader.take("cabeçalho", FIXED_LEN)?;
+    if fixed[..4] != MAGIC {
+        return Err(WireError::BadMagic);
+    }
+    if fixed[4] != WIRE_VERSION {
+        return Err(WireError::UnsupportedVersion(fixed[4]));
+    }
+    let verb = Verb::from_code(fixed[5]).ok_or(WireError::UnknownVerb(fixed[5]))?;
+    let flags = fixed[6];
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(WireError::UnknownFlags(flags & !KNOWN_FLAGS));
+    }
+    if flags & FLAG_FRAGMENT != 0 {
+        return Err(WireError::Fragmented);
+    }
+
+    let request_id = if flags & FLAG_REQUEST_ID != 0 {
+        Some(reader.u32("id da requisição")?)
+    } else {
+        None
+    };
+    let path = reader.string("path")?;
+    let host = reader.string("host")?;
+    let mut headers = Vec::new();
+    if flags & FLAG_HEADERS != 0 {
+        let count = reader.take("lista de cabeçalhos", 1)?[0];
+        for _ in 0..count {
+            let name = reader.string("nome de cabeçalho")?;
+            let value = reader.string("valor de cabeçalho")?;
+            headers.push((name, value));
+        }
+    }
+    let len = reader.u32("tamanho do corpo")? as usize;
+    let body = reader.take("corpo", len)?.to_vec();
+
+    if reader.pos < data.len() {
+        return Err(WireError::TrailingBytes(data.len() - reader.pos));
+    }
+    Ok(UdpMessage {
+        verb,
+        request_id,
+        path,
+        host,
+        headers,
+        body,
+    })
+}
+
+/// Pedaço de uma mensagem maior que um datagrama
+///
+/// ```text
+/// "FWUP" | versão u8 | verbo u8 | flags (FLAG_REQUEST_ID | FLAG_FRAGMENT)
+/// id: u32 | índice: u16 | quantidade: u16 | tamanho da mensagem: u32
+/// pedaço da mensagem codificada (resto do datagrama)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fragment<'a> {
+    pub request_id: u32,
+    pub verb: Verb, // Verbo da mensagem completa
+    pub index: u16,
+    pub count: u16,
+    pub total_len: u32, // Tamanho da mensagem codificada completa
+    pub data: &'a [u8],
+}
+
+/// Datagrama recebido: mensagem completa ou fragmento a remontar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Datagram<'a> {
+    Message(UdpMessage),
+    Fragment(Fragment<'a>),
+}
+
+/// Codifica `message` em um ou mais datagramas de até `max_datagram` bytes
+///
+/// Uma mensagem que cabe em um datagrama sai sem fragmentação. Maior que isso,
+/// a mensagem codificada é dividida em fragmentos, que exigem `request_id`.
+/// Mensagens codificadas maiores que `max_message` são recusadas.
+pub fn encode_fragments(
+    message: &UdpMessage,
+    max_datagram: usize,
+    max_message: usize,
+) -> Result<Vec<Vec<u8>>, WireError> {
+    let encoded = encode(message)?;
+    if encoded.len() > max_message {
+        return Err(WireError::MessageTooLarge { size: encoded.len(), max: max_message });
+    }
+    if encoded.len() <= max_datagram {
+        return Ok(vec![encoded]);
+    }
+    let request_id = message.request_id.ok_or(WireError::MissingRequestId)?;
+    if max_datagram <= FRAGMENT_HEADER_LEN {
+        return Err(WireError::MessageTooLarge { size: encoded.len(), max: max_datagram });
+    }
+
+    let chunks = encoded.chunks(max_datagram - FRAGMENT_HEADER_LEN);
+    let count = u16::try_from(chunks.len()).map_err(|_| WireError::MessageTooLarge {
+        size: encoded.len(),
+        max: usize::from(u16::MAX) * (max_datagram - FRAGMENT_HEADER_LEN),
+    })?;
+    let total_len = u32::try_from(encoded.len()).map_err(|_| WireError::MessageTooLarge {
+        size: encoded.len(),
+        max: u32::MAX as usize,
+    })?;
+
+    let fragments = chunks
+        .enumerate()
+        .map(|(index, chunk)| {
+            let mut out = Vec::with_capacity(FRAGMENT_HEADER_LEN + chunk.len());
+            out.extend_from_slice(&MAGIC);
+            out.push(WIRE_VERSION);
+            out.push(message.verb.code());
+            out.push(FLAG_REQUEST_ID | FLAG_FRAGMENT);
+            out.extend_from_slice(&request_id.to_be_bytes());
+            out.extend_from_slice(&(index as u16).to_be_bytes());
+            out.extend_from_slice(&count.to_be_bytes());
+            out.extend_from_slice(&total_len.to_be_bytes());
+            out.extend_from_slice(chunk);
+            out
+        })
+        .collect();
+    Ok(fragments)
+}
+
+/// Decodifica um datagrama binário, completo ou fragmento
+pub fn decode_datagram(data: &[u8]) -> Result<Datagram<'_>, WireError> {
+    match decode(data) {
+        Err(WireError::Fragmented) => decode_fragment(data).map(Datagram::Fragment),
+        result => result.map(Datagram::Message),
+    }
+}
+
+fn decode_fragment(data: &[u8]) -> Result<Fragment<'_>, WireError> {
+    let mut reader = Reader { data, pos: 0 };
+    let fixed = reader.take("cabeçalho", FIXED_LEN)?;
+    let verb = Verb::from_code(fixed[5]).ok_or(WireError::UnknownVerb(fixed[5]))?;
+    if fixed[6] & FLAG_REQUEST_ID == 0 {
+        return Err(WireError::MissingRequestId);
+    }
+    let request_id = reader.u32("id da requisição")?;
+    let index = reader.take("índice do fragmento", 2)?;
+    let count = reader.take("quantidade de fragmentos", 2)?;
+    let total_len = reader.u32("tamanho da mensagem")?;
+    Ok(Fragment {
+        request_id,
+        verb,
+        index: u16::from_be_bytes([index[0], index[1]]),
+        count: u16::from_be_bytes([count[0], count[1]]),
+        total_len,
+        data: &data[reader.pos..],
+    })
+}
+
+/// Decodifica o formato legado `GET<path>\0<host>\0` / `POST<path>\0<host>\0<dados>`
+///
+/// No legado não há como distinguir um NUL dos dados de um separador: tudo
+/// após o segundo NUL de um POST é corpo.
+pub fn decode_legacy(data: &[u8]) -> Result<UdpMessage, WireError> {
+    let (verb, rest) = if let Some(rest) = data.strip_prefix(b"POST") {
+        (Verb::Post, rest)
+    } else if let Some(rest) = data.strip_prefix(b"GET") {
+        (Verb::Get, rest)
+    } else {
+        return Err(WireError::Legacy("verbo diferente de GET/POST"));
+    };
+
+    let mut parts = rest.splitn(3, |&b| b == 0);
+    let path = parts.next().unwrap_or_default();
+    let host = parts.next().ok_or(WireError::Legacy("separador após o path ausente"))?;
+    let tail = parts.next().ok_or(WireError::Legacy("separador após o host ausente"))?;
+    if verb == Verb::Get && !tail.is_empty() {
+        return Err(WireError::TrailingBytes(tail.len()));
+    }
+
+    Ok(UdpMessage {
+        verb,
+        request_id: None,
+        path: utf8(path, "path")?,
+        host: utf8(host, "host")?,
+        headers: Vec::new(),
+        body: tail.to_vec(),
+    })
+}
+
+/// Decodifica qualquer formato suportado; o binário é reconhecido pelo `MAGIC`
+pub fn decode_any(data: &[u8]) -> Result<UdpMessage, WireError> {
+    if is_wire_format(data) {
+        decode(data)
+    } else {
+        decode_legacy(data)
+    }
+}
+
+pub fn is_wire_format(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+fn put_u16_field(out: &mut Vec<u8>, field: &'static str, bytes: &[u8]) -> Result<(), WireError> {
+    let len = u16::try_from(bytes.len()).map_err(|_| WireError::FieldTooLong {
+        field,
+        len: bytes.len(),
+        max: usize::from(u16::MAX),
+    })?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn utf8(bytes: &[u8], field: &'static str) -> Result<String, WireError> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| WireError::InvalidUtf8(field))
+}
+
+/// Cursor sobre o datagrama recebido
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, field: &'static str, len: usize) -> Result<&'a [u8], WireError> {
+        let available = self.data.len() - self.pos;
+        if available < len {
+            return Err(WireError::Truncated { field, needed: len, available });
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self, field: &'static str) -> Result<u32, WireError> {
+        let bytes = self.take(field, 4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Campo de texto com prefixo de tamanho u16
+    fn string(&mut self, field: &'static str) -> Result<String, WireError> {
+        let len = self.take(field, 2)?;
+        let len = usize::from(u16::from_be_bytes([len[0], len[1]]));
+        utf8(self.take(field, len)?, field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(body_len: usize) -> UdpMessage {
+        let body: Vec<u8> = (0..body_len).map(|i| (i % 256) as u8).collect();
+        UdpMessage::post("localhost", "/upload", &body)
+            .with_request_id(42)
+            .with_header("Content-Type", "application/octet-stream")
+    }
+
+    #[test]
+    fn codifica_e_decodifica_mensagem() {
+        let message = post(64);
+        assert_eq!(decode(&encode(&message).unwrap()), Ok(message.clone()));
+        assert_eq!(decode_any(&encode(&message).unwrap()), Ok(message));
+    }
+
+    #[test]
+    fn mensagem_pequena_sai_em_um_datagrama() {
+        let message = UdpMessage::get("localhost", "/");
+        let datagrams = encode_fragments(&message, DEFAULT_MAX_DATAGRAM, DEFAULT_MAX_MESSAGE).unwrap();
+        assert_eq!(datagrams.len(), 1);
+        assert_eq!(decode_datagram(&datagrams[0]), Ok(Datagram::Message(message)));
+    }
+
+    #[test]
+    fn fragmentos_concatenados_reproduzem_a_mensagem() {
+        let message = post(3000);
+        let encoded = encode(&message).unwrap();
+        let datagrams = encode_fragments(&message, 500, DEFAULT_MAX_MESSAGE).unwrap();
+        assert_eq!(datagrams.len(), encoded.len().div_ceil(500 - FRAGMENT_HEADER_LEN));
+
+        let mut joined = Vec::new();
+        for (i, datagram) in datagrams.iter().enumerate() {
+            assert!(datagram.len() <= 500);
+            let Ok(Datagram::Fragment(fragment)) = decode_datagram(datagram) else {
+                panic!("datagrama {} não é um fragmento", i);
+            };
+            assert_eq!((fragment.request_id, fragment.verb), (42, Verb::Post));
+            assert_eq!((usize::from(fragment.index), usize::from(fragment.count)), (i, datagrams.len()));
+            assert_eq!(fragment.total_len as usize, encoded.len());
+            joined.extend_from_slice(fragment.data);
+        }
+        assert_eq!(joined, encoded);
+        assert_eq!(decode(&datagrams[0]), Err(WireError::Fragmented));
+    }
+
+    #[test]
+    fn limites_da_fragmentacao() {
+        let mut message = post(3000);
+        assert!(matches!(
+            encode_fragments(&message, 500, 1000),
+            Err(WireError::MessageTooLarge { max: 1000, .. })
+        ));
+        assert!(matches!(
+            encode_fragments(&message, FRAGMENT_HEADER_LEN, DEFAULT_MAX_MESSAGE),
+            Err(WireError::MessageTooLarge { .. })
+        ));
+        message.request_id = None;
+        assert_eq!(
+            encode_fragments(&message, 500, DEFAULT_MAX_MESSAGE),
+            Err(WireError::MissingRequestId)
+        );
+    }
+
+    #[test]
+    fn fragmento_com_cabecalho_truncado() {
+        let datagrams = encode_fragments(&post(3000), 500, DEFAULT_MAX_MESSAGE).unwrap();
+        assert_eq!(
+            decode_datagram(&datagrams[0][..FRAGMENT_HEADER_LEN - 1]),
+            Err(WireError::Truncated {
+                field: "tamanho da mensagem",
+                needed: 4,
+                available: 3,
+            })
+        );
+    }
+}
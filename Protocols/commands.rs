@@ -0,0 +1,398 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+use chrono_tz::Tz;
+
+/// Erro ao interpretar ou executar um comando
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    Empty,
+    Unknown(String),
+    UnterminatedQuote,
+    WrongArgCount { command: String, usage: &'static str, found: usize },
+    InvalidArgument { command: String, arg: &'static str, value: String },
+    Failed(String), // Erro do próprio handler
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CommandError::Empty => write!(f, "Comando vazio"),
+            CommandError::Unknown(name) => write!(f, "Comando não reconhecido: '{}'", name),
+            CommandError::UnterminatedQuote => write!(f, "Aspas não fechadas"),
+            CommandError::WrongArgCount { command, usage, found } => write!(
+                f,
+                "{}: {} argumento(s) recebido(s); uso: {}",
+                command, found, usage
+            ),
+            CommandError::InvalidArgument { command, arg, value } => {
+                write!(f, "{}: valor inválido para <{}>: '{}'", command, arg, value)
+            }
+            CommandError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+/// Linha de comando separada em nome e argumentos
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandLine {
+    pub name: String, // Em maiúsculas: comandos não diferenciam maiúsculas
+    pub args: Vec<String>,
+}
+
+/// Separa `line` em palavras; aspas duplas agrupam, `\"` e `\\` escapam dentro delas
+pub fn parse_command_line(line: &str) -> Result<CommandLine, CommandError> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut chars = line.trim().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => current.push(escaped),
+                            Some(other) => {
+                                current.push('\\');
+                                current.push(other);
+                            }
+                            None => return Err(CommandError::UnterminatedQuote),
+                        },
+                        Some(other) => current.push(other),
+                        None => return Err(CommandError::UnterminatedQuote),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            c => {
+                in_word = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(current);
+    }
+
+    let mut words = words.into_iter();
+    let name = words.next().ok_or(CommandError::Empty)?.to_uppercase();
+    Ok(CommandLine {
+        name,
+        args: words.collect(),
+    })
+}
+
+/// Dados entregues ao handler de um comando
+pub struct Invocation<'a> {
+    pub registry: &'a CommandRegistry,
+    pub name: &'a str,
+    pub args: &'a [String],
+    pub stats: &'a dyn fmt::Display, // Estatísticas do servidor que recebeu o comando
+}
+
+impl Invocation<'_> {
+    /// Argumento opcional na posição `index`, convertido para `T`
+    pub fn arg<T: FromStr>(&self, index: usize, arg: &'static str) -> Result<Option<T>, CommandError> {
+        self.args
+            .get(index)
+            .map(|value| {
+                value.parse().map_err(|_| CommandError::InvalidArgument {
+                    command: self.name.to_string(),
+                    arg,
+                    value: value.clone(),
+                })
+            })
+            .transpose()
+    }
+}
+
+type Handler = Box<dyn Fn(&Invocation) -> Result<String, CommandError> + Send + Sync>;
+
+/// Comando registrado: nome, ajuda, aridade e handler
+pub struct Command {
+    name: String,
+    usage: &'static str,
+    summary: &'static str,
+    min_args: usize,
+    max_args: Option<usize>, // `None`: sem limite
+    handler: Handler,
+}
+
+impl Command {
+    pub fn new<F>(name: &str, handler: F) -> Self
+    where
+        F: Fn(&Invocation) -> Result<String, CommandError> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.to_uppercase(),
+            usage: "",
+            summary: "",
+            min_args: 0,
+            max_args: Some(0),
+            handler: Box::new(handler),
+        }
+    }
+
+    pub fn with_usage(mut self, usage: &'static str) -> Self {
+        self.usage = usage;
+        self
+    }
+
+    pub fn with_summary(mut self, summary: &'static str) -> Self {
+        self.summary = summary;
+        self
+    }
+
+    /// Quantidade de argumentos aceita; `max` `None` aceita qualquer quantidade a partir de `min`
+    pub fn with_args(mut self, min: usize, max: Option<usize>) -> Self {
+        self.min_args = min;
+        self.max_args = max;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn usage(&self) -> &str {
+        if self.usage.is_empty() {
+            &self.name
+        } else {
+            self.usage
+        }
+    }
+
+    pub fn summary(&self) -> &str {
+        self.summary
+    }
+}
+
+/// Comandos disponíveis em um servidor, por nome
+pub struct CommandRegistry {
+    commands: BTreeMap<String, Command>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::with_builtins()
+    }
+}
+
+impl CommandRegistry {
+    /// Registro vazio
+    pub fn new() -> Self {
+        Self {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Registro com PING, TIME, STATS, HELP e ECHO
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register(
+            Command::new("PING", |_| Ok("PONG".to_string()))
+                .with_summary("Testa se o servidor responde"),
+        );
+        registry.register(
+            Command::new("TIME", |call| {
+                let now = chrono::Utc::now();
+                Ok(match call.arg::<Tz>(0, "fuso")? {
+                    Some(tz) => now.with_timezone(&tz).format("%H:%M:%S %Z").to_string(),
+                    None => now.with_timezone(&chrono::Local).format("%H:%M:%S").to_string(),
+                })
+            })
+            .with_usage("TIME [fuso]")
+            .with_summary("Hora local do servidor ou no fuso IANA indicado")
+            .with_args(0, Some(1)),
+        );
+        registry.register(
+            Command::new("STATS", |call| Ok(call.stats.to_string()))
+                .with_summary("Estatísticas do servidor"),
+        );
+        registry.register(
+            Command::new("HELP", |call| match call.args.first() {
+                Some(name) => {
+                    let command = call
+                        .registry
+                        .get(name)
+                        .ok_or_else(|| CommandError::Unknown(name.clone()))?;
+                    Ok(format!("{} - {}", command.usage(), command.summary()))
+                }
+                None => Ok(call.registry.help()),
+            })
+            .with_usage("HELP [comando]")
+            .with_summary("Lista os comandos ou mostra o uso de um deles")
+            .with_args(0, Some(1)),
+        );
+        registry.register(
+            Command::new("ECHO", |call| Ok(call.args.join(" ")))
+                .with_usage("ECHO <texto>...")
+                .with_summary("Devolve os argumentos")
+                .with_args(0, None),
+        );
+        registry
+    }
+
+    /// Adiciona ou substitui um comando
+    pub fn register(&mut self, command: Command) -> &mut Self {
+        self.commands.insert(command.name.clone(), command);
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Command> {
+        self.commands.get(&name.to_uppercase())
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+
+    /// Uma linha por comando: uso e descrição
+    pub fn help(&self) -> String {
+        let width = self.commands.values().map(|c| c.usage().len()).max().unwrap_or(0);
+        self.commands
+            .values()
+            .map(|command| format!("{:width$}  {}", command.usage(), command.summary(), width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Interpreta `line` e executa o comando correspondente
+    pub fn dispatch(&self, line: &str, stats: &dyn fmt::Display) -> Result<String, CommandError> {
+        let parsed = parse_command_line(line)?;
+        self.execute(&parsed, stats)
+    }
+
+    /// Executa um comando já separado, verificando a quantidade de argumentos
+    pub fn execute(&self, line: &CommandLine, stats: &dyn fmt::Display) -> Result<String, CommandError> {
+        let command = self
+            .commands
+            .get(&line.name)
+            .ok_or_else(|| CommandError::Unknown(line.name.clone()))?;
+        let found = line.args.len();
+        if found < command.min_args || command.max_args.is_some_and(|max| found > max) {
+            return Err(CommandError::WrongArgCount {
+                command: command.name.clone(),
+                usage: if command.usage.is_empty() { "sem argumentos" } else { command.usage },
+                found,
+            });
+        }
+        (command.handler)(&Invocation {
+            registry: self,
+            name: &command.name,
+            args: &line.args,
+            stats,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Result<(String, Vec<String>), CommandError> {
+        parse_command_line(line).map(|parsed| (parsed.name, parsed.args))
+    }
+
+    fn run(line: &str) -> Result<String, CommandError> {
+        CommandRegistry::with_builtins().dispatch(line, &"estatísticas")
+    }
+
+    #[test]
+    fn separa_palavras_aspas_e_escapes() {
+        let owned = |args: &[&str]| args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let cases: [(&str, (&str, Vec<String>)); 6] = [
+            ("ping", ("PING", owned(&[]))),
+            ("  echo   a  b  ", ("ECHO", owned(&["a", "b"]))),
+            ("echo \"a  b\" c", ("ECHO", owned(&["a  b", "c"]))),
+            ("echo \"\" x", ("ECHO", owned(&["", "x"]))),
+            ("echo pre\"fixo junto\"", ("ECHO", owned(&["prefixo junto"]))),
+            (r#"echo "diz \"oi\" \\ \n""#, ("ECHO", owned(&[r#"diz "oi" \ \n"#]))),
+        ];
+        for (line, (name, args)) in cases {
+            assert_eq!(words(line), Ok((name.to_string(), args)), "{}", line);
+        }
+    }
+
+    #[test]
+    fn linha_vazia_ou_aspas_abertas() {
+        assert_eq!(words("   "), Err(CommandError::Empty));
+        assert_eq!(words("echo \"sem fim"), Err(CommandError::UnterminatedQuote));
+        assert_eq!(words("echo \"barra no fim\\"), Err(CommandError::UnterminatedQuote));
+    }
+
+    #[test]
+    fn verifica_quantidade_de_argumentos() {
+        let wrong = |command: &str, usage: &'static str, found| {
+            Err(CommandError::WrongArgCount { command: command.to_string(), usage, found })
+        };
+        assert_eq!(run("PING extra"), wrong("PING", "sem argumentos", 1));
+        assert_eq!(run("TIME UTC extra"), wrong("TIME", "TIME [fuso]", 2));
+        assert_eq!(run("HELP a b"), wrong("HELP", "HELP [comando]", 2));
+
+        let mut registry = CommandRegistry::new();
+        registry.register(
+            Command::new("SOMA", |call| Ok(call.args.len().to_string()))
+                .with_usage("SOMA <a> <b>...")
+                .with_args(2, None),
+        );
+        assert_eq!(registry.dispatch("soma 1", &""), wrong("SOMA", "SOMA <a> <b>...", 1));
+        assert_eq!(registry.dispatch("soma 1 2 3 4", &""), Ok("4".to_string()));
+        assert_eq!(registry.dispatch("PING", &""), Err(CommandError::Unknown("PING".to_string())));
+    }
+
+    #[test]
+    fn help_lista_comandos_alinhados() {
+        let expected = "\
+ECHO <texto>...  Devolve os argumentos
+HELP [comando]   Lista os comandos ou mostra o uso de um deles
+PING             Testa se o servidor responde
+STATS            Estatísticas do servidor
+TIME [fuso]      Hora local do servidor ou no fuso IANA indicado";
+        assert_eq!(run("HELP"), Ok(expected.to_string()));
+        assert_eq!(run("help echo"), Ok("ECHO <texto>... - Devolve os argumentos".to_string()));
+        assert_eq!(run("HELP NADA"), Err(CommandError::Unknown("NADA".to_string())));
+    }
+
+    #[test]
+    fn echo_e_stats() {
+        assert_eq!(run("ECHO"), Ok(String::new()));
+        assert_eq!(run("echo \"olá  mundo\" !"), Ok("olá  mundo !".to_string()));
+        assert_eq!(run("STATS"), Ok("estatísticas".to_string()));
+    }
+
+    #[test]
+    fn time_no_fuso_indicado() {
+        let is_clock = |s: &str| {
+            s.len() == 8
+                && s.char_indices()
+                    .all(|(i, c)| if i % 3 == 2 { c == ':' } else { c.is_ascii_digit() })
+        };
+        let local = run("TIME").unwrap();
+        assert!(is_clock(&local), "{}", local);
+
+        let utc = run("TIME UTC").unwrap();
+        assert!(utc.ends_with(" UTC") && is_clock(&utc[..8]), "{}", utc);
+        let sao_paulo = run("TIME America/Sao_Paulo").unwrap();
+        assert!(sao_paulo.ends_with(" -03"), "{}", sao_paulo);
+
+        assert_eq!(
+            run("TIME Marte/Olympus"),
+            Err(CommandError::InvalidArgument {
+                command: "TIME".to_string(),
+                arg: "fuso",
+                value: "Marte/Olympus".to_string(),
+            })
+        );
+    }
+}
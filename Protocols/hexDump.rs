@@ -0,0 +1,338 @@
+use std::{fmt::Write, ops::Range};
+
+/// Coluna de um dump, na ordem em que aparece na linha
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Offset,
+    Hex,
+    Decimal,
+    Binary,
+    Ascii,
+}
+
+impl Column {
+    fn title(self) -> &'static str {
+        match self {
+            Column::Offset => "Offset",
+            Column::Hex => "Hexadecimal",
+            Column::Decimal => "Decimal",
+            Column::Binary => "Binário",
+            Column::Ascii => "ASCII",
+        }
+    }
+
+    /// Largura de um byte na coluna, sem o separador
+    fn cell_width(self) -> usize {
+        match self {
+            Column::Hex => 2,
+            Column::Decimal => 3,
+            Column::Binary => 8,
+            Column::Offset | Column::Ascii => 1,
+        }
+    }
+
+    fn cell(self, byte: u8) -> String {
+        match self {
+            Column::Hex => format!("{:02X}", byte),
+            Column::Decimal => format!("{:3}", byte),
+            Column::Binary => format!("{:08b}", byte),
+            Column::Offset | Column::Ascii => printable(byte).to_string(),
+        }
+    }
+}
+
+/// Formato de um dump: colunas, bytes por linha e agrupamento
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpFormat {
+    pub columns: Vec<Column>,
+    pub width: usize, // Bytes por linha
+    pub group: usize, // Espaço extra a cada `group` bytes; 0 desativa
+    pub header: bool, // Linha de títulos e separador
+}
+
+impl Default for DumpFormat {
+    /// Dump clássico: offset, 16 bytes em hexadecimal e ASCII
+    fn default() -> Self {
+        Self::new(&[Column::Offset, Column::Hex, Column::Ascii])
+    }
+}
+
+impl DumpFormat {
+    pub fn new(columns: &[Column]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            width: 16,
+            group: 0,
+            header: true,
+        }
+    }
+
+    /// Offset, hexadecimal, decimal e ASCII, 8 bytes por linha
+    pub fn multi_format() -> Self {
+        Self::new(&[Column::Offset, Column::Hex, Column::Decimal, Column::Ascii]).with_width(8)
+    }
+
+    /// Um byte por linha em binário, hexadecimal, decimal e ASCII
+    pub fn binary_analysis() -> Self {
+        Self::new(&[
+            Column::Offset,
+            Column::Binary,
+            Column::Hex,
+            Column::Decimal,
+            Column::Ascii,
+        ])
+        .with_width(1)
+    }
+
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width.max(1);
+        self
+    }
+
+    pub fn with_group(mut self, group: usize) -> Self {
+        self.group = group;
+        self
+    }
+
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Largura total da coluna para uma linha completa
+    fn column_width(&self, column: Column) -> usize {
+        match column {
+            Column::Offset => 8,
+            Column::Ascii => self.width,
+            _ => {
+                let gaps = (self.width - 1).checked_div(self.group).unwrap_or(0);
+                self.width * (column.cell_width() + 1) - 1 + gaps
+            }
+        }
+    }
+
+    /// Bytes da coluna, separados por espaço e agrupados
+    fn render_bytes(&self, column: Column, bytes: &[u8]) -> String {
+        if column == Column::Ascii {
+            return bytes.iter().map(|&byte| printable(byte)).collect();
+        }
+        let mut out = String::new();
+        for (i, &byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                out.push(' ');
+                if self.group > 0 && i % self.group == 0 {
+                    out.push(' ');
+                }
+            }
+            out.push_str(&column.cell(byte));
+        }
+        out
+    }
+
+    /// Largura da coluna contando o título
+    fn padded_width(&self, column: Column) -> usize {
+        self.column_width(column).max(column.title().chars().count())
+    }
+
+    fn push_header(&self, out: &mut String, leading: &[(&str, usize)]) {
+        let mut titles: Vec<(String, usize)> = leading
+            .iter()
+            .map(|(title, width)| (title.to_string(), *width))
+            .collect();
+        for &column in &self.columns {
+            titles.push((column.title().to_string(), self.padded_width(column)));
+        }
+        let rules: Vec<(String, usize)> = titles
+            .iter()
+            .map(|(_, width)| ("-".repeat(*width), *width))
+            .collect();
+        push_row(out, &titles);
+        push_row(out, &rules);
+    }
+}
+
+/// Escreve uma linha com as colunas alinhadas; a última não recebe padding
+fn push_row(out: &mut String, cells: &[(String, usize)]) {
+    for (i, (cell, width)) in cells.iter().enumerate() {
+        if i + 1 == cells.len() {
+            let _ = writeln!(out, "{}", cell);
+        } else {
+            let _ = write!(out, "{:width$}   ", cell, width = width);
+        }
+    }
+}
+
+/// Dump de `data` no formato pedido, uma linha a cada `format.width` bytes
+pub fn hex_dump(data: &[u8], format: &DumpFormat) -> String {
+    let mut out = String::new();
+    if format.header {
+        format.push_header(&mut out, &[]);
+    }
+    for (i, chunk) in data.chunks(format.width).enumerate() {
+        let offset = i * format.width;
+        let cells: Vec<(String, usize)> = format
+            .columns
+            .iter()
+            .map(|&column| {
+                let cell = match column {
+                    Column::Offset => format!("{:08X}", offset),
+                    column => format.render_bytes(column, chunk),
+                };
+                (cell, format.padded_width(column))
+            })
+            .collect();
+        push_row(&mut out, &cells);
+    }
+    out
+}
+
+/// Dump anotado: uma linha por campo, com seu nome e valor
+///
+/// `fields` vem do cabeçalho decodificado (ex.: `TcpHeader::field_ranges`).
+/// Campos de até 4 bytes mostram o valor inteiro (ordem de rede) na coluna
+/// `Decimal`; campos maiores que `format.width` continuam nas linhas seguintes.
+pub fn annotated_dump(data: &[u8], fields: &[(&str, Range<usize>)], format: &DumpFormat) -> String {
+    let name_width = fields
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Campo".len());
+
+    let mut out = String::new();
+    if format.header {
+        format.push_header(&mut out, &[("Campo", name_width)]);
+    }
+
+    for (name, range) in fields {
+        let end = range.end.min(data.len());
+        let bytes = &data[range.start.min(end)..end];
+        for (i, chunk) in bytes.chunks(format.width).enumerate() {
+            let offset = range.start + i * format.width;
+            let label = if i == 0 { name.to_string() } else { String::new() };
+            let mut cells = vec![(label, name_width)];
+            for &column in &format.columns {
+                let cell = match column {
+                    Column::Offset => format!("{:08X}", offset),
+                    Column::Decimal if i == 0 && bytes.len() <= 4 => field_value(bytes).to_string(),
+                    Column::Decimal if bytes.len() <= 4 => String::new(),
+                    column => format.render_bytes(column, chunk),
+                };
+                cells.push((cell, format.padded_width(column)));
+            }
+            push_row(&mut out, &cells);
+        }
+    }
+    out
+}
+
+/// Valor inteiro big-endian (ordem de rede) de até 4 bytes
+pub fn field_value(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0, |value, &byte| value << 8 | u32::from(byte))
+}
+
+/// Caractere exibido na coluna ASCII; não imprimíveis viram '.'
+pub fn printable(byte: u8) -> char {
+    if byte.is_ascii_graphic() || byte == b' ' {
+        byte as char
+    } else {
+        '.'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Linhas esperadas, cada uma terminada em '\n'
+    fn lines(lines: &[&str]) -> String {
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    #[test]
+    fn formato_padrao_com_ultima_linha_parcial() {
+        let data: Vec<u8> = (b'A'..=b'T').collect();
+        assert_eq!(
+            hex_dump(&data, &DumpFormat::default()),
+            lines(&[
+                "Offset     Hexadecimal                                       ASCII",
+                "--------   -----------------------------------------------   ----------------",
+                "00000000   41 42 43 44 45 46 47 48 49 4A 4B 4C 4D 4E 4F 50   ABCDEFGHIJKLMNOP",
+                "00000010   51 52 53 54                                       QRST",
+            ])
+        );
+    }
+
+    #[test]
+    fn multi_formato_agrupado() {
+        assert_eq!(
+            hex_dump(b"GET /\r\n\x00\xff", &DumpFormat::multi_format().with_group(4)),
+            lines(&[
+                "Offset     Hexadecimal                Decimal                            ASCII",
+                "--------   ------------------------   --------------------------------   --------",
+                "00000000   47 45 54 20  2F 0D 0A 00    71  69  84  32   47  13  10   0   GET /...",
+                "00000008   FF                         255                                .",
+            ])
+        );
+    }
+
+    #[test]
+    fn sem_cabecalho_mantem_largura_dos_titulos() {
+        assert_eq!(
+            hex_dump(&[0x00, 0x7f, 0xa5], &DumpFormat::binary_analysis().with_header(false)),
+            lines(&[
+                "00000000   00000000   00              0       .",
+                "00000001   01111111   7F            127       .",
+                "00000002   10100101   A5            165       .",
+            ])
+        );
+
+        let format = DumpFormat::new(&[Column::Hex, Column::Ascii])
+            .with_width(3)
+            .with_group(2)
+            .with_header(false);
+        assert_eq!(
+            hex_dump(b"abcdefg", &format),
+            lines(&["61 62  63     abc", "64 65  66     def", "67            g"])
+        );
+    }
+
+    #[test]
+    fn entrada_vazia() {
+        assert_eq!(
+            hex_dump(b"", &DumpFormat::default()),
+            lines(&[
+                "Offset     Hexadecimal                                       ASCII",
+                "--------   -----------------------------------------------   ----------------",
+            ])
+        );
+        assert_eq!(hex_dump(b"", &DumpFormat::default().with_header(false)), "");
+    }
+
+    #[test]
+    fn anotacoes_sobrepostas_e_fora_dos_dados() {
+        let segment = [0x9c, 0x40, 0x01, 0xbb, 0x00, 0x00, 0x03, 0xe8, 0xff, 0x01];
+        let fields = [
+            ("Porta", 0..2),
+            ("Sobreposto", 1..4),
+            ("Seq", 4..8),
+            ("Longo", 2..10),  // Mais de 4 bytes: decimal byte a byte, em duas linhas
+            ("Fora", 8..14),   // Cortado no fim dos dados
+            ("Vazio", 20..24), // Inteiro fora: nenhuma linha
+        ];
+        let format = DumpFormat::new(&[Column::Offset, Column::Hex, Column::Decimal]).with_width(4);
+        assert_eq!(
+            annotated_dump(&segment, &fields, &format),
+            lines(&[
+                "Campo        Offset     Hexadecimal   Decimal",
+                "----------   --------   -----------   ---------------",
+                "Porta        00000000   9C 40         40000",
+                "Sobreposto   00000001   40 01 BB      4194747",
+                "Seq          00000004   00 00 03 E8   1000",
+                "Longo        00000002   01 BB 00 00     1 187   0   0",
+                "             00000006   03 E8 FF 01     3 232 255   1",
+                "Fora         00000008   FF 01         65281",
+            ])
+        );
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+// Números de protocolo IANA usados pelos analisadores
+pub const PROTO_TCP: u8 = 6;
+pub const PROTO_UDP: u8 = 17;
+
+// Cabeçalhos de extensão IPv6 percorridos até o protocolo de transporte
+const IPV6_HOP_BY_HOP: u8 = 0;
+const IPV6_ROUTING: u8 = 43;
+const IPV6_FRAGMENT: u8 = 44;
+const IPV6_DEST_OPTIONS: u8 = 60;
+
+/// Erro ao decodificar um pacote bruto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PacketError {
+    Truncated { layer: &'static str, needed: usize, available: usize },
+    UnsupportedVersion(u8),              // Primeiro nibble diferente de 4 e 6
+    InvalidHeaderLength(&'static str, usize), // IHL/data offset menor que o mínimo
+    UnexpectedProtocol { expected: u8, found: u8 },
+    Fragment,                            // Fragmento não inicial: sem cabeçalho de transporte
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PacketError::Truncated { layer, needed, available } => write!(
+                f,
+                "{} truncado: {} bytes necessários, {} disponíveis",
+                layer, needed, available
+            ),
+            PacketError::UnsupportedVersion(version) => {
+                write!(f, "versão IP {} não suportada", version)
+            }
+            PacketError::InvalidHeaderLength(layer, len) => {
+                write!(f, "tamanho de cabeçalho {} inválido: {} bytes", layer, len)
+            }
+            PacketError::UnexpectedProtocol { expected, found } => write!(
+                f,
+                "protocolo {} encontrado, esperado {}",
+                found, expected
+            ),
+            PacketError::Fragment => write!(f, "fragmento IP sem cabeçalho de transporte"),
+        }
+    }
+}
+
+impl std::error::Error for PacketError {}
+
+/// Cabeçalho IPv4 (RFC 791)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv4Header {
+    pub header_len: usize, // IHL em bytes (20-60)
+    pub dscp: u8,
+    pub ecn: u8,
+    pub total_length: u16,
+    pub identification: u16,
+    pub dont_fragment: bool,
+    pub more_fragments: bool,
+    pub fragment_offset: u16, // Em unidades de 8 bytes
+    pub ttl: u8,
+    pub protocol: u8,
+    pub checksum: u16,
+    pub src: Ipv4Addr,
+    pub dst: Ipv4Addr,
+    pub options: Vec<u8>,
+}
+
+/// Cabeçalho IPv6 fixo (RFC 8200), com o protocolo final após as extensões
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ipv6Header {
+    pub traffic_class: u8,
+    pub flow_label: u32,
+    pub payload_length: u16,
+    pub next_header: u8, // Primeiro cabeçalho após o fixo
+    pub hop_limit: u8,
+    pub src: Ipv6Addr,
+    pub dst: Ipv6Addr,
+    pub protocol: u8,      // Protocolo de transporte após as extensões
+    pub header_len: usize, // 40 + extensões
+}
+
+/// Cabeçalho de rede de um pacote bruto
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpHeader {
+    V4(Ipv4Header),
+    V6(Ipv6Header),
+}
+
+impl IpHeader {
+    pub fn src(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => IpAddr::V4(header.src),
+            IpHeader::V6(header) => IpAddr::V6(header.src),
+        }
+    }
+
+    pub fn dst(&self) -> IpAddr {
+        match self {
+            IpHeader::V4(header) => IpAddr::V4(header.dst),
+            IpHeader::V6(header) => IpAddr::V6(header.dst),
+        }
+    }
+
+    /// Protocolo de transporte (6 = TCP, 17 = UDP)
+    pub fn protocol(&self) -> u8 {
+        match self {
+            IpHeader::V4(header) => header.protocol,
+            IpHeader::V6(header) => header.protocol,
+        }
+    }
+
+    /// Bytes do cabeçalho de rede, incluindo opções/extensões
+    pub fn header_len(&self) -> usize {
+        match self {
+            IpHeader::V4(header) => header.header_len,
+            IpHeader::V6(header) => header.header_len,
+        }
+    }
+}
+
+impl fmt::Display for IpHeader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IpHeader::V4(header) => write!(
+                f,
+                "IPv4 {} -> {} | proto {} | TTL {} | {} bytes | id 0x{:04X}{}",
+                header.src,
+                header.dst,
+                header.protocol,
+                header.ttl,
+                header.total_length,
+                header.identification,
+                if header.dont_fragment { " | DF" } else { "" }
+            ),
+            IpHeader::V6(header) => write!(
+                f,
+                "IPv6 {} -> {} | proto {} | hop limit {} | payload {} bytes",
+                header.src, header.dst, header.protocol, header.hop_limit, header.payload_length
+            ),
+        }
+    }
+}
+
+/// Decodifica o cabeçalho IPv4/IPv6 de um pacote bruto
+///
+/// Retorna o cabeçalho e a carga de transporte, limitada ao tamanho
+/// declarado no cabeçalho (bytes extras, como padding Ethernet, são descartados).
+pub fn parse_ip_packet(packet: &[u8]) -> Result<(IpHeader, &[u8]), PacketError> {
+    let first = *packet.first().ok_or(PacketError::Truncated {
+        layer: "cabeçalho IP",
+        needed: 1,
+        available: 0,
+    })?;
+    match first >> 4 {
+        4 => parse_ipv4(packet),
+        6 => parse_ipv6(packet),
+        version => Err(PacketError::UnsupportedVersion(version)),
+    }
+}
+
+fn parse_ipv4(packet: &[u8]) -> Result<(IpHeader, &[u8]), PacketError> {
+    require(packet, 20, "cabeçalho IPv4")?;
+    let header_len = usize::from(packet[0] & 0x0F) * 4;
+    if header_len < 20 {
+        return Err(PacketError::InvalidHeaderLength("IPv4", header_len));
+    }
+    require(packet, header_len, "cabeçalho IPv4")?;
+
+    let total_length = be16(packet, 2);
+    if usize::from(total_length) < header_len {
+        return Err(PacketError::InvalidHeaderLength("IPv4", usize::from(total_length)));
+    }
+    require(packet, usize::from(total_length), "pacote IPv4")?;
+
+    let flags_fragment = be16(packet, 6);
+    let header = Ipv4Header {
+        header_len,
+        dscp: packet[1] >> 2,
+        ecn: packet[1] & 0x03,
+        total_length,
+        identification: be16(packet, 4),
+        dont_fragment: flags_fragment & 0x4000 != 0,
+        more_fragments: flags_fragment & 0x2000 != 0,
+        fragment_offset: flags_fragment & 0x1FFF,
+        ttl: packet[8],
+        protocol: packet[9],
+        checksum: be16(packet, 10),
+        src: Ipv4Addr::new(packet[12], packet[13], packet[14], packet[15]),
+        dst: Ipv4Addr::new(packet[16], packet[17], packet[18], packet[19]),
+        options: packet[20..header_len].to_vec(),
+    };
+    if header.fragment_offset != 0 {
+        return Err(PacketError::Fragment);
+    }
+    Ok((IpHeader::V4(header), &packet[header_len..usize::from(total_length)]))
+}
+
+fn parse_ipv6(packet: &[u8]) -> Result<(IpHeader, &[u8]), PacketError> {
+    require(packet, 40, "cabeçalho IPv6")?;
+    let payload_length = be16(packet, 4);
+    let end = 40 + usize::from(payload_length);
+    require(packet, end, "pacote IPv6")?;
+
+    let address = |offset: usize| {
+        let mut octets = [0u8; 16];
+        octets.copy_from_slice(&packet[offset..offset + 16]);
+        Ipv6Addr::from(octets)
+    };
+
+    // Percorre as extensões até o cabeçalho de transporte
+    let next_header = packet[6];
+    let mut protocol = next_header;
+    let mut offset = 40;
+    loop {
+        match protocol {
+            IPV6_HOP_BY_HOP | IPV6_ROUTING | IPV6_DEST_OPTIONS => {
+                require(&packet[..end], offset + 2, "extensão IPv6")?;
+                let len = (usize::from(packet[offset + 1]) + 1) * 8;
+                require(&packet[..end], offset + len, "extensão IPv6")?;
+                protocol = packet[offset];
+                offset += len;
+            }
+            IPV6_FRAGMENT => {
+                require(&packet[..end], offset + 8, "extensão IPv6")?;
+                if be16(packet, offset + 2) >> 3 != 0 {
+                    return Err(PacketError::Fragment);
+                }
+                protocol = packet[offset];
+                offset += 8;
+            }
+            _ => break,
+        }
+    }
+
+    let header = Ipv6Header {
+        traffic_class: (packet[0] << 4) | (packet[1] >> 4),
+        flow_label: u32::from_be_bytes([0, packet[1] & 0x0F, packet[2], packet[3]]),
+        payload_length,
+        next_header,
+        hop_limit: packet[7],
+        src: address(8),
+        dst: address(24),
+        protocol,
+        header_len: offset,
+    };
+    Ok((IpHeader::V6(header), &packet[offset..end]))
+}
+
+/// Checksum de TCP/UDP (RFC 1071) sobre o pseudo-cabeçalho IP e `segment`
+///
+/// `segment` é o cabeçalho de transporte seguido dos dados. Com o campo de
+/// checksum preenchido, um segmento íntegro resulta em 0; com o campo
+/// zerado, o resultado é o valor que deveria estar no cabeçalho.
+pub fn transport_checksum(ip: &IpHeader, protocol: u8, segment: &[u8]) -> u16 {
+    let mut sum = 0u32;
+    match ip {
+        IpHeader::V4(header) => {
+            sum = ones_complement_sum(sum, &header.src.octets());
+            sum = ones_complement_sum(sum, &header.dst.octets());
+            sum = ones_complement_sum(sum, &[0, protocol]);
+            sum = ones_complement_sum(sum, &(segment.len() as u16).to_be_bytes());
+        }
+        IpHeader::V6(header) => {
+            sum = ones_complement_sum(sum, &header.src.octets());
+            sum = ones_complement_sum(sum, &header.dst.octets());
+            sum = ones_complement_sum(sum, &(segment.len() as u32).to_be_bytes());
+            sum = ones_complement_sum(sum, &[0, 0, 0, protocol]);
+        }
+    }
+    !fold(ones_complement_sum(sum, segment))
+}
+
+/// Soma de palavras de 16 bits; um byte final ímpar é completado com zero
+fn ones_complement_sum(mut sum: u32, data: &[u8]) -> u32 {
+    for chunk in data.chunks(2) {
+        let word = u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]);
+        sum = fold(sum + u32::from(word)).into();
+    }
+    sum
+}
+
+/// Dobra os carries para os 16 bits inferiores
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+/// Falha com `Truncated` se `data` tiver menos de `needed` bytes
+pub fn require(data: &[u8], needed: usize, layer: &'static str) -> Result<(), PacketError> {
+    if data.len() < needed {
+        return Err(PacketError::Truncated {
+            layer,
+            needed,
+            available: data.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Inteiro de 16 bits big-endian (ordem de rede) na posição `offset`
+pub fn be16(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+/// Inteiro de 32 bits big-endian (ordem de rede) na posição `offset`
+pub fn be32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // IHL 6: 4 bytes de opção (Router Alert); DSCP 46, ECN 1; DF; TCP
+    const IPV4_COM_OPCOES: [u8; 24] = [
+        0x46, 0xb9, 0x00, 0x2f, 0x12, 0x34, 0x40, 0x00, 0x40, 0x06, 0xc8, 0x2c, 0xc0, 0xa8, 0x00, 0x01,
+        0x0a, 0x00, 0x00, 0x02, 0x94, 0x04, 0x00, 0x00,
+    ];
+
+    // Classe de tráfego 0x2e, flow label 0xbeef, payload 55, next header 0 (hop-by-hop)
+    const IPV6_FIXO: [u8; 40] = [
+        0x62, 0xe0, 0xbe, 0xef, 0x00, 0x37, 0x00, 0x40, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+    ];
+
+    // Hop-by-hop -> roteamento -> opções de destino -> fragmento (offset 0, M=1) -> TCP
+    const IPV6_EXTENSOES: [u8; 32] = [
+        43, 0, 1, 4, 0, 0, 0, 0, //
+        60, 0, 0, 0, 0, 0, 0, 0, //
+        44, 0, 1, 4, 0, 0, 0, 0, //
+        6, 0, 0x00, 0x01, 0xde, 0xad, 0xbe, 0xef,
+    ];
+
+    /// SYN 1234 -> 80 com "oi!" e o checksum informado
+    fn segmento(checksum: u16) -> Vec<u8> {
+        let mut segment = vec![
+            0x04, 0xd2, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x50, 0x02, 0x04, 0x00,
+            0x00, 0x00, 0x00, 0x00, b'o', b'i', b'!',
+        ];
+        segment[16..18].copy_from_slice(&checksum.to_be_bytes());
+        segment
+    }
+
+    fn ipv4(checksum: u16) -> Vec<u8> {
+        [&IPV4_COM_OPCOES[..], &segmento(checksum)].concat()
+    }
+
+    fn ipv6(checksum: u16) -> Vec<u8> {
+        [&IPV6_FIXO[..], &IPV6_EXTENSOES, &segmento(checksum)].concat()
+    }
+
+    #[test]
+    fn ipv4_com_opcoes() {
+        // Padding Ethernet depois do total_length é descartado
+        let mut packet = ipv4(0x4ba8);
+        packet.extend_from_slice(&[0; 6]);
+        let (header, payload) = parse_ip_packet(&packet).unwrap();
+        let IpHeader::V4(v4) = &header else { panic!("esperado IPv4: {:?}", header) };
+
+        assert_eq!(v4.header_len, 24);
+        assert_eq!(v4.options, [0x94, 0x04, 0x00, 0x00]);
+        assert_eq!((v4.dscp, v4.ecn), (46, 1));
+        assert_eq!((v4.total_length, v4.identification, v4.ttl), (47, 0x1234, 64));
+        assert!(v4.dont_fragment && !v4.more_fragments);
+        assert_eq!(header.src(), IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)));
+        assert_eq!(header.dst(), IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+        assert_eq!((header.protocol(), header.header_len()), (PROTO_TCP, 24));
+        assert_eq!(payload, segmento(0x4ba8));
+    }
+
+    #[test]
+    fn ipv4_invalido() {
+        let packet = ipv4(0);
+        let with = |index: usize, value: u8| {
+            let mut packet = packet.clone();
+            packet[index] = value;
+            packet
+        };
+
+        assert_eq!(parse_ip_packet(&with(0, 0x44)), Err(PacketError::InvalidHeaderLength("IPv4", 16)));
+        assert_eq!(
+            parse_ip_packet(&with(0, 0x4f)),
+            Err(PacketError::Truncated { layer: "cabeçalho IPv4", needed: 60, available: 47 })
+        );
+        // total_length menor que o cabeçalho ou além do buffer
+        assert_eq!(parse_ip_packet(&with(3, 20)), Err(PacketError::InvalidHeaderLength("IPv4", 20)));
+        assert_eq!(
+            parse_ip_packet(&with(3, 48)),
+            Err(PacketError::Truncated { layer: "pacote IPv4", needed: 48, available: 47 })
+        );
+        assert_eq!(
+            parse_ip_packet(&packet[..19]),
+            Err(PacketError::Truncated { layer: "cabeçalho IPv4", needed: 20, available: 19 })
+        );
+        // Fragmento com offset 1 (8 bytes)
+        assert_eq!(parse_ip_packet(&with(7, 0x01)), Err(PacketError::Fragment));
+        assert_eq!(parse_ip_packet(&with(0, 0x56)), Err(PacketError::UnsupportedVersion(5)));
+        assert_eq!(
+            parse_ip_packet(&[]),
+            Err(PacketError::Truncated { layer: "cabeçalho IP", needed: 1, available: 0 })
+        );
+    }
+
+    #[test]
+    fn ipv6_percorre_cadeia_de_extensoes() {
+        let packet = ipv6(0xbade);
+        let (header, payload) = parse_ip_packet(&packet).unwrap();
+        let IpHeader::V6(v6) = &header else { panic!("esperado IPv6: {:?}", header) };
+
+        assert_eq!((v6.traffic_class, v6.flow_label), (0x2e, 0xbeef));
+        assert_eq!((v6.payload_length, v6.hop_limit), (55, 64));
+        assert_eq!((v6.next_header, v6.protocol), (IPV6_HOP_BY_HOP, PROTO_TCP));
+        assert_eq!(header.header_len(), 72);
+        assert_eq!(header.src(), "2001:db8::1".parse::<IpAddr>().unwrap());
+        assert_eq!(header.dst(), "2001:db8::2".parse::<IpAddr>().unwrap());
+        assert_eq!(payload, segmento(0xbade));
+
+        // Sem extensões o transporte começa logo após os 40 bytes
+        let mut direct = [&IPV6_FIXO[..], &segmento(0)].concat();
+        direct[5] = 23;
+        direct[6] = PROTO_TCP;
+        let (header, payload) = parse_ip_packet(&direct).unwrap();
+        assert_eq!((header.header_len(), payload.len()), (40, 23));
+    }
+
+    #[test]
+    fn ipv6_invalido() {
+        // Fragmento não inicial (offset 1)
+        let mut packet = ipv6(0);
+        packet[40 + 26] = 0x08;
+        assert_eq!(parse_ip_packet(&packet), Err(PacketError::Fragment));
+
+        // Cabeçalho de roteamento (offset 48) declarando 72 bytes, além do payload
+        let mut packet = ipv6(0);
+        packet[40 + 9] = 8;
+        assert_eq!(
+            parse_ip_packet(&packet),
+            Err(PacketError::Truncated { layer: "extensão IPv6", needed: 120, available: 95 })
+        );
+
+        // payload_length além do buffer
+        let packet = ipv6(0);
+        assert_eq!(
+            parse_ip_packet(&packet[..90]),
+            Err(PacketError::Truncated { layer: "pacote IPv6", needed: 95, available: 90 })
+        );
+        assert_eq!(
+            parse_ip_packet(&IPV6_FIXO[..39]),
+            Err(PacketError::Truncated { layer: "cabeçalho IPv6", needed: 40, available: 39 })
+        );
+    }
+
+    #[test]
+    fn checksum_de_transporte() {
+        // Com o campo zerado o resultado é o valor esperado no cabeçalho
+        for (packet, expected) in [(ipv4(0), 0x4ba8), (ipv6(0), 0xbade)] {
+            let (header, segment) = parse_ip_packet(&packet).unwrap();
+            assert_eq!(transport_checksum(&header, PROTO_TCP, segment), expected);
+        }
+
+        for packet in [ipv4(0x4ba8), ipv6(0xbade)] {
+            let (header, segment) = parse_ip_packet(&packet).unwrap();
+            assert_eq!(transport_checksum(&header, PROTO_TCP, segment), 0);
+
+            // Um bit trocado nos dados ou o protocolo errado no pseudo-cabeçalho falham
+            let mut corrupted = segment.to_vec();
+            corrupted[21] ^= 0x01;
+            assert_ne!(transport_checksum(&header, PROTO_TCP, &corrupted), 0);
+            assert_ne!(transport_checksum(&header, PROTO_UDP, segment), 0);
+        }
+    }
+}
@@ -0,0 +1,574 @@
+use std::fmt;
+
+use super::connTrack::ConnState;
+use super::ipNet::{nets_cover, subtract_nets, IpNet};
+use super::objectSets::ResolvedRule;
+use super::portSpec::PortSpec;
+use super::writeRules::{Action, Direction, FirewallConfig, Protocol};
+
+/// Conjunto de tráfego descrito pelos campos de casamento de uma regra
+///
+/// Campos `None` significam "qualquer valor", como na avaliação; listas de
+/// redes representam a união delas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficMatch {
+    pub direction: Direction,
+    pub protocol: Protocol,
+    pub src_ip: Option<Vec<IpNet>>,
+    pub dst_ip: Option<Vec<IpNet>>,
+    pub src_port: Option<PortSpec>,
+    pub dst_port: Option<PortSpec>,
+    pub state: Option<Vec<ConnState>>, // Estados de conexão, ordenados e sem repetição
+}
+
+impl TrafficMatch {
+    /// Tráfego casado pela regra; `None` se a regra nunca pode casar
+    pub fn of(rule: &ResolvedRule) -> Option<Self> {
+        Self {
+            direction: rule.direction,
+            protocol: rule.protocol,
+            src_ip: rule.src_ip.clone(),
+            dst_ip: rule.dst_ip.clone(),
+            src_port: rule.src_port.clone(),
+            dst_port: rule.dst_port.clone(),
+            state: rule.state.as_ref().map(|states| {
+                let mut states = states.clone();
+                states.sort();
+                states.dedup();
+                states
+            }),
+        }
+        .non_empty()
+    }
+
+    /// Tráfego casado pelas duas regras ao mesmo tempo
+    pub fn intersect(&self, other: &TrafficMatch) -> Option<TrafficMatch> {
+        Self {
+            direction: intersect_direction(self.direction, other.direction)?,
+            protocol: intersect_protocol(self.protocol, other.protocol)?,
+            src_ip: intersect_opt(&self.src_ip, &other.src_ip, |a, b| intersect_nets(a, b))?,
+            dst_ip: intersect_opt(&self.dst_ip, &other.dst_ip, |a, b| intersect_nets(a, b))?,
+            src_port: intersect_opt(&self.src_port, &other.src_port, PortSpec::intersect)?,
+            dst_port: intersect_opt(&self.dst_port, &other.dst_port, PortSpec::intersect)?,
+            state: intersect_opt(&self.state, &other.state, |a, b| intersect_states(a, b))?,
+        }
+        .non_empty()
+    }
+
+    /// Verifica se todo tráfego de `other` também é casado por este conjunto
+    pub fn covers(&self, other: &TrafficMatch) -> bool {
+        self.direction.matches(other.direction)
+            && self.protocol.matches(other.protocol)
+            && covers_opt(&self.src_ip, &other.src_ip, |a, b| covers_nets(a, b))
+            && covers_opt(&self.dst_ip, &other.dst_ip, |a, b| covers_nets(a, b))
+            && covers_opt(&self.src_port, &other.src_port, PortSpec::contains_spec)
+            && covers_opt(&self.dst_port, &other.dst_port, PortSpec::contains_spec)
+            && covers_opt(&self.state, &other.state, |a, b| b.iter().all(|s| a.contains(s)))
+    }
+
+    /// Tráfego deste conjunto que `other` não casa, em conjuntos disjuntos
+    ///
+    /// Quando a diferença não é representável (qualquer protocolo menos TCP,
+    /// campo ausente menos uma lista de portas ou estados) o conjunto é mantido
+    /// inteiro: o resultado nunca é menor que a diferença real.
+    pub fn subtract(&self, other: &TrafficMatch) -> Vec<TrafficMatch> {
+        self.try_subtract(other).unwrap_or_else(|| vec![self.clone()])
+    }
+
+    fn try_subtract(&self, other: &TrafficMatch) -> Option<Vec<TrafficMatch>> {
+        let Some(common) = self.intersect(other) else {
+            return Some(vec![self.clone()]);
+        };
+
+        // Campo a campo, o que fica fora de `other` vira uma peça e o restante
+        // segue restrito à interseção naquele campo
+        let mut pieces = Vec::new();
+        let mut rest = self.clone();
+        for direction in subtract_direction(self.direction, other.direction)? {
+            pieces.push(Self { direction, ..rest.clone() });
+        }
+        rest.direction = common.direction;
+        for protocol in subtract_protocol(self.protocol, other.protocol)? {
+            pieces.push(Self { protocol, ..rest.clone() });
+        }
+        rest.protocol = common.protocol;
+        for src_ip in subtract_nets_opt(&self.src_ip, &other.src_ip) {
+            pieces.push(Self { src_ip, ..rest.clone() });
+        }
+        rest.src_ip = common.src_ip;
+        for dst_ip in subtract_nets_opt(&self.dst_ip, &other.dst_ip) {
+            pieces.push(Self { dst_ip, ..rest.clone() });
+        }
+        rest.dst_ip = common.dst_ip;
+        for src_port in subtract_opt(&self.src_port, &other.src_port, PortSpec::subtract)? {
+            pieces.push(Self { src_port, ..rest.clone() });
+        }
+        rest.src_port = common.src_port;
+        for dst_port in subtract_opt(&self.dst_port, &other.dst_port, PortSpec::subtract)? {
+            pieces.push(Self { dst_port, ..rest.clone() });
+        }
+        rest.dst_port = common.dst_port;
+        for state in subtract_opt(&self.state, &other.state, |a, b| subtract_states(a, b))? {
+            pieces.push(Self { state, ..rest.clone() });
+        }
+
+        Some(pieces.into_iter().filter_map(Self::non_empty).collect())
+    }
+
+    /// ICMP não tem portas: uma restrição de porta torna o conjunto vazio,
+    /// assim como um conjunto de endereços ou de estados vazio
+    fn non_empty(self) -> Option<Self> {
+        let has_ports = self.src_port.is_some() || self.dst_port.is_some();
+        let empty_set = |nets: &Option<Vec<IpNet>>| nets.as_ref().is_some_and(Vec::is_empty);
+        if self.protocol == Protocol::Icmp && has_ports
+            || empty_set(&self.src_ip)
+            || empty_set(&self.dst_ip)
+            || self.state.as_ref().is_some_and(Vec::is_empty)
+        {
+            None
+        } else {
+            Some(self)
+        }
+    }
+}
+
+impl fmt::Display for TrafficMatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}:{} -> {}:{}",
+            self.direction,
+            self.protocol,
+            display_nets(&self.src_ip),
+            display_opt(&self.src_port),
+            display_nets(&self.dst_ip),
+            display_opt(&self.dst_port),
+        )?;
+        if let Some(states) = &self.state {
+            let states: Vec<&str> = states.iter().map(|state| state.as_str()).collect();
+            write!(f, " ct {}", states.join(","))?;
+        }
+        Ok(())
+    }
+}
+
+/// Tipo de problema encontrado entre duas regras
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindingKind {
+    Shadowed,  // Regra nunca casa: anteriores, alguma com outra ação, cobrem todo o seu tráfego
+    Redundant, // Regra nunca casa: anteriores com a mesma ação cobrem todo o seu tráfego
+    Conflict,  // Allow e deny se sobrepõem parcialmente; a anterior vence na interseção
+}
+
+/// Problema entre uma regra e outra anterior a ela
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub kind: FindingKind,
+    pub rule: String, // Regra afetada (posterior)
+    pub rule_index: usize,
+    pub by: String, // Regra anterior que causa o problema (ou completa a cobertura)
+    pub by_index: usize,
+    pub overlap: TrafficMatch, // Tráfego exato casado pelas duas regras
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = match self.kind {
+            FindingKind::Shadowed => "sombreada por",
+            FindingKind::Redundant => "redundante com",
+            FindingKind::Conflict => "em conflito com",
+        };
+        write!(
+            f,
+            "regra #{} ('{}') {} #{} ('{}') em [{}]",
+            self.rule_index, self.rule, description, self.by_index, self.by, self.overlap
+        )
+    }
+}
+
+/// Resultado da análise de um conjunto de regras
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnalysisReport {
+    pub findings: Vec<Finding>,
+}
+
+impl AnalysisReport {
+    pub fn is_clean(&self) -> bool {
+        self.findings.is_empty()
+    }
+
+    pub fn of_kind(&self, kind: FindingKind) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(move |finding| finding.kind == kind)
+    }
+}
+
+impl fmt::Display for AnalysisReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_clean() {
+            return writeln!(f, "✅ Nenhum sombreamento, redundância ou conflito encontrado");
+        }
+        for finding in &self.findings {
+            let icon = match finding.kind {
+                FindingKind::Shadowed => "❌",
+                FindingKind::Redundant => "📝",
+                FindingKind::Conflict => "⚠️",
+            };
+            writeln!(f, "{} {}", icon, finding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Analisa o conjunto de regras na ordem de avaliação (first-match)
+///
+/// Uma regra totalmente coberta por uma anterior, ou pela união das
+/// anteriores, é reportada apenas uma vez (sombreada ou redundante), já
+/// que nunca chegará a casar. As demais
+/// são comparadas com todas as anteriores em busca de conflitos allow/deny.
+/// A ordem considerada é a de prioridade, ignorando grupos desabilitados;
+/// os índices reportados são as posições no arquivo.
+pub fn analisar_regras(config: &FirewallConfig) -> AnalysisReport {
+    let mut report = AnalysisReport::default();
+    let resolved = config.resolved_rules();
+    let matches: Vec<Option<TrafficMatch>> = resolved.iter().map(TrafficMatch::of).collect();
+
+    for (j, later) in resolved.iter().enumerate() {
+        let Some(later_match) = &matches[j] else {
+            continue;
+        };
+
+        let mut conflicts = Vec::new();
+        let mut covered = None;
+        // Tráfego da regra ainda não casado pelas anteriores; `None` se a
+        // fragmentação passar do limite e a união deixar de ser acompanhada
+        let mut remaining = Some(vec![later_match.clone()]);
+        let mut other_action = false;
+
+        for (i, earlier) in resolved[..j].iter().enumerate() {
+            let Some(earlier_match) = &matches[i] else {
+                continue;
+            };
+            let Some(overlap) = earlier_match.intersect(later_match) else {
+                continue;
+            };
+
+            // Uma regra com condição de tempo só vale em parte do tempo: não cobre a posterior
+            if !earlier.is_timed() && earlier_match.covers(later_match) {
+                let kind = if earlier.action == later.action {
+                    FindingKind::Redundant
+                } else {
+                    FindingKind::Shadowed
+                };
+                covered = Some(finding(kind, later, earlier, overlap));
+                break;
+            }
+
+            if let (false, Some(pieces)) = (earlier.is_timed(), &remaining) {
+                if pieces.iter().any(|piece| piece.intersect(earlier_match).is_some()) {
+                    other_action |= earlier.action != later.action;
+                }
+                let pieces: Vec<TrafficMatch> =
+                    pieces.iter().flat_map(|piece| piece.subtract(earlier_match)).collect();
+                if pieces.is_empty() {
+                    let kind = if other_action {
+                        FindingKind::Shadowed
+                    } else {
+                        FindingKind::Redundant
+                    };
+                    covered = Some(finding(kind, later, earlier, overlap));
+                    break;
+                }
+                remaining = (pieces.len() <= MAX_REMAINING_PIECES).then_some(pieces);
+            }
+
+            if is_allow_deny_pair(earlier.action, later.action) {
+                conflicts.push(finding(FindingKind::Conflict, later, earlier, overlap));
+            }
+        }
+
+        match covered {
+            Some(finding) => report.findings.push(finding),
+            None => report.findings.extend(conflicts),
+        }
+    }
+
+    report
+}
+
+/// Limite de conjuntos disjuntos acompanhados por regra na cobertura por união
+const MAX_REMAINING_PIECES: usize = 4096;
+
+fn finding(kind: FindingKind, rule: &ResolvedRule, by: &ResolvedRule, overlap: TrafficMatch) -> Finding {
+    Finding {
+        kind,
+        rule: rule.name.clone(),
+        rule_index: rule.index,
+        by: by.name.clone(),
+        by_index: by.index,
+        overlap,
+    }
+}
+
+fn is_allow_deny_pair(a: Action, b: Action) -> bool {
+    matches!(
+        (a, b),
+        (Action::Allow, Action::Deny) | (Action::Deny, Action::Allow)
+    )
+}
+
+fn intersect_direction(a: Direction, b: Direction) -> Option<Direction> {
+    if a.matches(b) {
+        Some(b)
+    } else if b.matches(a) {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+fn intersect_protocol(a: Protocol, b: Protocol) -> Option<Protocol> {
+    if a.matches(b) {
+        Some(b)
+    } else if b.matches(a) {
+        Some(a)
+    } else {
+        None
+    }
+}
+
+/// Direções de `a` fora de `b`; `None` se não representável (qualquer menos uma)
+fn subtract_direction(a: Direction, b: Direction) -> Option<Vec<Direction>> {
+    if b.matches(a) {
+        Some(Vec::new())
+    } else if a.matches(b) {
+        None
+    } else {
+        Some(vec![a])
+    }
+}
+
+/// Protocolos de `a` fora de `b`; `None` se não representável (qualquer menos um)
+fn subtract_protocol(a: Protocol, b: Protocol) -> Option<Vec<Protocol>> {
+    if b.matches(a) {
+        Some(Vec::new())
+    } else if a.matches(b) {
+        None
+    } else {
+        Some(vec![a])
+    }
+}
+
+/// Redes de `a` fora de `b`; campo ausente é todo o espaço IPv4 e IPv6,
+/// já que todo fluxo tem endereço
+fn subtract_nets_opt(a: &Option<Vec<IpNet>>, b: &Option<Vec<IpNet>>) -> Vec<Option<Vec<IpNet>>> {
+    let Some(b) = b else {
+        return Vec::new();
+    };
+    let everything = ["0.0.0.0/0".parse().unwrap(), "::/0".parse().unwrap()];
+    let nets = subtract_nets(a.as_deref().unwrap_or(&everything), b);
+    if nets.is_empty() {
+        Vec::new()
+    } else {
+        vec![Some(nets)]
+    }
+}
+
+/// Diferença de campos opcionais; `None` se não representável: um campo
+/// ausente também casa fluxos sem porta ou sem estado, que uma lista não expressa
+fn subtract_opt<T>(
+    a: &Option<T>,
+    b: &Option<T>,
+    subtract: impl Fn(&T, &T) -> Option<T>,
+) -> Option<Vec<Option<T>>> {
+    match (a, b) {
+        (_, None) => Some(Vec::new()),
+        (None, Some(_)) => None,
+        (Some(a), Some(b)) => Some(subtract(a, b).map(Some).into_iter().collect()),
+    }
+}
+
+fn subtract_states(a: &[ConnState], b: &[ConnState]) -> Option<Vec<ConnState>> {
+    let states: Vec<ConnState> = a.iter().filter(|state| !b.contains(state)).copied().collect();
+    (!states.is_empty()).then_some(states)
+}
+
+/// Interseção de campos opcionais; o `Option` externo `None` indica conjunto vazio
+fn intersect_opt<T: Clone>(
+    a: &Option<T>,
+    b: &Option<T>,
+    intersect: impl Fn(&T, &T) -> Option<T>,
+) -> Option<Option<T>> {
+    match (a, b) {
+        (None, None) => Some(None),
+        (Some(value), None) | (None, Some(value)) => Some(Some(value.clone())),
+        (Some(a), Some(b)) => intersect(a, b).map(Some),
+    }
+}
+
+/// Interseções par a par das redes; `None` se nenhuma se sobrepõe
+fn intersect_nets(a: &[IpNet], b: &[IpNet]) -> Option<Vec<IpNet>> {
+    let nets: Vec<IpNet> = a
+        .iter()
+        .flat_map(|a| b.iter().filter_map(move |b| a.intersect(b)))
+        .collect();
+    (!nets.is_empty()).then_some(nets)
+}
+
+fn intersect_states(a: &[ConnState], b: &[ConnState]) -> Option<Vec<ConnState>> {
+    let states: Vec<ConnState> = a.iter().filter(|state| b.contains(state)).copied().collect();
+    (!states.is_empty()).then_some(states)
+}
+
+/// Cada rede interna precisa estar contida na união das redes externas
+fn covers_nets(outer: &[IpNet], inner: &[IpNet]) -> bool {
+    nets_cover(outer, inner)
+}
+
+fn covers_opt<T>(outer: &Option<T>, inner: &Option<T>, contains: impl Fn(&T, &T) -> bool) -> bool {
+    match (outer, inner) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(outer), Some(inner)) => contains(outer, inner),
+    }
+}
+
+fn display_nets(nets: &Option<Vec<IpNet>>) -> String {
+    match nets {
+        None => "*".to_string(),
+        Some(nets) => nets.iter().map(|net| net.to_string()).collect::<Vec<_>>().join(","),
+    }
+}
+
+fn display_opt<T: fmt::Display>(value: &Option<T>) -> String {
+    value
+        .as_ref()
+        .map_or_else(|| "*".to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn analisar(sets: serde_json::Value, rules: serde_json::Value) -> Vec<(FindingKind, usize, usize)> {
+        let config: FirewallConfig =
+            serde_json::from_value(json!({ "address_sets": sets, "rules": rules })).unwrap();
+        analisar_regras(&config)
+            .findings
+            .iter()
+            .map(|finding| (finding.kind, finding.rule_index, finding.by_index))
+            .collect()
+    }
+
+    fn rule(name: &str, action: &str, src_ip: &str, dst_port: serde_json::Value) -> serde_json::Value {
+        json!({
+            "name": name, "action": action, "direction": "inbound", "protocol": "tcp",
+            "src_ip": src_ip, "dst_port": dst_port
+        })
+    }
+
+    #[test]
+    fn sombreada_por_regra_mais_ampla_com_outra_acao() {
+        let findings = analisar(json!({}), json!([
+            rule("Bloquear LAN", "deny", "10.0.0.0/8", json!("1-1024")),
+            rule("Permitir SSH", "allow", "10.1.0.0/16", json!(22)),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Shadowed, 1, 0)]);
+    }
+
+    #[test]
+    fn redundante_com_mesma_acao() {
+        let findings = analisar(json!({}), json!([
+            rule("Web", "allow", "0.0.0.0/0", json!([80, 443])),
+            rule("HTTPS", "allow", "192.168.0.0/16", json!(443)),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Redundant, 1, 0)]);
+    }
+
+    #[test]
+    fn conflito_em_sobreposicao_parcial() {
+        let findings = analisar(json!({}), json!([
+            rule("Bloquear baixas", "deny", "10.0.0.0/8", json!("1-100")),
+            rule("Permitir faixa", "allow", "10.0.0.0/8", json!("50-150")),
+            rule("Outra rede", "deny", "172.16.0.0/12", json!("50-150")),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Conflict, 1, 0)]);
+    }
+
+    #[test]
+    fn uniao_de_redes_cobre_regra_posterior() {
+        let findings = analisar(
+            json!({ "metades": ["10.0.0.0/25", "10.0.0.128/25"] }),
+            json!([
+                rule("Bloquear metades", "deny", "@metades", json!(22)),
+                rule("Permitir /24", "allow", "10.0.0.0/24", json!(22)),
+            ]),
+        );
+        assert_eq!(findings, vec![(FindingKind::Shadowed, 1, 0)]);
+
+        // Com um buraco na união a cobertura é só parcial
+        let findings = analisar(
+            json!({ "partes": ["10.0.0.0/25", "10.0.1.0/24"] }),
+            json!([
+                rule("Bloquear partes", "deny", "@partes", json!(22)),
+                rule("Permitir /23", "allow", "10.0.0.0/23", json!(22)),
+            ]),
+        );
+        assert_eq!(findings, vec![(FindingKind::Conflict, 1, 0)]);
+    }
+
+    #[test]
+    fn uniao_de_regras_anteriores_sombreia_posterior() {
+        let findings = analisar(json!({}), json!([
+            rule("Metade baixa", "deny", "10.0.0.0/25", json!(22)),
+            rule("Metade alta", "deny", "10.0.0.128/25", json!(22)),
+            rule("Permitir /24", "allow", "10.0.0.0/24", json!(22)),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Shadowed, 2, 1)]);
+
+        // Cobertura em dois campos diferentes, uma delas com a mesma ação
+        let findings = analisar(json!({}), json!([
+            rule("Portas baixas", "allow", "10.0.0.0/24", json!("1-1000")),
+            rule("Portas altas", "deny", "10.0.0.0/24", json!("1001-65535")),
+            rule("Todas as portas", "deny", "10.0.0.0/24", json!("1-65535")),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Shadowed, 2, 1)]);
+
+        // Só a mesma ação: redundante
+        let findings = analisar(json!({}), json!([
+            rule("Metade baixa", "allow", "10.0.0.0/25", json!(22)),
+            rule("Metade alta", "allow", "10.0.0.128/25", json!(22)),
+            rule("Permitir /24", "allow", "10.0.0.0/24", json!(22)),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Redundant, 2, 1)]);
+    }
+
+    #[test]
+    fn uniao_com_regra_temporizada_ou_buraco_nao_cobre() {
+        let mut timed = rule("Metade alta à noite", "deny", "10.0.0.128/25", json!(22));
+        timed["schedule"] = json!({ "hours": ["22:00-06:00"] });
+        let findings = analisar(json!({}), json!([
+            rule("Metade baixa", "deny", "10.0.0.0/25", json!(22)),
+            timed,
+            rule("Permitir /24", "allow", "10.0.0.0/24", json!(22)),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Conflict, 2, 0), (FindingKind::Conflict, 2, 1)]);
+
+        // A porta 23 da metade alta fica de fora
+        let findings = analisar(json!({}), json!([
+            rule("Metade baixa", "deny", "10.0.0.0/25", json!("22-23")),
+            rule("Metade alta", "deny", "10.0.0.128/25", json!(22)),
+            rule("Permitir /24", "allow", "10.0.0.0/24", json!("22-23")),
+        ]));
+        assert_eq!(findings, vec![(FindingKind::Conflict, 2, 0), (FindingKind::Conflict, 2, 1)]);
+    }
+
+    #[test]
+    fn regras_disjuntas_nao_geram_achados() {
+        let findings = analisar(json!({}), json!([
+            rule("SSH", "allow", "10.0.0.0/8", json!(22)),
+            rule("HTTP", "deny", "10.0.0.0/8", json!(80)),
+        ]));
+        assert!(findings.is_empty());
+    }
+}
@@ -0,0 +1,478 @@
+use std::{collections::HashMap, fmt, net::IpAddr, sync::Mutex};
+
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::evalRules::Flow;
+use super::writeRules::Protocol;
+
+// Limite padrão de conexões rastreadas
+const DEFAULT_MAX_ENTRIES: usize = 65536;
+
+/// Estado de conexão de um fluxo, usado em `state` nas regras
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnState {
+    New,         // Primeiro pacote, ou conexão ainda sem resposta vista
+    Established, // Conexão já viu tráfego nos dois sentidos
+    Related,     // ICMP entre hosts com uma conexão TCP/UDP rastreada
+}
+
+impl ConnState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConnState::New => "new",
+            ConnState::Established => "established",
+            ConnState::Related => "related",
+        }
+    }
+
+    /// Interpreta nomes do nftables/iptables (`established`, `ESTABLISHED`)
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "new" => Some(ConnState::New),
+            "established" => Some(ConnState::Established),
+            "related" => Some(ConnState::Related),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ConnState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Flags do cabeçalho TCP relevantes para o rastreamento
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct TcpFlags(pub u8);
+
+impl TcpFlags {
+    pub const FIN: u8 = 0x01;
+    pub const SYN: u8 = 0x02;
+    pub const RST: u8 = 0x04;
+    pub const PSH: u8 = 0x08;
+    pub const ACK: u8 = 0x10;
+    pub const URG: u8 = 0x20;
+
+    pub fn contains(self, flag: u8) -> bool {
+        self.0 & flag != 0
+    }
+}
+
+/// Tempo de vida das conexões sem tráfego, por protocolo e estágio
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnTimeouts {
+    pub tcp_syn: Duration,         // TCP aguardando resposta ao SYN
+    pub tcp_established: Duration,
+    pub tcp_closing: Duration,     // Após FIN em qualquer sentido
+    pub udp: Duration,             // UDP sem resposta
+    pub udp_stream: Duration,      // UDP que já recebeu resposta
+    pub icmp: Duration,
+}
+
+impl Default for ConnTimeouts {
+    /// Valores próximos aos padrões do nf_conntrack do Linux
+    fn default() -> Self {
+        Self {
+            tcp_syn: Duration::seconds(120),
+            tcp_established: Duration::days(5),
+            tcp_closing: Duration::seconds(120),
+            udp: Duration::seconds(30),
+            udp_stream: Duration::seconds(180),
+            icmp: Duration::seconds(30),
+        }
+    }
+}
+
+/// Extremidades de uma conexão, no sentido de quem a iniciou
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ConnKey {
+    protocol: Protocol,
+    src_ip: IpAddr,
+    src_port: Option<u16>,
+    dst_ip: IpAddr,
+    dst_port: Option<u16>,
+}
+
+impl ConnKey {
+    fn of(flow: &Flow) -> Self {
+        Self {
+            protocol: flow.protocol,
+            src_ip: flow.src_ip,
+            src_port: flow.src_port,
+            dst_ip: flow.dst_ip,
+            dst_port: flow.dst_port,
+        }
+    }
+
+    fn reversed(&self) -> Self {
+        Self {
+            protocol: self.protocol,
+            src_ip: self.dst_ip,
+            src_port: self.dst_port,
+            dst_ip: self.src_ip,
+            dst_port: self.src_port,
+        }
+    }
+}
+
+/// Estágio de uma conexão TCP rastreada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TcpStage {
+    SynSent,
+    Established,
+    Closing,
+}
+
+#[derive(Debug, Clone)]
+struct ConnEntry {
+    seen_reply: bool,
+    tcp: TcpStage,
+    expires: DateTime<Utc>,
+}
+
+/// Conexão rastreada, como listada por `ConnTracker::entries`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnInfo {
+    pub protocol: Protocol,
+    pub src_ip: IpAddr, // Quem iniciou a conexão
+    pub src_port: Option<u16>,
+    pub dst_ip: IpAddr,
+    pub dst_port: Option<u16>,
+    pub state: ConnState,
+    pub expires: DateTime<Utc>,
+}
+
+/// Tabela de conexões consultada pelo avaliador
+///
+/// O primeiro fluxo permitido de uma conexão cria a entrada; fluxos no
+/// sentido inverso passam a ser `Established`. UDP e ICMP não têm
+/// handshake, então usam um pseudo-estado que expira sem tráfego.
+#[derive(Debug)]
+pub struct ConnTracker {
+    table: Mutex<HashMap<ConnKey, ConnEntry>>,
+    timeouts: ConnTimeouts,
+    max_entries: usize,
+}
+
+impl Default for ConnTracker {
+    fn default() -> Self {
+        Self::new(ConnTimeouts::default())
+    }
+}
+
+impl ConnTracker {
+    pub fn new(timeouts: ConnTimeouts) -> Self {
+        Self {
+            table: Mutex::new(HashMap::new()),
+            timeouts,
+            max_entries: DEFAULT_MAX_ENTRIES,
+        }
+    }
+
+    /// Limita o tamanho da tabela; conexões novas além do limite não são rastreadas
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries;
+        self
+    }
+
+    /// Estado do fluxo segundo a tabela, sem alterá-la
+    pub fn classify(&self, flow: &Flow, now: DateTime<Utc>) -> ConnState {
+        classify_in(&self.table.lock().unwrap(), flow, now)
+    }
+
+    /// Registra um fluxo permitido, criando ou atualizando sua conexão
+    ///
+    /// `state` é o resultado de `classify` para o mesmo fluxo. Retorna
+    /// `false` se a conexão não pôde ser rastreada (tabela cheia).
+    pub fn track(&self, flow: &Flow, state: ConnState, now: DateTime<Utc>) -> bool {
+        self.track_in(&mut self.table.lock().unwrap(), flow, state, now)
+    }
+
+    /// Classifica o fluxo, decide e registra sob um único lock
+    ///
+    /// `decide` recebe o estado e retorna o resultado e se o fluxo foi
+    /// permitido; só fluxos permitidos são registrados. Com `classify` e
+    /// `track` separados, dois pacotes da mesma conexão avaliados em paralelo
+    /// poderiam ver a tabela entre as duas chamadas.
+    pub fn classify_and_track<T>(
+        &self,
+        flow: &Flow,
+        now: DateTime<Utc>,
+        decide: impl FnOnce(ConnState) -> (T, bool),
+    ) -> T {
+        let mut table = self.table.lock().unwrap();
+        let state = classify_in(&table, flow, now);
+        let (result, allowed) = decide(state);
+        if allowed {
+            self.track_in(&mut table, flow, state, now);
+        }
+        result
+    }
+
+    fn track_in(
+        &self,
+        table: &mut HashMap<ConnKey, ConnEntry>,
+        flow: &Flow,
+        state: ConnState,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if state == ConnState::Related {
+            return true; // Pertence à conexão original
+        }
+
+        let key = ConnKey::of(flow);
+        let reversed = key.reversed();
+        let flags = flow.tcp_flags.unwrap_or_default();
+
+        let (key, is_reply) = if table.get(&key).is_some_and(|entry| entry.expires > now) {
+            (key, false)
+        } else if table.get(&reversed).is_some_and(|entry| entry.expires > now) {
+            (reversed, true)
+        } else {
+            if flow.protocol == Protocol::Tcp && flags.contains(TcpFlags::RST) {
+                return true; // RST sem conexão não cria estado
+            }
+            if table.len() >= self.max_entries {
+                table.retain(|_, entry| entry.expires > now);
+                if table.len() >= self.max_entries {
+                    return false;
+                }
+            }
+            let entry = ConnEntry {
+                seen_reply: false,
+                tcp: TcpStage::SynSent,
+                expires: now,
+            };
+            table.insert(key, entry);
+            (key, false)
+        };
+
+        if flow.protocol == Protocol::Tcp && flags.contains(TcpFlags::RST) {
+            table.remove(&key);
+            return true;
+        }
+
+        let entry = table.get_mut(&key).expect("entrada inserida acima");
+        entry.seen_reply |= is_reply;
+        if flow.protocol == Protocol::Tcp {
+            entry.tcp = match entry.tcp {
+                _ if flags.contains(TcpFlags::FIN) => TcpStage::Closing,
+                TcpStage::SynSent if entry.seen_reply => TcpStage::Established,
+                stage => stage,
+            };
+        }
+        entry.expires = now + self.timeout(key.protocol, entry);
+        true
+    }
+
+    fn timeout(&self, protocol: Protocol, entry: &ConnEntry) -> Duration {
+        match protocol {
+            Protocol::Tcp => match entry.tcp {
+                TcpStage::SynSent => self.timeouts.tcp_syn,
+                TcpStage::Established => self.timeouts.tcp_established,
+                TcpStage::Closing => self.timeouts.tcp_closing,
+            },
+            Protocol::Icmp => self.timeouts.icmp,
+            Protocol::Udp | Protocol::Any if entry.seen_reply => self.timeouts.udp_stream,
+            Protocol::Udp | Protocol::Any => self.timeouts.udp,
+        }
+    }
+
+    /// Remove as conexões expiradas e retorna quantas foram removidas
+    pub fn expire(&self, now: DateTime<Utc>) -> usize {
+        let mut table = self.table.lock().unwrap();
+        let before = table.len();
+        table.retain(|_, entry| entry.expires > now);
+        before - table.len()
+    }
+
+    /// Conexões ainda não expiradas
+    pub fn entries(&self, now: DateTime<Utc>) -> Vec<ConnInfo> {
+        let table = self.table.lock().unwrap();
+        table
+            .iter()
+            .filter(|(_, entry)| entry.expires > now)
+            .map(|(key, entry)| ConnInfo {
+                protocol: key.protocol,
+                src_ip: key.src_ip,
+                src_port: key.src_port,
+                dst_ip: key.dst_ip,
+                dst_port: key.dst_port,
+                state: if entry.seen_reply {
+                    ConnState::Established
+                } else {
+                    ConnState::New
+                },
+                expires: entry.expires,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.table.lock().unwrap().clear();
+    }
+}
+
+/// Estado do fluxo segundo `table`
+fn classify_in(table: &HashMap<ConnKey, ConnEntry>, flow: &Flow, now: DateTime<Utc>) -> ConnState {
+    let key = ConnKey::of(flow);
+    let live = |key: &ConnKey| table.get(key).filter(|entry| entry.expires > now);
+
+    if let Some(entry) = live(&key) {
+        return if entry.seen_reply {
+            ConnState::Established
+        } else {
+            ConnState::New
+        };
+    }
+    if live(&key.reversed()).is_some() {
+        return ConnState::Established;
+    }
+
+    // Sem o conteúdo do ICMP, qualquer ICMP entre os hosts de uma
+    // conexão TCP/UDP ativa é tratado como relacionado a ela
+    let related = flow.protocol == Protocol::Icmp
+        && table.iter().any(|(other, entry)| {
+            other.protocol != Protocol::Icmp
+                && entry.expires > now
+                && ((other.src_ip == flow.src_ip && other.dst_ip == flow.dst_ip)
+                    || (other.src_ip == flow.dst_ip && other.dst_ip == flow.src_ip))
+        });
+    if related {
+        ConnState::Related
+    } else {
+        ConnState::New
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::writeRules::Direction;
+
+    fn t0() -> DateTime<Utc> {
+        "2026-10-18T12:00:00Z".parse().unwrap()
+    }
+
+    fn tcp(client_to_server: bool, flags: u8) -> Flow {
+        let (client, server) = ("192.168.1.7".parse().unwrap(), "10.0.0.1".parse().unwrap());
+        let (src_ip, dst_ip, src_port, dst_port) = if client_to_server {
+            (client, server, 40000, 22)
+        } else {
+            (server, client, 22, 40000)
+        };
+        Flow {
+            direction: Direction::Inbound,
+            protocol: Protocol::Tcp,
+            src_ip,
+            dst_ip,
+            src_port: Some(src_port),
+            dst_port: Some(dst_port),
+            bytes: 60,
+            tcp_flags: Some(TcpFlags(flags)),
+        }
+    }
+
+    /// Avalia permitindo tudo, como o motor faz para um veredito Allow
+    fn step(tracker: &ConnTracker, flow: &Flow, now: DateTime<Utc>) -> ConnState {
+        tracker.classify_and_track(flow, now, |state| (state, true))
+    }
+
+    #[test]
+    fn handshake_fin_e_fechamento() {
+        let tracker = ConnTracker::default();
+        let timeouts = ConnTimeouts::default();
+        let now = t0();
+
+        assert_eq!(step(&tracker, &tcp(true, TcpFlags::SYN), now), ConnState::New);
+        assert_eq!(tracker.entries(now)[0].expires, now + timeouts.tcp_syn);
+        assert_eq!(tracker.classify(&tcp(true, TcpFlags::ACK), now), ConnState::New);
+
+        let syn_ack = TcpFlags::SYN | TcpFlags::ACK;
+        assert_eq!(step(&tracker, &tcp(false, syn_ack), now), ConnState::Established);
+        assert_eq!(step(&tracker, &tcp(true, TcpFlags::ACK), now), ConnState::Established);
+        let info = &tracker.entries(now)[0];
+        assert_eq!((info.state, info.expires), (ConnState::Established, now + timeouts.tcp_established));
+
+        // FIN encurta o tempo de vida; a conexão segue estabelecida até expirar
+        let fin = TcpFlags::FIN | TcpFlags::ACK;
+        assert_eq!(step(&tracker, &tcp(true, fin), now), ConnState::Established);
+        assert_eq!(tracker.entries(now)[0].expires, now + timeouts.tcp_closing);
+        let after = now + timeouts.tcp_closing;
+        assert_eq!(tracker.classify(&tcp(false, TcpFlags::ACK), after), ConnState::New);
+    }
+
+    #[test]
+    fn rst_encerra_a_conexao() {
+        let tracker = ConnTracker::default();
+        step(&tracker, &tcp(true, TcpFlags::SYN), t0());
+        step(&tracker, &tcp(false, TcpFlags::SYN | TcpFlags::ACK), t0());
+        step(&tracker, &tcp(false, TcpFlags::RST), t0());
+        assert!(tracker.is_empty());
+
+        // RST sem conexão não cria estado
+        step(&tracker, &tcp(true, TcpFlags::RST), t0());
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn fluxo_negado_nao_e_registrado() {
+        let tracker = ConnTracker::default();
+        let state = tracker.classify_and_track(&tcp(true, TcpFlags::SYN), t0(), |state| (state, false));
+        assert_eq!(state, ConnState::New);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn conexoes_expiram_sem_trafego() {
+        let timeouts = ConnTimeouts::default();
+        let tracker = ConnTracker::new(timeouts);
+        let mut dns = tcp(true, 0);
+        dns.protocol = Protocol::Udp;
+        dns.tcp_flags = None;
+        let mut reply = tcp(false, 0);
+        reply.protocol = Protocol::Udp;
+        reply.tcp_flags = None;
+
+        step(&tracker, &dns, t0());
+        let expiry = t0() + timeouts.udp;
+        assert_eq!(tracker.classify(&reply, expiry - Duration::seconds(1)), ConnState::Established);
+        assert_eq!(tracker.classify(&reply, expiry), ConnState::New);
+
+        // Depois da resposta vale o tempo de fluxo UDP
+        step(&tracker, &reply, t0());
+        assert_eq!(tracker.entries(t0())[0].expires, t0() + timeouts.udp_stream);
+        assert_eq!(tracker.expire(t0() + timeouts.udp_stream), 1);
+        assert!(tracker.is_empty());
+    }
+
+    #[test]
+    fn icmp_entre_hosts_de_conexao_ativa_e_relacionado() {
+        let tracker = ConnTracker::default();
+        step(&tracker, &tcp(true, TcpFlags::SYN), t0());
+
+        let mut icmp = tcp(false, 0);
+        icmp.protocol = Protocol::Icmp;
+        icmp.src_port = None;
+        icmp.dst_port = None;
+        icmp.tcp_flags = None;
+        assert_eq!(step(&tracker, &icmp, t0()), ConnState::Related);
+        assert_eq!(tracker.len(), 1);
+        let later = t0() + ConnTimeouts::default().tcp_syn;
+        assert_eq!(tracker.classify(&icmp, later), ConnState::New);
+    }
+}
@@ -0,0 +1,290 @@
+use std::{net::IpAddr, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
+
+use super::connTrack::{ConnState, ConnTracker, TcpFlags};
+use super::ipNet::IpNet;
+use super::objectSets::ResolvedRule;
+use super::portSpec::PortSpec;
+use super::ruleStats::{RuleStats, StatsSnapshot};
+use super::writeRules::{Action, Direction, FirewallConfig, Protocol};
+
+/// Veredito produzido pela avaliação de um fluxo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    Deny,
+    Log,
+}
+
+impl From<Action> for Verdict {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Allow => Verdict::Allow,
+            Action::Deny => Verdict::Deny,
+            Action::Log => Verdict::Log,
+        }
+    }
+}
+
+/// Fluxo de rede a ser avaliado contra as regras
+#[derive(Debug, Clone)]
+pub struct Flow {
+    pub direction: Direction,
+    pub protocol: Protocol,
+    pub src_ip: IpAddr,
+    pub dst_ip: IpAddr,
+    pub src_port: Option<u16>, // Ausente para protocolos sem porta (ICMP)
+    pub dst_port: Option<u16>,
+    pub bytes: u64,            // Tamanho do pacote/fluxo, somado nos contadores da regra
+    pub tcp_flags: Option<TcpFlags>, // SYN/FIN/RST para o rastreamento de conexões; None se desconhecido
+}
+
+/// Resultado da avaliação de um fluxo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decision {
+    pub verdict: Verdict,
+    pub rule: Option<String>, // None quando a política padrão foi aplicada
+    pub log: bool,            // Regra pediu registro do fluxo (`log: true`)
+    pub state: ConnState,     // Estado da conexão no momento da avaliação
+}
+
+/// Motor de avaliação de regras com semântica first-match
+pub struct RuleEngine {
+    config: FirewallConfig,
+    resolved: Vec<ResolvedRule>, // Regras ativas em ordem de prioridade
+    timezone: Tz,
+    default_policy: Verdict,
+    stats: RuleStats,
+    conntrack: Arc<ConnTracker>,
+}
+
+impl RuleEngine {
+    /// Cria o motor com política padrão `Deny`
+    pub fn new(config: FirewallConfig) -> Self {
+        Self {
+            resolved: config.resolved_rules(),
+            // Um fuso inválido é rejeitado no carregamento; aqui cai para UTC
+            timezone: config.timezone().unwrap_or(Tz::UTC),
+            stats: RuleStats::new(&config),
+            config,
+            default_policy: Verdict::Deny,
+            conntrack: Arc::new(ConnTracker::default()),
+        }
+    }
+
+    /// Usa a tabela de conexões informada (ex.: a do motor anterior, na recarga)
+    pub fn with_conntrack(mut self, conntrack: Arc<ConnTracker>) -> Self {
+        self.conntrack = conntrack;
+        self
+    }
+
+    /// Continua a contagem de `previous` para as regras com o mesmo nome
+    ///
+    /// Nomes repetidos são pareados pela ordem em que aparecem no arquivo.
+    pub fn with_stats_from(mut self, previous: &RuleEngine) -> Self {
+        self.stats = RuleStats::inherit(&self.config, &previous.stats);
+        self
+    }
+
+    /// Define o veredito aplicado quando nenhuma regra casa com o fluxo
+    pub fn with_default_policy(mut self, policy: Verdict) -> Self {
+        self.default_policy = policy;
+        self
+    }
+
+    pub fn config(&self) -> &FirewallConfig {
+        &self.config
+    }
+
+    pub fn default_policy(&self) -> Verdict {
+        self.default_policy
+    }
+
+    /// Regras ativas, na ordem em que são avaliadas
+    pub fn rules(&self) -> &[ResolvedRule] {
+        &self.resolved
+    }
+
+    /// Acertos, bytes e último casamento de cada regra
+    pub fn stats(&self) -> StatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    pub fn reset_stats(&self) {
+        self.stats.reset();
+    }
+
+    /// Tabela de conexões consultada por regras com `state`
+    pub fn conntrack(&self) -> &Arc<ConnTracker> {
+        &self.conntrack
+    }
+
+    /// Avalia o fluxo e retorna o veredito da primeira regra que casar
+    pub fn evaluate(&self, flow: &Flow) -> Decision {
+        self.evaluate_at(flow, Utc::now())
+    }
+
+    /// Avalia o fluxo como se fosse o instante `now`
+    ///
+    /// Regras expiradas ou fora da janela de horário são ignoradas. Fluxos
+    /// que não forem negados são registrados na tabela de conexões.
+    pub fn evaluate_at(&self, flow: &Flow, now: DateTime<Utc>) -> Decision {
+        self.conntrack.classify_and_track(flow, now, |state| {
+            let decision = self.decide(flow, state, now);
+            let allowed = decision.verdict != Verdict::Deny;
+            (decision, allowed)
+        })
+    }
+
+    fn decide(&self, flow: &Flow, state: ConnState, now: DateTime<Utc>) -> Decision {
+        for rule in &self.resolved {
+            if rule.is_active_at(now, self.timezone) && rule_matches(rule, flow, state) {
+                if let Some(counter) = self.stats.counter(rule.index) {
+                    counter.record(flow.bytes, now);
+                }
+                return Decision {
+                    verdict: rule.action.into(),
+                    rule: Some(rule.name.clone()),
+                    log: rule.log,
+                    state,
+                };
+            }
+        }
+
+        self.stats.default_policy().record(flow.bytes, now);
+        Decision {
+            verdict: self.default_policy,
+            rule: None,
+            log: false,
+            state,
+        }
+    }
+}
+
+/// Verifica se todos os campos presentes na regra casam com o fluxo
+fn rule_matches(rule: &ResolvedRule, flow: &Flow, state: ConnState) -> bool {
+    rule.direction.matches(flow.direction)
+        && rule.protocol.matches(flow.protocol)
+        && addr_matches(rule.src_ip.as_deref(), flow.src_ip)
+        && addr_matches(rule.dst_ip.as_deref(), flow.dst_ip)
+        && port_matches(rule.src_port.as_ref(), flow.src_port)
+        && port_matches(rule.dst_port.as_ref(), flow.dst_port)
+        && rule.state.as_ref().is_none_or(|states| states.contains(&state))
+}
+
+/// Campo ausente casa com qualquer endereço; caso contrário verifica os prefixos CIDR
+fn addr_matches(rule_nets: Option<&[IpNet]>, addr: IpAddr) -> bool {
+    rule_nets.is_none_or(|nets| nets.iter().any(|net| net.contains(addr)))
+}
+
+/// Campo ausente casa com qualquer porta; fluxos sem porta nunca casam com uma restrição
+fn port_matches(rule_ports: Option<&PortSpec>, port: Option<u16>) -> bool {
+    match (rule_ports, port) {
+        (None, _) => true,
+        (Some(spec), Some(port)) => spec.contains(port),
+        (Some(_), None) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn engine(rules: serde_json::Value) -> RuleEngine {
+        RuleEngine::new(serde_json::from_value(json!({ "rules": rules })).unwrap())
+    }
+
+    fn flow(protocol: Protocol, src: &str, dst_port: Option<u16>) -> Flow {
+        Flow {
+            direction: Direction::Inbound,
+            protocol,
+            src_ip: src.parse().unwrap(),
+            dst_ip: "10.0.0.1".parse().unwrap(),
+            src_port: dst_port.map(|_| 40000),
+            dst_port,
+            bytes: 100,
+            tcp_flags: None,
+        }
+    }
+
+    fn rule(name: &str, action: &str, protocol: &str) -> serde_json::Value {
+        json!({ "name": name, "action": action, "direction": "inbound", "protocol": protocol })
+    }
+
+    #[test]
+    fn primeira_regra_que_casa_vence() {
+        let engine = engine(json!([
+            rule("Bloquear SSH", "deny", "tcp"),
+            rule("Permitir TCP", "allow", "tcp"),
+        ]));
+        let decision = engine.evaluate(&flow(Protocol::Tcp, "192.168.1.7", Some(22)));
+        assert_eq!(decision.verdict, Verdict::Deny);
+        assert_eq!(decision.rule.as_deref(), Some("Bloquear SSH"));
+    }
+
+    #[test]
+    fn politica_padrao_quando_nada_casa() {
+        let engine = engine(json!([rule("Permitir UDP", "allow", "udp")]));
+        let tcp = flow(Protocol::Tcp, "192.168.1.7", Some(80));
+
+        let decision = engine.evaluate(&tcp);
+        assert_eq!((decision.verdict, decision.rule), (Verdict::Deny, None));
+
+        let engine = engine.with_default_policy(Verdict::Allow);
+        assert_eq!(engine.evaluate(&tcp).verdict, Verdict::Allow);
+        assert_eq!(engine.stats().default_policy.hits, 2);
+    }
+
+    #[test]
+    fn casa_protocolo_porta_e_cidr() {
+        let mut ssh = rule("SSH da LAN", "allow", "tcp");
+        ssh["src_ip"] = json!("192.168.1.0/24");
+        ssh["dst_port"] = json!("22-23");
+        let engine = engine(json!([ssh]));
+
+        let allowed = |protocol, src, port| engine.evaluate(&flow(protocol, src, port)).verdict == Verdict::Allow;
+        assert!(allowed(Protocol::Tcp, "192.168.1.7", Some(22)));
+        assert!(allowed(Protocol::Tcp, "192.168.1.7", Some(23)));
+        assert!(!allowed(Protocol::Tcp, "192.168.1.7", Some(24)));
+        assert!(!allowed(Protocol::Tcp, "192.168.2.7", Some(22)));
+        assert!(!allowed(Protocol::Udp, "192.168.1.7", Some(22)));
+        assert!(!allowed(Protocol::Tcp, "192.168.1.7", None));
+        assert!(!allowed(Protocol::Tcp, "fe80::1", Some(22)));
+    }
+
+    #[test]
+    fn protocolo_any_casa_com_todos() {
+        let engine = engine(json!([rule("Registrar tudo", "log", "any")]));
+        for protocol in [Protocol::Tcp, Protocol::Udp, Protocol::Icmp] {
+            let port = (protocol != Protocol::Icmp).then_some(53);
+            let decision = engine.evaluate(&flow(protocol, "8.8.8.8", port));
+            assert_eq!(decision.verdict, Verdict::Log, "{}", protocol);
+        }
+    }
+
+    #[test]
+    fn regras_com_mesmo_nome_tem_contadores_separados() {
+        let mut ssh = rule("Duplicada", "allow", "tcp");
+        ssh["dst_port"] = json!(22);
+        let mut dns = rule("Duplicada", "allow", "udp");
+        dns["dst_port"] = json!(53);
+        let engine = engine(json!([ssh, dns]));
+
+        engine.evaluate(&flow(Protocol::Tcp, "192.168.1.7", Some(22)));
+        engine.evaluate(&flow(Protocol::Udp, "192.168.1.7", Some(53)));
+        engine.evaluate(&flow(Protocol::Udp, "192.168.1.7", Some(53)));
+
+        let hits = |engine: &RuleEngine| -> Vec<(usize, u64)> {
+            engine.stats().rules.iter().map(|entry| (entry.index, entry.counters.hits)).collect()
+        };
+        assert_eq!(hits(&engine), vec![(0, 1), (1, 2)]);
+
+        // Na recarga, a n-ésima "Duplicada" continua a contagem da n-ésima anterior
+        let reloaded = RuleEngine::new(engine.config().clone()).with_stats_from(&engine);
+        reloaded.evaluate(&flow(Protocol::Tcp, "192.168.1.7", Some(22)));
+        assert_eq!(hits(&reloaded), vec![(0, 2), (1, 2)]);
+    }
+}
@@ -0,0 +1,615 @@
+use std::fmt::Write;
+
+use chrono::Weekday;
+use chrono_tz::Tz;
+
+use super::connTrack::ConnState;
+use super::evalRules::Verdict;
+use super::ipNet::IpNet;
+use super::objectSets::ResolvedRule;
+use super::portSpec::{PortRange, PortSpec};
+use super::schedule::MINUTES_PER_DAY;
+use super::writeRules::{Action, Direction, FirewallConfig, Protocol};
+
+// Limite do --log-prefix do iptables (o nftables aceita mais, mas usamos o mesmo)
+const LOG_PREFIX_MAX: usize = 29;
+
+/// Família de endereços de uma regra exportada ou importada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Family {
+    V4,
+    V6,
+}
+
+/// Janela de horário exportável, sem atravessar a meia-noite
+///
+/// `days` vazio significa todos os dias; `end` é exclusivo.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TimeWindow {
+    days: Vec<Weekday>,
+    start: u16,
+    end: u16,
+}
+
+/// Gera um ruleset nftables (`nft -f`) equivalente ao conjunto de regras
+///
+/// As regras são emitidas na ordem de avaliação (prioridade) dentro de uma
+/// tabela `inet firewall`, preservando a semântica first-match; grupos
+/// desabilitados não são emitidos. Regras `log` registram e aceitam o
+/// tráfego, como o veredito `Log` do avaliador.
+pub fn gerar_nftables(config: &FirewallConfig, default_policy: Verdict) -> String {
+    let resolved = config.resolved_rules();
+    let timezone = config.timezone().unwrap_or(Tz::UTC);
+    let mut out = String::new();
+    let policy = match default_policy {
+        Verdict::Deny => "drop",
+        Verdict::Allow | Verdict::Log => "accept",
+    };
+
+    out.push_str("#!/usr/sbin/nft -f\n");
+    out.push_str("# Gerado a partir de FirewallConfig - não edite manualmente\n");
+    push_timezone_note(&mut out, config, &resolved);
+    out.push('\n');
+    out.push_str("table inet firewall {\n");
+
+    for (index, chain) in ["input", "output"].iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "    chain {} {{", chain);
+        let _ = writeln!(
+            out,
+            "        type filter hook {} priority 0; policy {};",
+            chain, policy
+        );
+
+        for rule in resolved.iter().filter(|rule| in_chain(rule, chain)) {
+            match nft_matches(rule) {
+                Ok(matches) => {
+                    for time in nft_time_matches(rule, timezone) {
+                        let mut line = matches.clone();
+                        for part in [time, nft_verdict(rule)] {
+                            if !line.is_empty() && !part.is_empty() {
+                                line.push(' ');
+                            }
+                            line.push_str(&part);
+                        }
+                        let _ = writeln!(
+                            out,
+                            "        {} comment \"{}\"",
+                            line,
+                            escape(&rule.name)
+                        );
+                    }
+                }
+                Err(reason) => {
+                    let _ = writeln!(out, "        # regra '{}' ignorada: {}", rule.name, reason);
+                }
+            }
+        }
+        out.push_str("    }\n");
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Gera um script `iptables-restore` (IPv4) para a tabela filter
+pub fn gerar_iptables_restore(config: &FirewallConfig, default_policy: Verdict) -> String {
+    gerar_restore(config, default_policy, Family::V4)
+}
+
+/// Gera um script `ip6tables-restore` (IPv6) para a tabela filter
+pub fn gerar_ip6tables_restore(config: &FirewallConfig, default_policy: Verdict) -> String {
+    gerar_restore(config, default_policy, Family::V6)
+}
+
+fn gerar_restore(config: &FirewallConfig, default_policy: Verdict, family: Family) -> String {
+    let resolved = config.resolved_rules();
+    let timezone = config.timezone().unwrap_or(Tz::UTC);
+    let mut out = String::new();
+    let policy = match default_policy {
+        Verdict::Deny => "DROP",
+        Verdict::Allow | Verdict::Log => "ACCEPT",
+    };
+
+    out.push_str("# Gerado a partir de FirewallConfig - não edite manualmente\n");
+    push_timezone_note(&mut out, config, &resolved);
+    out.push_str("*filter\n");
+    let _ = writeln!(out, ":INPUT {} [0:0]", policy);
+    let _ = writeln!(out, ":OUTPUT {} [0:0]", policy);
+
+    for (chain, nft_chain) in [("INPUT", "input"), ("OUTPUT", "output")] {
+        for rule in resolved.iter().filter(|rule| in_chain(rule, nft_chain)) {
+            match rule_family(rule) {
+                Err(reason) => {
+                    let _ = writeln!(out, "# regra '{}' ignorada: {}", rule.name, reason);
+                }
+                // Regra restrita à outra família: pertence ao outro script
+                Ok(Some(rule_family)) if rule_family != family => {}
+                Ok(_) => {
+                    for matches in iptables_matches(rule, family) {
+                        for time in iptables_time_matches(rule, timezone) {
+                            for target in iptables_targets(rule) {
+                                let _ = writeln!(
+                                    out,
+                                    "-A {}{}{} -m comment --comment \"{}\" -j {}",
+                                    chain,
+                                    matches,
+                                    time,
+                                    escape(&rule.name),
+                                    target
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    out.push_str("COMMIT\n");
+    out
+}
+
+/// nft (`meta hour`) e iptables (`--kerneltz`) usam o fuso local do host
+fn push_timezone_note(out: &mut String, config: &FirewallConfig, resolved: &[ResolvedRule]) {
+    if resolved.iter().any(ResolvedRule::is_timed) {
+        let timezone = config.timezone().unwrap_or(Tz::UTC);
+        let _ = writeln!(
+            out,
+            "# Horários no fuso {}: o host precisa usar o mesmo fuso local",
+            timezone
+        );
+    }
+}
+
+fn in_chain(rule: &ResolvedRule, chain: &str) -> bool {
+    match rule.direction {
+        Direction::Inbound => chain == "input",
+        Direction::Outbound => chain == "output",
+        Direction::Any => true,
+    }
+}
+
+/// Família exigida pelos endereços da regra; `Err` com o motivo se a
+/// regra não puder ser exportada
+fn rule_family(rule: &ResolvedRule) -> Result<Option<Family>, &'static str> {
+    let family = |net: &IpNet| if net.is_ipv4() { Family::V4 } else { Family::V6 };
+    if rule.state.as_ref().is_some_and(Vec::is_empty) {
+        return Err("lista de estados de conexão vazia");
+    }
+    // Sem portas no pacote a regra nunca casa no avaliador; exportar sem elas a ampliaria
+    if rule.protocol == Protocol::Icmp && (rule.src_port.is_some() || rule.dst_port.is_some()) {
+        return Err("protocolo icmp não tem portas");
+    }
+    let mut required = None;
+    for nets in rule.src_ip.iter().chain(&rule.dst_ip) {
+        if nets.is_empty() {
+            return Err("conjunto de endereços vazio");
+        }
+        for net in nets {
+            match required {
+                Some(previous) if previous != family(net) => {
+                    return Err("mistura endereços IPv4 e IPv6")
+                }
+                _ => required = Some(family(net)),
+            }
+        }
+    }
+    Ok(required)
+}
+
+fn nft_matches(rule: &ResolvedRule) -> Result<String, &'static str> {
+    let family = rule_family(rule)?;
+    let mut parts = Vec::new();
+
+    if let Some(nets) = &rule.src_ip {
+        parts.push(format!("{} saddr {}", nft_family(&nets[0]), nft_nets(nets)));
+    }
+    if let Some(nets) = &rule.dst_ip {
+        parts.push(format!("{} daddr {}", nft_family(&nets[0]), nft_nets(nets)));
+    }
+
+    let has_ports = rule.src_port.is_some() || rule.dst_port.is_some();
+    let port_proto = match rule.protocol {
+        Protocol::Tcp => Some("tcp"),
+        Protocol::Udp => Some("udp"),
+        Protocol::Icmp => {
+            parts.push(
+                match family {
+                    Some(Family::V4) => "meta l4proto icmp",
+                    Some(Family::V6) => "meta l4proto ipv6-icmp",
+                    None => "meta l4proto { icmp, ipv6-icmp }",
+                }
+                .to_string(),
+            );
+            None
+        }
+        Protocol::Any if has_ports => {
+            // `th` casa a porta de qualquer protocolo de transporte
+            parts.push("meta l4proto { tcp, udp }".to_string());
+            Some("th")
+        }
+        Protocol::Any => None,
+    };
+
+    match port_proto {
+        Some(proto) if has_ports => {
+            if let Some(ports) = &rule.src_port {
+                parts.push(format!("{} sport {}", proto, nft_ports(ports)));
+            }
+            if let Some(ports) = &rule.dst_port {
+                parts.push(format!("{} dport {}", proto, nft_ports(ports)));
+            }
+        }
+        Some(proto) => parts.push(format!("meta l4proto {}", proto)),
+        None => {}
+    }
+
+    if let Some(states) = &rule.state {
+        parts.push(format!("ct state {}", state_names(states).join(",")));
+    }
+
+    Ok(parts.join(" "))
+}
+
+fn nft_family(net: &IpNet) -> &'static str {
+    if net.is_ipv4() {
+        "ip"
+    } else {
+        "ip6"
+    }
+}
+
+fn nft_nets(nets: &[IpNet]) -> String {
+    match nets {
+        [net] => net.to_string(),
+        nets => {
+            let items: Vec<String> = nets.iter().map(|net| net.to_string()).collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+    }
+}
+
+fn nft_ports(ports: &PortSpec) -> String {
+    match ports.ranges() {
+        [range] => range.to_string(),
+        ranges => {
+            let items: Vec<String> = ranges.iter().map(|range| range.to_string()).collect();
+            format!("{{ {} }}", items.join(", "))
+        }
+    }
+}
+
+fn nft_verdict(rule: &ResolvedRule) -> String {
+    let log = format!("log prefix \"{}\"", escape(&log_prefix(&rule.name)));
+    match (rule.action, rule.log) {
+        (Action::Allow, false) => "accept".to_string(),
+        (Action::Deny, false) => "drop".to_string(),
+        (Action::Allow, true) | (Action::Log, _) => format!("{} accept", log),
+        (Action::Deny, true) => format!("{} drop", log),
+    }
+}
+
+/// Janelas da regra; `[None]` quando não há `schedule`
+///
+/// Uma faixa que atravessa a meia-noite vira duas janelas, com a parte da
+/// madrugada atribuída ao dia seguinte, como na avaliação.
+fn time_windows(rule: &ResolvedRule) -> Vec<Option<TimeWindow>> {
+    let Some(schedule) = &rule.schedule else {
+        return vec![None];
+    };
+    let days: Vec<Weekday> = schedule.days.iter().map(|day| day.weekday()).collect();
+    if schedule.hours.is_empty() {
+        return vec![Some(TimeWindow {
+            days,
+            start: 0,
+            end: MINUTES_PER_DAY,
+        })];
+    }
+
+    let mut windows = Vec::new();
+    for range in &schedule.hours {
+        if range.wraps() {
+            windows.push(Some(TimeWindow {
+                days: days.clone(),
+                start: range.start(),
+                end: MINUTES_PER_DAY,
+            }));
+            // "22:00-00:00" termina exatamente à meia-noite
+            if range.end() > 0 {
+                windows.push(Some(TimeWindow {
+                    days: days.iter().map(|day| day.succ()).collect(),
+                    start: 0,
+                    end: range.end(),
+                }));
+            }
+        } else {
+            windows.push(Some(TimeWindow {
+                days: days.clone(),
+                start: range.start(),
+                end: range.end(),
+            }));
+        }
+    }
+    windows
+}
+
+/// Condições `meta day`/`meta hour`/`meta time`, uma linha por janela
+fn nft_time_matches(rule: &ResolvedRule, timezone: Tz) -> Vec<String> {
+    time_windows(rule)
+        .into_iter()
+        .map(|window| {
+            let mut parts = Vec::new();
+            if let Some(window) = window {
+                match window.days.as_slice() {
+                    [] => {}
+                    [day] => parts.push(format!("meta day \"{}\"", day_name(*day))),
+                    days => {
+                        let names: Vec<String> =
+                            days.iter().map(|day| format!("\"{}\"", day_name(*day))).collect();
+                        parts.push(format!("meta day {{ {} }}", names.join(", ")));
+                    }
+                }
+                if window.start != 0 || window.end != MINUTES_PER_DAY {
+                    parts.push(format!(
+                        "meta hour \"{}\"-\"{}\"",
+                        format_start(window.start),
+                        format_stop(window.end)
+                    ));
+                }
+            }
+            if let Some(expires_at) = &rule.expires_at {
+                parts.push(format!(
+                    "meta time < \"{}\"",
+                    expires_at.with_timezone(&timezone).format("%Y-%m-%d %H:%M:%S")
+                ));
+            }
+            parts.join(" ")
+        })
+        .collect()
+}
+
+/// Módulo `-m time`, uma variação por janela; vazio quando não há condição
+fn iptables_time_matches(rule: &ResolvedRule, timezone: Tz) -> Vec<String> {
+    if !rule.is_timed() {
+        return vec![String::new()];
+    }
+
+    time_windows(rule)
+        .into_iter()
+        .map(|window| {
+            let mut line = " -m time".to_string();
+            if let Some(window) = window {
+                if window.start != 0 || window.end != MINUTES_PER_DAY {
+                    let _ = write!(
+                        line,
+                        " --timestart {} --timestop {}",
+                        format_start(window.start),
+                        format_stop(window.end)
+                    );
+                }
+                if !window.days.is_empty() {
+                    let days: Vec<String> = window.days.iter().map(|day| day.to_string()).collect();
+                    let _ = write!(line, " --weekdays {}", days.join(","));
+                }
+            }
+            if let Some(expires_at) = &rule.expires_at {
+                let _ = write!(
+                    line,
+                    " --datestop {}",
+                    expires_at.with_timezone(&timezone).format("%Y-%m-%dT%H:%M:%S")
+                );
+            }
+            line.push_str(" --kerneltz");
+            line
+        })
+        .collect()
+}
+
+fn day_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+fn format_start(minutes: u16) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+/// nft e iptables comparam o fim de forma inclusiva; `end` é exclusivo
+fn format_stop(minutes: u16) -> String {
+    let last = minutes - 1;
+    format!("{:02}:{:02}:59", last / 60, last % 60)
+}
+
+/// Combinações de `-p`/endereços/portas; protocolo `any` com portas gera tcp e udp
+fn iptables_matches(rule: &ResolvedRule, family: Family) -> Vec<String> {
+    let has_ports = rule.src_port.is_some() || rule.dst_port.is_some();
+    let protocols: Vec<Option<&str>> = match rule.protocol {
+        Protocol::Tcp => vec![Some("tcp")],
+        Protocol::Udp => vec![Some("udp")],
+        Protocol::Icmp if family == Family::V4 => vec![Some("icmp")],
+        Protocol::Icmp => vec![Some("ipv6-icmp")],
+        Protocol::Any if has_ports => vec![Some("tcp"), Some("udp")],
+        Protocol::Any => vec![None],
+    };
+
+    protocols
+        .into_iter()
+        .map(|protocol| {
+            let mut line = String::new();
+            if let Some(protocol) = protocol {
+                let _ = write!(line, " -p {}", protocol);
+            }
+            if let Some(nets) = &rule.src_ip {
+                let _ = write!(line, " -s {}", iptables_nets(nets));
+            }
+            if let Some(nets) = &rule.dst_ip {
+                let _ = write!(line, " -d {}", iptables_nets(nets));
+            }
+            if let Some(ports) = &rule.src_port {
+                line.push_str(&iptables_ports(ports, "sport"));
+            }
+            if let Some(ports) = &rule.dst_port {
+                line.push_str(&iptables_ports(ports, "dport"));
+            }
+            if let Some(states) = &rule.state {
+                let states: Vec<String> = state_names(states)
+                    .iter()
+                    .map(|state| state.to_ascii_uppercase())
+                    .collect();
+                let _ = write!(line, " -m conntrack --ctstate {}", states.join(","));
+            }
+            line
+        })
+        .collect()
+}
+
+/// Nomes dos estados, sem repetição e em ordem estável
+fn state_names(states: &[ConnState]) -> Vec<&'static str> {
+    let mut states = states.to_vec();
+    states.sort();
+    states.dedup();
+    states.into_iter().map(ConnState::as_str).collect()
+}
+
+/// O iptables expande `-s a,b` em uma regra por endereço
+fn iptables_nets(nets: &[IpNet]) -> String {
+    nets.iter().map(|net| net.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn iptables_ports(ports: &PortSpec, option: &str) -> String {
+    let format_range = |range: &PortRange| {
+        if range.start == range.end {
+            range.start.to_string()
+        } else {
+            format!("{}:{}", range.start, range.end)
+        }
+    };
+
+    match ports.ranges() {
+        [range] => format!(" --{} {}", option, format_range(range)),
+        ranges => {
+            let items: Vec<String> = ranges.iter().map(format_range).collect();
+            format!(" -m multiport --{}s {}", option, items.join(","))
+        }
+    }
+}
+
+/// Alvos emitidos para a regra; LOG não é terminal no iptables, então é
+/// seguido do alvo final para manter a semântica first-match
+fn iptables_targets(rule: &ResolvedRule) -> Vec<String> {
+    let log = format!("LOG --log-prefix \"{}\"", escape(&log_prefix(&rule.name)));
+    match (rule.action, rule.log) {
+        (Action::Allow, false) => vec!["ACCEPT".to_string()],
+        (Action::Deny, false) => vec!["DROP".to_string()],
+        (Action::Allow, true) | (Action::Log, _) => vec![log, "ACCEPT".to_string()],
+        (Action::Deny, true) => vec![log, "DROP".to_string()],
+    }
+}
+
+fn log_prefix(name: &str) -> String {
+    let mut prefix = String::new();
+    for c in name.chars() {
+        // Reserva espaço para o sufixo ": "
+        if prefix.len() + c.len_utf8() > LOG_PREFIX_MAX - 2 {
+            break;
+        }
+        prefix.push(c);
+    }
+    prefix.push_str(": ");
+    prefix
+}
+
+/// Aspas duplas não são aceitas dentro de strings do nft nem do iptables-restore
+fn escape(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // Montada sem validação: o ping com porta só chega aqui por configuração feita em código
+    fn exemplo() -> FirewallConfig {
+        serde_json::from_value(json!({ "rules": [
+            { "name": "Permitir SSH", "action": "allow", "direction": "inbound", "protocol": "tcp",
+              "src_ip": "192.168.1.0/24", "dst_port": 22, "state": ["new", "established"] },
+            { "name": "Web", "action": "allow", "direction": "inbound", "protocol": "any",
+              "dst_port": [80, "8000-8100"] },
+            { "name": "Logar DNS", "action": "log", "direction": "outbound", "protocol": "udp",
+              "dst_ip": "2001:4860:4860::8888", "dst_port": 53 },
+            { "name": "Bloquear ping", "action": "deny", "direction": "any", "protocol": "icmp",
+              "src_ip": "10.0.0.0/8" },
+            { "name": "Ping com porta", "action": "allow", "direction": "inbound", "protocol": "icmp",
+              "dst_port": 7 }
+        ]}))
+        .unwrap()
+    }
+
+    #[test]
+    fn gera_nftables() {
+        let expected = r#"#!/usr/sbin/nft -f
+# Gerado a partir de FirewallConfig - não edite manualmente
+
+table inet firewall {
+    chain input {
+        type filter hook input priority 0; policy drop;
+        ip saddr 192.168.1.0/24 tcp dport 22 ct state new,established accept comment "Permitir SSH"
+        meta l4proto { tcp, udp } th dport { 80, 8000-8100 } accept comment "Web"
+        ip saddr 10.0.0.0/8 meta l4proto icmp drop comment "Bloquear ping"
+        # regra 'Ping com porta' ignorada: protocolo icmp não tem portas
+    }
+
+    chain output {
+        type filter hook output priority 0; policy drop;
+        ip6 daddr 2001:4860:4860::8888 udp dport 53 log prefix "Logar DNS: " accept comment "Logar DNS"
+        ip saddr 10.0.0.0/8 meta l4proto icmp drop comment "Bloquear ping"
+    }
+}
+"#;
+        assert_eq!(gerar_nftables(&exemplo(), Verdict::Deny), expected);
+    }
+
+    #[test]
+    fn gera_iptables_restore() {
+        let expected = r#"# Gerado a partir de FirewallConfig - não edite manualmente
+*filter
+:INPUT DROP [0:0]
+:OUTPUT DROP [0:0]
+-A INPUT -p tcp -s 192.168.1.0/24 --dport 22 -m conntrack --ctstate NEW,ESTABLISHED -m comment --comment "Permitir SSH" -j ACCEPT
+-A INPUT -p tcp -m multiport --dports 80,8000:8100 -m comment --comment "Web" -j ACCEPT
+-A INPUT -p udp -m multiport --dports 80,8000:8100 -m comment --comment "Web" -j ACCEPT
+-A INPUT -p icmp -s 10.0.0.0/8 -m comment --comment "Bloquear ping" -j DROP
+# regra 'Ping com porta' ignorada: protocolo icmp não tem portas
+-A OUTPUT -p icmp -s 10.0.0.0/8 -m comment --comment "Bloquear ping" -j DROP
+COMMIT
+"#;
+        assert_eq!(gerar_iptables_restore(&exemplo(), Verdict::Deny), expected);
+    }
+
+    #[test]
+    fn gera_ip6tables_restore() {
+        let expected = r#"# Gerado a partir de FirewallConfig - não edite manualmente
+*filter
+:INPUT ACCEPT [0:0]
+:OUTPUT ACCEPT [0:0]
+-A INPUT -p tcp -m multiport --dports 80,8000:8100 -m comment --comment "Web" -j ACCEPT
+-A INPUT -p udp -m multiport --dports 80,8000:8100 -m comment --comment "Web" -j ACCEPT
+# regra 'Ping com porta' ignorada: protocolo icmp não tem portas
+-A OUTPUT -p udp -d 2001:4860:4860::8888 --dport 53 -m comment --comment "Logar DNS" -j LOG --log-prefix "Logar DNS: "
+-A OUTPUT -p udp -d 2001:4860:4860::8888 --dport 53 -m comment --comment "Logar DNS" -j ACCEPT
+COMMIT
+"#;
+        assert_eq!(gerar_ip6tables_restore(&exemplo(), Verdict::Allow), expected);
+    }
+}
@@ -0,0 +1,986 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+
+use super::connTrack::ConnState;
+use super::evalRules::Verdict;
+use super::exportRules::Family;
+use super::ipNet::IpNet;
+use super::objectSets::{AddrRef, PortRef};
+use super::portSpec::{PortRange, PortSpec};
+use super::writeRules::{rules, Action, Direction, FirewallConfig, Protocol};
+
+/// Construção que não pôde ser convertida para `rules`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportIssue {
+    pub line: usize,  // Linha (1-based) na saída importada
+    pub text: String, // Conteúdo original da linha
+    pub reason: String,
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "linha {}: {} ({})", self.line, self.reason, self.text)
+    }
+}
+
+/// Resultado de uma importação: regras convertidas e o que ficou de fora
+#[derive(Debug, Clone, Default)]
+pub struct ImportResult {
+    pub config: FirewallConfig,
+    pub policies: BTreeMap<String, Verdict>, // Política padrão por chain ("input", "output")
+    pub issues: Vec<ImportIssue>,
+}
+
+impl ImportResult {
+    fn issue(&mut self, line: usize, text: &str, reason: impl Into<String>) {
+        self.issues.push(ImportIssue {
+            line,
+            text: text.trim().to_string(),
+            reason: reason.into(),
+        });
+    }
+
+    /// Adiciona a regra garantindo nome único no conjunto
+    fn push_rule(&mut self, mut rule: rules) {
+        let base = rule.name.clone();
+        let mut suffix = 2;
+        while self.config.position(&rule.name).is_some() {
+            rule.name = format!("{} ({})", base, suffix);
+            suffix += 1;
+        }
+        self.config.rules.push(rule);
+    }
+}
+
+impl fmt::Display for ImportResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "📥 {} regra(s) importada(s)", self.config.rules.len())?;
+        if !self.issues.is_empty() {
+            writeln!(f, "⚠️ {} construção(ões) não representável(is):", self.issues.len())?;
+            for issue in &self.issues {
+                writeln!(f, "   - {}", issue)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Regra em construção durante a importação
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RuleDraft {
+    name: Option<String>,
+    protocol: Option<Protocol>,
+    src_ip: Option<IpNet>,
+    dst_ip: Option<IpNet>,
+    src_port: Option<PortSpec>,
+    dst_port: Option<PortSpec>,
+    state: Option<Vec<ConnState>>,
+    action: Option<Action>,
+    log: bool,
+    family: Option<Family>, // Da tabela/saída de origem ou dos endereços da regra
+}
+
+impl RuleDraft {
+    /// Fixa a família; endereços ou expressões da outra família são rejeitados
+    fn set_family(&mut self, family: Family) -> Result<(), String> {
+        match self.family {
+            Some(current) if current != family => Err(format!(
+                "expressão {} em regra {}",
+                family_name(family),
+                family_name(current)
+            )),
+            _ => {
+                self.family = Some(family);
+                Ok(())
+            }
+        }
+    }
+
+    /// Lê um endereço e fixa a família da regra por ele
+    fn pin_net(&mut self, value: &str) -> Result<IpNet, String> {
+        let net = parse_net(value)?;
+        self.set_family(if net.is_ipv4() { Family::V4 } else { Family::V6 })?;
+        Ok(net)
+    }
+
+    fn set_protocol(&mut self, protocol: Protocol) -> Result<(), String> {
+        match self.protocol {
+            Some(current) if current != protocol && current != Protocol::Any => Err(format!(
+                "protocolos conflitantes '{}' e '{}'",
+                current, protocol
+            )),
+            _ => {
+                self.protocol = Some(protocol);
+                Ok(())
+            }
+        }
+    }
+
+    fn build(self, direction: Direction, fallback_name: String) -> Result<rules, String> {
+        let action = self.action.ok_or("regra sem veredito final")?;
+        // Sem endereços, a rede 0/0 da família impede que a regra valha também para a outra
+        let src_ip = match (self.src_ip, self.dst_ip, self.family) {
+            (None, None, Some(family)) => Some(any_net(family)),
+            (src_ip, _, _) => src_ip,
+        };
+        Ok(rules {
+            name: self.name.unwrap_or(fallback_name),
+            action,
+            direction,
+            protocol: self.protocol.unwrap_or(Protocol::Any),
+            src_ip: src_ip.map(AddrRef::Net),
+            dst_ip: self.dst_ip.map(AddrRef::Net),
+            src_port: self.src_port.map(PortRef::Ports),
+            dst_port: self.dst_port.map(PortRef::Ports),
+            state: self.state,
+            chain: None,
+            severity: None,
+            log: self.log,
+            priority: None,
+            group: None,
+            schedule: None,
+            expires_at: None,
+        })
+    }
+}
+
+/// Importa a saída de `iptables-save` / `ip6tables-save`
+///
+/// Apenas a tabela `filter` e as chains INPUT/OUTPUT são convertidas.
+/// Um `-j LOG` seguido de uma regra idêntica com ACCEPT/DROP vira uma
+/// única regra (`log` ou `log: true`), como gerado por `exportRules`.
+/// A família vem do cabeçalho "# Generated by iptables-save/ip6tables-save".
+pub fn importar_iptables_save(text: &str) -> ImportResult {
+    let mut result = ImportResult::default();
+    let mut table = String::new();
+    let mut family = None;
+    let mut chain_counters: BTreeMap<String, usize> = BTreeMap::new();
+    // LOG pendente aguardando o alvo final: (linha, texto, chain, regra)
+    let mut pending_log: Option<(usize, String, String, RuleDraft)> = None;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = raw_line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if comment.contains("ip6tables-save") {
+                family = Some(Family::V6);
+            } else if comment.contains("iptables-save") {
+                family = Some(Family::V4);
+            }
+            continue;
+        }
+
+        let (chain, direction, draft, is_log) =
+            match parse_iptables_line(line, &mut table, family, &mut result.policies) {
+                Ok(Some(rule)) => rule,
+                other => {
+                    // Qualquer linha que não seja regra separa o LOG do seu alvo
+                    if let Some((log_line, log_text, _, _)) = pending_log.take() {
+                        result.issue(log_line, &log_text, LOG_SEM_ALVO);
+                    }
+                    if let Err(reason) = other {
+                        result.issue(line_no, raw_line, reason);
+                    }
+                    continue;
+                }
+            };
+
+        // Resolve um LOG anterior: só é representável se a regra seguinte
+        // tiver exatamente os mesmos critérios e um alvo final
+        if let Some((log_line, log_text, log_chain, mut log_draft)) = pending_log.take() {
+            let same_match = log_chain == chain && {
+                let mut candidate = draft.clone();
+                candidate.action = None;
+                candidate.log = false;
+                candidate == log_draft
+            };
+            if same_match && !is_log {
+                log_draft.action = match draft.action {
+                    Some(Action::Allow) => Some(Action::Log),
+                    other => other,
+                };
+                log_draft.log = log_draft.action != Some(Action::Log);
+                emit_iptables_rule(
+                    &mut result,
+                    &mut chain_counters,
+                    &chain,
+                    direction,
+                    log_draft,
+                    log_line,
+                    &log_text,
+                );
+                continue;
+            }
+            result.issue(log_line, &log_text, LOG_SEM_ALVO);
+        }
+
+        if is_log {
+            pending_log = Some((line_no, raw_line.to_string(), chain, draft));
+            continue;
+        }
+
+        emit_iptables_rule(
+            &mut result,
+            &mut chain_counters,
+            &chain,
+            direction,
+            draft,
+            line_no,
+            raw_line,
+        );
+    }
+
+    if let Some((log_line, log_text, _, _)) = pending_log {
+        result.issue(log_line, &log_text, LOG_SEM_ALVO);
+    }
+
+    result.issues.sort_by_key(|issue| issue.line);
+    result
+}
+
+const LOG_SEM_ALVO: &str = "LOG sem alvo final correspondente (não terminal) não é representável";
+
+/// Interpreta uma linha do iptables-save; `Ok(None)` para tabelas, chains e COMMIT
+fn parse_iptables_line(
+    line: &str,
+    table: &mut String,
+    family: Option<Family>,
+    policies: &mut BTreeMap<String, Verdict>,
+) -> Result<Option<(String, Direction, RuleDraft, bool)>, String> {
+    if let Some(name) = line.strip_prefix('*') {
+        *table = name.to_string();
+        return Ok(None);
+    }
+    if line == "COMMIT" {
+        return Ok(None);
+    }
+    if let Some(declaration) = line.strip_prefix(':') {
+        if table == "filter" {
+            let mut parts = declaration.split_whitespace();
+            if let (Some(chain), Some(policy)) = (parts.next(), parts.next()) {
+                if let Some(verdict) = iptables_policy(policy) {
+                    policies.insert(chain.to_ascii_lowercase(), verdict);
+                }
+            }
+        }
+        return Ok(None);
+    }
+
+    if table != "filter" {
+        return Err(format!("tabela '{}' não suportada", table));
+    }
+
+    let tokens = tokenize(line);
+    if tokens.len() < 2 || tokens[0] != "-A" {
+        return Err("comando não suportado (esperado -A)".to_string());
+    }
+    let chain = tokens[1].clone();
+    let direction = match chain.as_str() {
+        "INPUT" => Direction::Inbound,
+        "OUTPUT" => Direction::Outbound,
+        _ => return Err(format!("chain '{}' não suportada", chain)),
+    };
+    let (draft, is_log) = parse_iptables_rule(&tokens[2..], family)?;
+    Ok(Some((chain, direction, draft, is_log)))
+}
+
+/// Adiciona a regra ou relata por que não foi possível
+///
+/// O número do nome padrão ("iptables INPUT #N") conta só as regras emitidas
+/// na chain: um LOG unido ao alvo seguinte ou uma linha rejeitada não o avança.
+/// Sem família conhecida a regra é importada, mas fica registrada em `issues`.
+fn emit_iptables_rule(
+    result: &mut ImportResult,
+    counters: &mut BTreeMap<String, usize>,
+    chain: &str,
+    direction: Direction,
+    draft: RuleDraft,
+    line: usize,
+    text: &str,
+) {
+    let counter = counters.entry(chain.to_string()).or_insert(0);
+    let unknown_family = draft.family.is_none();
+    match draft.build(direction, format!("iptables {} #{}", chain, *counter + 1)) {
+        Ok(rule) => {
+            *counter += 1;
+            result.push_rule(rule);
+            if unknown_family {
+                result.issue(
+                    line,
+                    text,
+                    "família desconhecida (sem cabeçalho do iptables-save): regra sem endereço vale para IPv4 e IPv6",
+                );
+            }
+        }
+        Err(reason) => result.issue(line, text, reason),
+    }
+}
+
+/// Converte os argumentos após `-A CHAIN`; retorna a regra e se o alvo é LOG
+fn parse_iptables_rule(tokens: &[String], family: Option<Family>) -> Result<(RuleDraft, bool), String> {
+    let mut draft = RuleDraft {
+        family,
+        ..RuleDraft::default()
+    };
+    let mut is_log = false;
+    let mut iter = tokens.iter();
+
+    while let Some(token) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("opção '{}' sem valor", token))
+        };
+
+        match token.as_str() {
+            "!" => return Err("negação ('!') não suportada".to_string()),
+            "-p" | "--protocol" => {
+                let protocol = value()?;
+                draft.set_protocol(parse_protocol(&protocol)?)?;
+            }
+            "-s" | "--source" => draft.src_ip = Some(draft.pin_net(&value()?)?),
+            "-d" | "--destination" => draft.dst_ip = Some(draft.pin_net(&value()?)?),
+            "--sport" | "--source-port" => {
+                draft.src_port = Some(parse_ports(&value()?, ':')?)
+            }
+            "--dport" | "--destination-port" => {
+                draft.dst_port = Some(parse_ports(&value()?, ':')?)
+            }
+            "--sports" | "--source-ports" => {
+                draft.src_port = Some(parse_ports(&value()?, ':')?)
+            }
+            "--dports" | "--destination-ports" => {
+                draft.dst_port = Some(parse_ports(&value()?, ':')?)
+            }
+            "-m" | "--match" => {
+                let module = value()?;
+                match module.as_str() {
+                    "tcp" | "udp" | "icmp" | "icmp6" | "multiport" | "comment" | "conntrack"
+                    | "state" => {}
+                    other => return Err(format!("módulo '-m {}' não suportado", other)),
+                }
+            }
+            "--comment" => draft.name = Some(value()?),
+            "--ctstate" | "--state" => draft.state = Some(parse_states(&value()?)?),
+            "-j" | "--jump" => {
+                let target = value()?;
+                match target.as_str() {
+                    "ACCEPT" => draft.action = Some(Action::Allow),
+                    "DROP" | "REJECT" => draft.action = Some(Action::Deny),
+                    "LOG" => is_log = true,
+                    other => return Err(format!("alvo '-j {}' não suportado", other)),
+                }
+            }
+            // Opções do alvo LOG/REJECT não afetam o casamento
+            "--log-prefix" | "--log-level" | "--reject-with" => {
+                value()?;
+            }
+            "--log-uid" | "--log-tcp-options" | "--log-ip-options" | "--log-tcp-sequence" => {}
+            other => return Err(format!("opção '{}' não suportada", other)),
+        }
+    }
+
+    if !is_log && draft.action.is_none() {
+        return Err("regra sem alvo (-j)".to_string());
+    }
+    Ok((draft, is_log))
+}
+
+fn iptables_policy(policy: &str) -> Option<Verdict> {
+    match policy {
+        "ACCEPT" => Some(Verdict::Allow),
+        "DROP" => Some(Verdict::Deny),
+        _ => None,
+    }
+}
+
+/// Importa a saída de `nft list ruleset`
+///
+/// Tabelas `ip`, `ip6` e `inet` são lidas; apenas chains base com hook
+/// `input`/`output` são convertidas. Construções como `iif`, `jump` ou
+/// sets nomeados são reportadas e a regra é ignorada.
+pub fn importar_nft_ruleset(text: &str) -> ImportResult {
+    let mut result = ImportResult::default();
+    let mut table: Option<(String, String)> = None; // (família, nome)
+    let mut chain: Option<String> = None;
+    let mut hook: Option<Direction> = None;
+    let mut chain_counter = 0;
+    let mut skipping_block = false;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        let line = strip_nft_comment(raw_line).trim().trim_end_matches(';').trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        // Sets, maps e outros objetos nomeados não têm equivalente em `rules`
+        if skipping_block {
+            skipping_block = line != "}";
+            continue;
+        }
+        if chain.is_none() && line.ends_with('{') && !line.starts_with("table ") && !line.starts_with("chain ") {
+            result.issue(line_no, raw_line, "objeto nomeado (set/map) não importado");
+            skipping_block = true;
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("table ") {
+            let mut parts = rest.trim_end_matches('{').split_whitespace();
+            let family = parts.next().unwrap_or_default().to_string();
+            let name = parts.next().unwrap_or_default().to_string();
+            table = Some((family, name));
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("chain ") {
+            // Aceita também a forma compacta "chain x { type filter hook input ..."
+            let (name, body) = rest.split_once('{').unwrap_or((rest, ""));
+            chain = Some(name.trim().to_string());
+            hook = None;
+            chain_counter = 0;
+            if body.trim().starts_with("type ") {
+                hook = parse_nft_hook(body, &mut result.policies);
+            }
+            continue;
+        }
+        if line == "}" {
+            if chain.take().is_none() {
+                table = None;
+            }
+            hook = None;
+            continue;
+        }
+
+        let (Some((family, _)), Some(chain_name)) = (&table, &chain) else {
+            result.issue(line_no, raw_line, "declaração fora de table/chain não suportada");
+            continue;
+        };
+
+        if !matches!(family.as_str(), "ip" | "ip6" | "inet") {
+            result.issue(line_no, raw_line, format!("família '{}' não suportada", family));
+            continue;
+        }
+
+        if line.starts_with("type ") {
+            hook = parse_nft_hook(line, &mut result.policies);
+            continue;
+        }
+
+        let Some(direction) = hook else {
+            result.issue(
+                line_no,
+                raw_line,
+                format!("chain '{}' sem hook input/output não suportada", chain_name),
+            );
+            continue;
+        };
+
+        // Como no iptables, o número do nome padrão só avança quando a regra é emitida
+        let fallback_name = format!("nft {} #{}", chain_name, chain_counter + 1);
+
+        let table_family = match family.as_str() {
+            "ip" => Some(Family::V4),
+            "ip6" => Some(Family::V6),
+            _ => None,
+        };
+        match parse_nft_rule(&tokenize(line), table_family)
+            .and_then(|draft| draft.build(direction, fallback_name))
+        {
+            Ok(rule) => {
+                chain_counter += 1;
+                result.push_rule(rule);
+            }
+            Err(reason) => result.issue(line_no, raw_line, reason),
+        }
+    }
+
+    result
+}
+
+/// Lê "type filter hook input priority 0; policy drop" e registra a política
+fn parse_nft_hook(line: &str, policies: &mut BTreeMap<String, Verdict>) -> Option<Direction> {
+    let tokens: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|t| !t.is_empty())
+        .collect();
+    if tokens.get(1) != Some(&"filter") {
+        return None;
+    }
+
+    let hook_name = tokens
+        .iter()
+        .position(|&t| t == "hook")
+        .and_then(|i| tokens.get(i + 1))
+        .copied()?;
+    let direction = match hook_name {
+        "input" => Direction::Inbound,
+        "output" => Direction::Outbound,
+        _ => return None,
+    };
+
+    let policy = tokens
+        .iter()
+        .position(|&t| t == "policy")
+        .and_then(|i| tokens.get(i + 1));
+    match policy {
+        Some(&"accept") => {
+            policies.insert(hook_name.to_string(), Verdict::Allow);
+        }
+        Some(&"drop") => {
+            policies.insert(hook_name.to_string(), Verdict::Deny);
+        }
+        _ => {}
+    }
+    Some(direction)
+}
+
+/// `family` é a da tabela (`None` para `inet`); `ip`/`ip6` na regra a fixam
+fn parse_nft_rule(tokens: &[String], family: Option<Family>) -> Result<RuleDraft, String> {
+    let mut draft = RuleDraft {
+        family,
+        ..RuleDraft::default()
+    };
+    let mut iter = tokens.iter().peekable();
+
+    while let Some(token) = iter.next() {
+        let mut value = || {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| format!("expressão '{}' incompleta", token))
+        };
+
+        match token.as_str() {
+            "ip" | "ip6" => {
+                let field = value()?;
+                let operand = value()?;
+                draft.set_family(if token == "ip" { Family::V4 } else { Family::V6 })?;
+                match field.as_str() {
+                    "saddr" => draft.src_ip = Some(draft.pin_net(&operand)?),
+                    "daddr" => draft.dst_ip = Some(draft.pin_net(&operand)?),
+                    "protocol" | "nexthdr" => draft.set_protocol(parse_protocol(&operand)?)?,
+                    other => return Err(format!("expressão '{} {}' não suportada", token, other)),
+                }
+            }
+            "tcp" | "udp" | "th" => {
+                let field = value()?;
+                let operand = value()?;
+                if token != "th" {
+                    draft.set_protocol(parse_protocol(token)?)?;
+                }
+                match field.as_str() {
+                    "sport" => draft.src_port = Some(parse_ports(&operand, '-')?),
+                    "dport" => draft.dst_port = Some(parse_ports(&operand, '-')?),
+                    other => return Err(format!("expressão '{} {}' não suportada", token, other)),
+                }
+            }
+            "meta" => {
+                let field = value()?;
+                let operand = value()?;
+                if field != "l4proto" {
+                    return Err(format!("expressão 'meta {}' não suportada", field));
+                }
+                draft.set_protocol(parse_nft_l4proto(&operand)?)?;
+            }
+            "counter" => {
+                // "counter packets N bytes M"
+                while matches!(iter.peek().map(|t| t.as_str()), Some("packets") | Some("bytes")) {
+                    iter.next();
+                    iter.next();
+                }
+            }
+            "log" => {
+                draft.log = true;
+                while matches!(iter.peek().map(|t| t.as_str()), Some("prefix") | Some("level")) {
+                    iter.next();
+                    iter.next();
+                }
+            }
+            "ct" => {
+                let field = value()?;
+                let operand = value()?;
+                if field != "state" {
+                    return Err(format!("expressão 'ct {}' não suportada", field));
+                }
+                draft.state = Some(parse_states(&operand)?);
+            }
+            "comment" => draft.name = Some(value()?),
+            "accept" => draft.action = Some(Action::Allow),
+            "drop" => draft.action = Some(Action::Deny),
+            "reject" => {
+                draft.action = Some(Action::Deny);
+                // "reject with icmp type ..." não altera o casamento
+                if iter.peek().map(|t| t.as_str()) == Some("with") {
+                    while !matches!(iter.peek().map(|t| t.as_str()), None | Some("comment")) {
+                        iter.next();
+                    }
+                }
+            }
+            other => return Err(format!("expressão '{}' não suportada", other)),
+        }
+    }
+
+    // `log ... accept` é a forma exportada do veredito Log
+    if draft.log && draft.action == Some(Action::Allow) {
+        draft.action = Some(Action::Log);
+        draft.log = false;
+    }
+    Ok(draft)
+}
+
+fn parse_nft_l4proto(operand: &str) -> Result<Protocol, String> {
+    match set_items(operand).as_slice() {
+        [single] => parse_protocol(single),
+        items => {
+            let mut protocols: Vec<Protocol> =
+                items.iter().map(|item| parse_protocol(item)).collect::<Result<_, _>>()?;
+            protocols.sort_by_key(|p| p.as_str());
+            protocols.dedup();
+            match protocols.as_slice() {
+                [Protocol::Icmp] => Ok(Protocol::Icmp),
+                [Protocol::Tcp, Protocol::Udp] => Ok(Protocol::Any),
+                _ => Err(format!("conjunto de protocolos '{}' não suportado", operand)),
+            }
+        }
+    }
+}
+
+/// Estados do conntrack: "ESTABLISHED,RELATED" ou "{ established, related }"
+fn parse_states(value: &str) -> Result<Vec<ConnState>, String> {
+    set_items(value)
+        .iter()
+        .map(|item| {
+            ConnState::parse(item)
+                .ok_or_else(|| format!("estado de conexão '{}' não suportado", item))
+        })
+        .collect()
+}
+
+fn parse_protocol(name: &str) -> Result<Protocol, String> {
+    match name {
+        "tcp" => Ok(Protocol::Tcp),
+        "udp" => Ok(Protocol::Udp),
+        "icmp" | "ipv6-icmp" | "icmpv6" => Ok(Protocol::Icmp),
+        "all" => Ok(Protocol::Any),
+        other => Err(format!("protocolo '{}' não suportado", other)),
+    }
+}
+
+fn family_name(family: Family) -> &'static str {
+    match family {
+        Family::V4 => "IPv4",
+        Family::V6 => "IPv6",
+    }
+}
+
+/// Rede 0/0 da família: casa todo endereço dela e nenhum da outra
+fn any_net(family: Family) -> IpNet {
+    let addr = match family {
+        Family::V4 => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        Family::V6 => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    IpNet::new(addr, 0).expect("prefixo 0 é válido nas duas famílias")
+}
+
+fn parse_net(value: &str) -> Result<IpNet, String> {
+    if value.starts_with('@') || value.starts_with('{') {
+        return Err(format!("conjunto de endereços '{}' não suportado", value));
+    }
+    value
+        .parse()
+        .map_err(|e| format!("endereço '{}' não suportado: {}", value, e))
+}
+
+/// Portas no formato do iptables (`80`, `8000:8100`, `80,443`) ou do nft
+/// (`80`, `8000-8100`, `{ 80, 443 }`)
+fn parse_ports(value: &str, range_separator: char) -> Result<PortSpec, String> {
+    if value.starts_with('@') {
+        return Err(format!("set nomeado '{}' não suportado", value));
+    }
+    let ranges = set_items(value)
+        .iter()
+        .map(|item| {
+            item.replace(range_separator, "-")
+                .parse::<PortRange>()
+                .map_err(|e| e.to_string())
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    PortSpec::new(ranges).map_err(|e| e.to_string())
+}
+
+/// Elementos de `{ a, b }` ou de uma lista `a,b`
+fn set_items(value: &str) -> Vec<String> {
+    value
+        .trim_start_matches('{')
+        .trim_end_matches('}')
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Remove comentários `# handle N` do nft (fora de strings)
+fn strip_nft_comment(line: &str) -> &str {
+    let mut in_quotes = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '#' if !in_quotes => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// Divide a linha em tokens respeitando aspas e agrupando `{ ... }` em um só
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut brace_depth = 0;
+
+    for c in line.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            '{' if !in_quotes => {
+                brace_depth += 1;
+                current.push(c);
+            }
+            '}' if !in_quotes => {
+                brace_depth -= 1;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes && brace_depth == 0 => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(result: &ImportResult) -> Vec<&str> {
+        result.config.rules.iter().map(|rule| rule.name.as_str()).collect()
+    }
+
+    fn issues(result: &ImportResult) -> Vec<(usize, &str)> {
+        result.issues.iter().map(|issue| (issue.line, issue.reason.as_str())).collect()
+    }
+
+    const IPTABLES_SAVE: &str = "\
+# Generated by iptables-save v1.8.7 on Mon Oct  5 10:00:00 2026
+*nat
+:PREROUTING ACCEPT [0:0]
+-A PREROUTING -p tcp --dport 8080 -j REDIRECT --to-ports 80
+COMMIT
+*filter
+:INPUT DROP [0:0]
+:FORWARD DROP [0:0]
+:OUTPUT ACCEPT [0:0]
+-A INPUT -p tcp -m tcp --dport 22 -j LOG --log-prefix \"ssh: \"
+-A INPUT -p tcp -m tcp --dport 22 -j ACCEPT
+-A INPUT -s 10.0.0.0/8 -p udp -m udp --dport 53 -j ACCEPT
+-A INPUT -i eth0 -p tcp -j DROP
+-A INPUT -m conntrack --ctstate RELATED,ESTABLISHED -m comment --comment \"Retorno\" -j ACCEPT
+-A INPUT -p icmp -j LOG
+-A INPUT -p tcp -j DROP
+-A FORWARD -j ACCEPT
+-A OUTPUT -p udp -m multiport --dports 53,123 -j ACCEPT
+COMMIT
+";
+
+    #[test]
+    fn iptables_save_une_log_e_numera_regras_emitidas() {
+        let result = importar_iptables_save(IPTABLES_SAVE);
+        assert_eq!(
+            names(&result),
+            vec!["iptables INPUT #1", "iptables INPUT #2", "Retorno", "iptables INPUT #4", "iptables OUTPUT #1"]
+        );
+
+        let ssh = &result.config.rules[0];
+        assert_eq!((ssh.action, ssh.log), (Action::Log, false));
+        assert_eq!(ssh.dst_port, Some(PortRef::Ports(PortSpec::single(22))));
+        assert_eq!(result.config.rules[1].src_ip, Some(AddrRef::Net("10.0.0.0/8".parse().unwrap())));
+        assert_eq!(result.config.rules[2].state, Some(vec![ConnState::Related, ConnState::Established]));
+
+        assert_eq!(result.policies.get("input"), Some(&Verdict::Deny));
+        assert_eq!(result.policies.get("output"), Some(&Verdict::Allow));
+    }
+
+    #[test]
+    fn iptables_save_relata_construcoes_nao_suportadas() {
+        let result = importar_iptables_save(IPTABLES_SAVE);
+        assert_eq!(
+            issues(&result),
+            vec![
+                (4, "tabela 'nat' não suportada"),
+                (13, "opção '-i' não suportada"),
+                (15, "LOG sem alvo final correspondente (não terminal) não é representável"),
+                (17, "chain 'FORWARD' não suportada"),
+            ]
+        );
+        assert_eq!(result.issues[1].text, "-A INPUT -i eth0 -p tcp -j DROP");
+    }
+
+    #[test]
+    fn log_seguido_de_drop_vira_deny_com_log() {
+        let result = importar_iptables_save(
+            "*filter\n-A OUTPUT -p tcp -j LOG\n-A OUTPUT -p tcp -j DROP\n-A OUTPUT -p udp -j DROP\nCOMMIT\n",
+        );
+        let deny = &result.config.rules[0];
+        assert_eq!((deny.action, deny.log), (Action::Deny, true));
+        assert_eq!(names(&result), vec!["iptables OUTPUT #1", "iptables OUTPUT #2"]);
+    }
+
+    #[test]
+    fn familia_do_ip6tables_save_fixa_regras_sem_endereco() {
+        let result = importar_iptables_save(
+            "# Generated by ip6tables-save v1.8.7\n*filter\n\
+             -A INPUT -p tcp --dport 22 -j ACCEPT\n\
+             -A INPUT -s 2001:db8::/32 -j DROP\n\
+             -A INPUT -s 10.0.0.0/8 -j DROP\n\
+             COMMIT\n",
+        );
+        let any_v6 = AddrRef::Net("::/0".parse().unwrap());
+        assert_eq!(result.config.rules[0].src_ip, Some(any_v6));
+        assert_eq!(result.config.rules[1].src_ip, Some(AddrRef::Net("2001:db8::/32".parse().unwrap())));
+        assert_eq!(issues(&result), vec![(5, "expressão IPv4 em regra IPv6")]);
+
+        // O mesmo texto vindo do iptables-save fica restrito ao IPv4
+        let result = importar_iptables_save("# Generated by iptables-save\n*filter\n-A OUTPUT -j DROP\n");
+        assert_eq!(result.config.rules[0].src_ip, Some(AddrRef::Net("0.0.0.0/0".parse().unwrap())));
+    }
+
+    #[test]
+    fn sem_cabecalho_regra_sem_endereco_fica_em_issues() {
+        let result = importar_iptables_save("*filter\n-A INPUT -p udp -j DROP\n-A INPUT -d ::1 -j DROP\n");
+        assert_eq!(result.config.rules.len(), 2);
+        assert_eq!(result.config.rules[0].src_ip, None);
+        assert_eq!(result.config.rules[1].src_ip, None);
+        assert_eq!(
+            issues(&result),
+            vec![(2, "família desconhecida (sem cabeçalho do iptables-save): regra sem endereço vale para IPv4 e IPv6")]
+        );
+    }
+
+    #[test]
+    fn linha_rejeitada_descarta_log_pendente() {
+        let result = importar_iptables_save(
+            "*filter\n\
+             -A INPUT -s 10.0.0.1 -j LOG\n\
+             -A INPUT -i eth0 -j DROP\n\
+             -A INPUT -s 10.0.0.1 -j ACCEPT\n\
+             -A OUTPUT -d 10.0.0.2 -j LOG\n\
+             COMMIT\n\
+             *filter\n\
+             -A OUTPUT -d 10.0.0.2 -j DROP\n",
+        );
+        assert_eq!(
+            issues(&result),
+            vec![
+                (2, LOG_SEM_ALVO),
+                (3, "opção '-i' não suportada"),
+                (5, LOG_SEM_ALVO),
+            ]
+        );
+        // O ACCEPT depois da linha rejeitada não herda o LOG
+        let rules = &result.config.rules;
+        assert_eq!((rules[0].action, rules[0].log), (Action::Allow, false));
+        assert_eq!((rules[1].action, rules[1].log), (Action::Deny, false));
+    }
+
+    const NFT_RULESET: &str = r#"table inet firewall {
+    set bloqueados {
+        type ipv4_addr
+        elements = { 203.0.113.7 }
+    }
+
+    chain input {
+        type filter hook input priority 0; policy drop;
+        ct state established,related accept # handle 4
+        iif "lo" accept
+        tcp dport 22 log prefix "ssh: " accept
+        ip saddr @bloqueados drop
+        ip saddr 192.168.0.0/16 meta l4proto { tcp, udp } th dport { 80, 443 } accept comment "Web"
+        udp dport 53 counter packets 10 bytes 640 drop
+    }
+
+    chain output {
+        type filter hook output priority 0; policy accept;
+        jump registrar
+    }
+
+    chain registrar {
+        log
+    }
+}
+"#;
+
+    #[test]
+    fn nft_ruleset_converte_chains_base() {
+        let result = importar_nft_ruleset(NFT_RULESET);
+        assert_eq!(names(&result), vec!["nft input #1", "nft input #2", "Web", "nft input #4"]);
+        assert_eq!(result.config.rules[1].action, Action::Log);
+        assert_eq!(result.config.rules[2].protocol, Protocol::Any);
+        assert_eq!(result.config.rules[3].action, Action::Deny);
+        assert_eq!(result.policies.get("input"), Some(&Verdict::Deny));
+        assert_eq!(result.policies.get("output"), Some(&Verdict::Allow));
+    }
+
+    #[test]
+    fn nft_ruleset_relata_construcoes_nao_suportadas() {
+        let result = importar_nft_ruleset(NFT_RULESET);
+        assert_eq!(
+            issues(&result),
+            vec![
+                (2, "objeto nomeado (set/map) não importado"),
+                (10, "expressão 'iif' não suportada"),
+                (12, "conjunto de endereços '@bloqueados' não suportado"),
+                (19, "expressão 'jump' não suportada"),
+                (23, "chain 'registrar' sem hook input/output não suportada"),
+            ]
+        );
+    }
+
+    #[test]
+    fn nft_fixa_familia_pela_tabela_e_pelas_expressoes() {
+        let result = importar_nft_ruleset(
+            "table ip6 filtro {\n\
+             chain input {\n\
+             type filter hook input priority 0; policy accept;\n\
+             tcp dport 22 accept\n\
+             ip saddr 10.0.0.0/8 drop\n\
+             }\n\
+             }\n\
+             table inet misto {\n\
+             chain output {\n\
+             type filter hook output priority 0;\n\
+             ip protocol tcp drop\n\
+             udp dport 53 accept\n\
+             }\n\
+             }\n",
+        );
+        let rules = &result.config.rules;
+        assert_eq!(rules[0].src_ip, Some(AddrRef::Net("::/0".parse().unwrap())));
+        assert_eq!(rules[1].src_ip, Some(AddrRef::Net("0.0.0.0/0".parse().unwrap())));
+        // `inet` sem expressão de família vale para as duas
+        assert_eq!(rules[2].src_ip, None);
+        assert_eq!(issues(&result), vec![(5, "expressão IPv4 em regra IPv6")]);
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Rede IPv4/IPv6 em notação CIDR (ex.: "192.168.1.0/24")
+///
+/// Um endereço sem prefixo é tratado como host único (/32 ou /128).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+/// Erro ao interpretar uma rede CIDR
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetParseError {
+    InvalidAddress(String),
+    InvalidPrefix(String),
+    PrefixTooLong { prefix: u8, max: u8 },
+    HostBitsSet { network: String, expected: IpAddr },
+}
+
+impl fmt::Display for NetParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NetParseError::InvalidAddress(addr) => write!(f, "endereço IP inválido '{}'", addr),
+            NetParseError::InvalidPrefix(prefix) => write!(f, "prefixo CIDR inválido '{}'", prefix),
+            NetParseError::PrefixTooLong { prefix, max } => {
+                write!(f, "prefixo /{} excede o máximo /{}", prefix, max)
+            }
+            NetParseError::HostBitsSet { network, expected } => write!(
+                f,
+                "rede '{}' possui bits de host definidos (esperado {})",
+                network, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetParseError {}
+
+impl JsonSchema for IpNet {
+    fn schema_name() -> String {
+        "IpNet".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        serde_json::from_value(json!({
+            "description": "IP único ou rede CIDR (ex.: \"192.168.1.0/24\", \"2001:db8::/32\")",
+            "type": "string",
+            "minLength": 1
+        }))
+        .expect("schema estático válido")
+    }
+}
+
+impl IpNet {
+    /// Cria a rede validando o prefixo e rejeitando bits de host definidos
+    pub fn new(addr: IpAddr, prefix: u8) -> Result<Self, NetParseError> {
+        let max = max_prefix(addr);
+        if prefix > max {
+            return Err(NetParseError::PrefixTooLong { prefix, max });
+        }
+
+        let net = Self { addr, prefix };
+        if net.network() != addr {
+            return Err(NetParseError::HostBitsSet {
+                network: format!("{}/{}", addr, prefix),
+                expected: net.network(),
+            });
+        }
+        Ok(net)
+    }
+
+    /// Rede contendo apenas um host
+    pub fn host(addr: IpAddr) -> Self {
+        Self {
+            addr,
+            prefix: max_prefix(addr),
+        }
+    }
+
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.addr.is_ipv4()
+    }
+
+    /// Endereço de rede (endereço com os bits de host zerados)
+    pub fn network(&self) -> IpAddr {
+        match self.addr {
+            IpAddr::V4(addr) => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(addr) & v4_mask(self.prefix)))
+            }
+            IpAddr::V6(addr) => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(addr) & v6_mask(self.prefix)))
+            }
+        }
+    }
+
+    /// Verifica se o endereço pertence à rede (famílias diferentes nunca casam)
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = v4_mask(self.prefix);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = v6_mask(self.prefix);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+
+    /// Verifica se `other` está inteiramente contida nesta rede
+    pub fn contains_net(&self, other: &IpNet) -> bool {
+        self.prefix <= other.prefix && self.contains(other.addr)
+    }
+
+    /// Primeiro e último endereço da rede, como inteiros
+    fn bounds(&self) -> (u128, u128) {
+        match self.network() {
+            IpAddr::V4(addr) => {
+                let start = u32::from(addr);
+                (start as u128, (start | !v4_mask(self.prefix)) as u128)
+            }
+            IpAddr::V6(addr) => {
+                let start = u128::from(addr);
+                (start, start | !v6_mask(self.prefix))
+            }
+        }
+    }
+
+    /// Interseção de duas redes CIDR: sempre vazia ou igual à menor delas
+    pub fn intersect(&self, other: &IpNet) -> Option<IpNet> {
+        if self.contains_net(other) {
+            Some(*other)
+        } else if other.contains_net(self) {
+            Some(*self)
+        } else {
+            None
+        }
+    }
+
+    /// Partes desta rede fora de `other`, como redes disjuntas
+    ///
+    /// A rede é dividida ao meio até isolar `other`; cada metade sem ele
+    /// entra inteira, então o resultado tem no máximo uma rede por bit de prefixo.
+    pub fn subtract(&self, other: &IpNet) -> Vec<IpNet> {
+        if other.contains_net(self) {
+            return Vec::new();
+        }
+        if !self.contains_net(other) {
+            return vec![*self];
+        }
+        let mut pieces = Vec::new();
+        let mut current = *self;
+        while current.prefix < other.prefix {
+            let (low, high) = current.halves();
+            if low.contains_net(other) {
+                pieces.push(high);
+                current = low;
+            } else {
+                pieces.push(low);
+                current = high;
+            }
+        }
+        pieces
+    }
+
+    /// As duas sub-redes com um bit de prefixo a mais
+    fn halves(&self) -> (IpNet, IpNet) {
+        let (start, end) = self.bounds();
+        let high_start = start + (end - start) / 2 + 1;
+        let half = |value: u128| {
+            let addr = match self.addr {
+                IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(value as u32)),
+                IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(value)),
+            };
+            Self {
+                addr,
+                prefix: self.prefix + 1,
+            }
+        };
+        (half(start), half(high_start))
+    }
+}
+
+impl FromStr for IpNet {
+    type Err = NetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once('/') {
+            Some((addr, prefix)) => {
+                let addr: IpAddr = addr
+                    .parse()
+                    .map_err(|_| NetParseError::InvalidAddress(addr.to_string()))?;
+                let prefix: u8 = prefix
+                    .parse()
+                    .map_err(|_| NetParseError::InvalidPrefix(prefix.to_string()))?;
+                IpNet::new(addr, prefix)
+            }
+            None => s
+                .parse()
+                .map(IpNet::host)
+                .map_err(|_| NetParseError::InvalidAddress(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for IpNet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Hosts únicos são exibidos sem prefixo, como aparecem no rules.json
+        if self.prefix == max_prefix(self.addr) {
+            write!(f, "{}", self.addr)
+        } else {
+            write!(f, "{}/{}", self.addr, self.prefix)
+        }
+    }
+}
+
+impl TryFrom<String> for IpNet {
+    type Error = NetParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpNet> for String {
+    fn from(net: IpNet) -> Self {
+        net.to_string()
+    }
+}
+
+/// Verifica se a união das redes de `outer` contém todas as redes de `inner`
+///
+/// Redes adjacentes são fundidas antes da comparação: ["10.0.0.0/25",
+/// "10.0.0.128/25"] cobre "10.0.0.0/24" mesmo sem nenhuma das duas cobri-la.
+pub fn nets_cover(outer: &[IpNet], inner: &[IpNet]) -> bool {
+    let merged = merge_nets(outer);
+    inner.iter().all(|net| {
+        let (start, end) = net.bounds();
+        merged
+            .iter()
+            .any(|&(v4, first, last)| v4 == net.is_ipv4() && first <= start && end <= last)
+    })
+}
+
+/// Redes de `nets` fora da união de `removed`, como redes disjuntas
+pub fn subtract_nets(nets: &[IpNet], removed: &[IpNet]) -> Vec<IpNet> {
+    removed.iter().fold(nets.to_vec(), |nets, other| {
+        nets.iter().flat_map(|net| net.subtract(other)).collect()
+    })
+}
+
+/// Intervalos de endereços da união das redes, por família, ordenados e fundidos
+/// quando sobrepostos ou adjacentes
+fn merge_nets(nets: &[IpNet]) -> Vec<(bool, u128, u128)> {
+    let mut sorted: Vec<(bool, u128, u128)> = nets
+        .iter()
+        .map(|net| {
+            let (start, end) = net.bounds();
+            (net.is_ipv4(), start, end)
+        })
+        .collect();
+    sorted.sort();
+
+    let mut merged: Vec<(bool, u128, u128)> = Vec::with_capacity(sorted.len());
+    for (v4, start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if last.0 == v4 && start <= last.2.saturating_add(1) => {
+                last.2 = last.2.max(end);
+            }
+            _ => merged.push((v4, start, end)),
+        }
+    }
+    merged
+}
+
+fn max_prefix(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn v4_mask(prefix: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0)
+}
+
+fn v6_mask(prefix: u8) -> u128 {
+    u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(s: &str) -> IpNet {
+        s.parse().unwrap()
+    }
+
+    type Case = (&'static str, Result<(&'static str, u8), NetParseError>);
+
+    #[test]
+    fn interpreta_cidr() {
+        let cases: &[Case] = &[
+            ("192.168.1.0/24", Ok(("192.168.1.0", 24))),
+            (" 10.0.0.1 ", Ok(("10.0.0.1", 32))),
+            ("0.0.0.0/0", Ok(("0.0.0.0", 0))),
+            ("2001:db8::/32", Ok(("2001:db8::", 32))),
+            ("::1", Ok(("::1", 128))),
+            ("192.168.1.1/24", Err(NetParseError::HostBitsSet {
+                network: "192.168.1.1/24".to_string(),
+                expected: "192.168.1.0".parse().unwrap(),
+            })),
+            ("2001:db8::1/64", Err(NetParseError::HostBitsSet {
+                network: "2001:db8::1/64".to_string(),
+                expected: "2001:db8::".parse().unwrap(),
+            })),
+            ("10.0.0.0/33", Err(NetParseError::PrefixTooLong { prefix: 33, max: 32 })),
+            ("2001:db8::/129", Err(NetParseError::PrefixTooLong { prefix: 129, max: 128 })),
+            ("10.0.0.0/x", Err(NetParseError::InvalidPrefix("x".to_string()))),
+            ("10.0.0.256/8", Err(NetParseError::InvalidAddress("10.0.0.256".to_string()))),
+            ("rede", Err(NetParseError::InvalidAddress("rede".to_string()))),
+        ];
+        for (input, expected) in cases {
+            let parsed = input.parse::<IpNet>().map(|net| (net.addr(), net.prefix()));
+            let expected = expected.clone().map(|(addr, prefix)| (addr.parse().unwrap(), prefix));
+            assert_eq!(parsed, expected, "{}", input);
+        }
+        assert!(net("10.0.0.0/8").is_ipv4());
+        assert!(!net("2001:db8::/32").is_ipv4());
+    }
+
+    #[test]
+    fn exibe_como_no_json() {
+        for text in ["10.0.0.0/8", "10.0.0.1", "2001:db8::/32", "::1"] {
+            assert_eq!(net(text).to_string(), text);
+        }
+    }
+
+    #[test]
+    fn contains() {
+        let cases = [
+            ("192.168.1.0/24", "192.168.1.255", true),
+            ("192.168.1.0/24", "192.168.2.0", false),
+            ("0.0.0.0/0", "8.8.8.8", true),
+            ("10.0.0.1", "10.0.0.1", true),
+            ("10.0.0.1", "10.0.0.2", false),
+            ("2001:db8::/32", "2001:db8:ffff::1", true),
+            ("2001:db8::/32", "2001:db9::1", false),
+            ("::/0", "10.0.0.1", false), // Famílias diferentes nunca casam
+            ("0.0.0.0/0", "::ffff:10.0.0.1", false),
+        ];
+        for (network, ip, expected) in cases {
+            assert_eq!(net(network).contains(ip.parse().unwrap()), expected, "{} ∋ {}", network, ip);
+        }
+    }
+
+    #[test]
+    fn contains_net_e_intersect() {
+        let cases = [
+            ("10.0.0.0/8", "10.1.0.0/16", true, Some("10.1.0.0/16")),
+            ("10.1.0.0/16", "10.0.0.0/8", false, Some("10.1.0.0/16")),
+            ("10.0.0.0/24", "10.0.0.0/24", true, Some("10.0.0.0/24")),
+            ("10.0.0.0/25", "10.0.0.128/25", false, None),
+            ("10.0.0.0/8", "2001:db8::/32", false, None),
+            ("2001:db8::/32", "2001:db8:1::/48", true, Some("2001:db8:1::/48")),
+        ];
+        for (a, b, contains, intersection) in cases {
+            assert_eq!(net(a).contains_net(&net(b)), contains, "{} ⊇ {}", a, b);
+            assert_eq!(net(a).intersect(&net(b)), intersection.map(net), "{} ∩ {}", a, b);
+            assert_eq!(net(b).intersect(&net(a)), intersection.map(net), "{} ∩ {}", b, a);
+        }
+    }
+
+    #[test]
+    fn nets_cover_considera_a_uniao() {
+        let nets = |list: &[&str]| list.iter().map(|s| net(s)).collect::<Vec<_>>();
+        let cases: &[(&[&str], &[&str], bool)] = &[
+            (&["10.0.0.0/25", "10.0.0.128/25"], &["10.0.0.0/24"], true),
+            (&["10.0.0.128/25", "10.0.0.0/25"], &["10.0.0.0/24", "10.0.0.7"], true),
+            (&["10.0.0.0/25", "10.0.1.0/24"], &["10.0.0.0/23"], false),
+            (&["10.0.0.0/24", "10.0.1.0/24", "10.0.2.0/23"], &["10.0.0.0/22"], true),
+            (&["10.0.0.0/8"], &["10.1.0.0/16", "11.0.0.0/8"], false),
+            (&["0.0.0.0/1", "128.0.0.0/1"], &["0.0.0.0/0"], true),
+            (&["0.0.0.0/0"], &["::/0"], false),
+            (&["::/1", "8000::/1"], &["::/0", "2001:db8::1"], true),
+            (&["255.255.255.255"], &["255.255.255.255"], true),
+            (&[], &["10.0.0.1"], false),
+        ];
+        for (outer, inner, expected) in cases {
+            assert_eq!(nets_cover(&nets(outer), &nets(inner)), *expected, "{:?} ⊇ {:?}", outer, inner);
+        }
+    }
+
+    #[test]
+    fn serde_usa_texto() {
+        let nets: Vec<IpNet> = serde_json::from_str(r#"["10.0.0.0/8", "::1"]"#).unwrap();
+        assert_eq!(serde_json::to_string(&nets).unwrap(), r#"["10.0.0.0/8","::1"]"#);
+        assert!(serde_json::from_str::<IpNet>(r#""10.0.0.1/8""#).is_err());
+    }
+
+    #[test]
+    fn subtract_divide_ate_isolar_a_rede_removida() {
+        let names = |nets: Vec<IpNet>| nets.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            names(net("10.0.0.0/24").subtract(&net("10.0.0.64/26"))),
+            vec!["10.0.0.128/25", "10.0.0.0/26"]
+        );
+        assert_eq!(names(net("10.0.0.0/24").subtract(&net("10.0.0.0/8"))), Vec::<String>::new());
+        assert_eq!(names(net("10.0.0.0/24").subtract(&net("10.0.1.0/24"))), vec!["10.0.0.0/24"]);
+        assert_eq!(names(net("::/0").subtract(&net("8000::/1"))), vec!["::/1"]);
+
+        let halves = [net("10.0.0.0/25"), net("10.0.0.128/25")];
+        assert!(subtract_nets(&[net("10.0.0.0/24")], &halves).is_empty());
+        assert_eq!(names(subtract_nets(&[net("10.0.0.0/24")], &halves[..1])), vec!["10.0.0.128/25"]);
+    }
+}
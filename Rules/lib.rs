@@ -0,0 +1,17 @@
+//! Regras do firewall: leitura, validação, avaliação, análise e exportação
+#![allow(non_snake_case, non_camel_case_types)] // Nomes seguem os arquivos e a struct `rules`
+
+pub mod analyzeRules;
+pub mod connTrack;
+pub mod evalRules;
+pub mod exportRules;
+pub mod importRules;
+pub mod ipNet;
+pub mod objectSets;
+pub mod portSpec;
+pub mod readRules;
+pub mod ruleSchema;
+pub mod ruleStats;
+pub mod ruleStore;
+pub mod schedule;
+pub mod writeRules;
@@ -0,0 +1,318 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use chrono_tz::Tz;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::connTrack::ConnState;
+use super::ipNet::{IpNet, NetParseError};
+use super::portSpec::PortSpec;
+use super::schedule::{is_expired, Schedule};
+use super::writeRules::{rules, Action, Direction, FirewallConfig, Protocol};
+
+/// Endereço usado em `src_ip`/`dst_ip`: rede literal ou conjunto nomeado
+///
+/// Conjuntos são referenciados com `@` (ex.: `"src_ip": "@admins"`) e
+/// declarados em `address_sets`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum AddrRef {
+    Net(IpNet),
+    Set(String),
+}
+
+impl AddrRef {
+    /// Rede literal, se não for uma referência a conjunto
+    pub fn net(&self) -> Option<&IpNet> {
+        match self {
+            AddrRef::Net(net) => Some(net),
+            AddrRef::Set(_) => None,
+        }
+    }
+}
+
+impl From<IpNet> for AddrRef {
+    fn from(net: IpNet) -> Self {
+        AddrRef::Net(net)
+    }
+}
+
+impl fmt::Display for AddrRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddrRef::Net(net) => write!(f, "{}", net),
+            AddrRef::Set(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+impl FromStr for AddrRef {
+    type Err = NetParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().strip_prefix('@') {
+            Some(name) if !name.is_empty() => Ok(AddrRef::Set(name.to_string())),
+            Some(_) => Err(NetParseError::InvalidAddress(s.to_string())),
+            None => s.parse().map(AddrRef::Net),
+        }
+    }
+}
+
+impl TryFrom<String> for AddrRef {
+    type Error = NetParseError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<AddrRef> for String {
+    fn from(addr: AddrRef) -> Self {
+        addr.to_string()
+    }
+}
+
+impl JsonSchema for AddrRef {
+    fn schema_name() -> String {
+        "AddrRef".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        serde_json::from_value(json!({
+            "description": "IP único, rede CIDR ou conjunto de address_sets (\"@nome\")",
+            "type": "string",
+            "minLength": 1
+        }))
+        .expect("schema estático válido")
+    }
+}
+
+/// Portas usadas em `src_port`/`dst_port`: especificação literal ou conjunto nomeado
+///
+/// Conjuntos são referenciados com `@` (ex.: `"dst_port": "@web"`) e
+/// declarados em `port_sets`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub enum PortRef {
+    Ports(PortSpec),
+    Set(String),
+}
+
+impl PortRef {
+    /// Especificação literal, se não for uma referência a conjunto
+    pub fn ports(&self) -> Option<&PortSpec> {
+        match self {
+            PortRef::Ports(ports) => Some(ports),
+            PortRef::Set(_) => None,
+        }
+    }
+}
+
+impl From<PortSpec> for PortRef {
+    fn from(ports: PortSpec) -> Self {
+        PortRef::Ports(ports)
+    }
+}
+
+impl fmt::Display for PortRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PortRef::Ports(ports) => write!(f, "{}", ports),
+            PortRef::Set(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+impl TryFrom<Value> for PortRef {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        if let Some(name) = value.as_str().and_then(|s| s.trim().strip_prefix('@')) {
+            if name.is_empty() {
+                return Err("nome de conjunto de portas vazio".to_string());
+            }
+            return Ok(PortRef::Set(name.to_string()));
+        }
+        PortSpec::deserialize(value)
+            .map(PortRef::Ports)
+            .map_err(|e| e.to_string())
+    }
+}
+
+impl JsonSchema for PortRef {
+    fn schema_name() -> String {
+        "PortRef".to_string()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        let set: Schema = serde_json::from_value(json!({
+            "description": "Conjunto de port_sets (\"@nome\")",
+            "type": "string",
+            "pattern": "^\\s*@.+$"
+        }))
+        .expect("schema estático válido");
+        let mut schema = schemars::schema::SchemaObject::default();
+        schema.subschemas().any_of = Some(vec![gen.subschema_for::<PortSpec>(), set]);
+        schema.into()
+    }
+}
+
+impl From<PortRef> for Value {
+    fn from(ports: PortRef) -> Self {
+        match ports {
+            PortRef::Ports(ports) => serde_json::to_value(ports).unwrap_or(Value::Null),
+            PortRef::Set(name) => Value::String(format!("@{}", name)),
+        }
+    }
+}
+
+/// Grupo nomeado de regras, habilitado ou desabilitado em conjunto
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct RuleGroup {
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Default for RuleGroup {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            description: None,
+        }
+    }
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// Regra pronta para avaliação: conjuntos nomeados já expandidos
+///
+/// Campos `None` significam "qualquer valor"; listas de endereços casam
+/// se qualquer uma das redes casar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRule {
+    pub index: usize, // Posição da regra em `FirewallConfig::rules`
+    pub name: String,
+    pub action: Action,
+    pub direction: Direction,
+    pub protocol: Protocol,
+    pub src_ip: Option<Vec<IpNet>>,
+    pub dst_ip: Option<Vec<IpNet>>,
+    pub src_port: Option<PortSpec>,
+    pub dst_port: Option<PortSpec>,
+    pub state: Option<Vec<ConnState>>,
+    pub log: bool,
+    pub schedule: Option<Schedule>,
+    pub expires_at: Option<DateTime<FixedOffset>>,
+}
+
+impl ResolvedRule {
+    /// Regra com condição de tempo (janela ou expiração)
+    pub fn is_timed(&self) -> bool {
+        self.schedule.is_some() || self.expires_at.is_some()
+    }
+
+    /// Verifica expiração e janela de horário no instante `now`
+    pub fn is_active_at(&self, now: DateTime<Utc>, timezone: Tz) -> bool {
+        if is_expired(self.expires_at.as_ref(), now) {
+            return false;
+        }
+        match &self.schedule {
+            Some(schedule) => schedule.contains(&now.with_timezone(&timezone)),
+            None => true,
+        }
+    }
+}
+
+impl FirewallConfig {
+    /// Verifica se a regra está ativa (grupos não declarados estão habilitados)
+    pub fn is_enabled(&self, rule: &rules) -> bool {
+        match &rule.group {
+            Some(group) => self.groups.get(group).is_none_or(|group| group.enabled),
+            None => true,
+        }
+    }
+
+    /// Regras ativas na ordem de avaliação
+    ///
+    /// A ordem é dada por `priority` (menor valor primeiro; ausente vale 0),
+    /// com empates mantendo a ordem do arquivo. Regras de grupos
+    /// desabilitados são omitidas.
+    pub fn resolved_rules(&self) -> Vec<ResolvedRule> {
+        let mut resolved: Vec<ResolvedRule> = (0..self.rules.len())
+            .filter_map(|index| self.resolve_rule(index))
+            .collect();
+        resolved.sort_by_key(|rule| self.rules[rule.index].priority.unwrap_or(0));
+        resolved
+    }
+
+    /// Regra `index` com os conjuntos expandidos; `None` se estiver
+    /// desabilitada ou referenciar um conjunto inexistente
+    pub fn resolve_rule(&self, index: usize) -> Option<ResolvedRule> {
+        let rule = self.rules.get(index)?;
+        if !self.is_enabled(rule) {
+            return None;
+        }
+
+        Some(ResolvedRule {
+            index,
+            name: rule.name.clone(),
+            action: rule.action,
+            direction: rule.direction,
+            protocol: rule.protocol,
+            src_ip: self.resolve_addr(rule.src_ip.as_ref())?,
+            dst_ip: self.resolve_addr(rule.dst_ip.as_ref())?,
+            src_port: self.resolve_ports(rule.src_port.as_ref())?,
+            dst_port: self.resolve_ports(rule.dst_port.as_ref())?,
+            state: rule.state.clone(),
+            log: rule.log,
+            schedule: rule.schedule.clone(),
+            expires_at: rule.expires_at,
+        })
+    }
+
+    /// Referências a conjuntos não declarados, como (regra, campo, conjunto)
+    pub fn unresolved_references(&self) -> Vec<(usize, &'static str, String)> {
+        let mut missing = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            for (field, addr) in [("src_ip", &rule.src_ip), ("dst_ip", &rule.dst_ip)] {
+                if let Some(AddrRef::Set(name)) = addr {
+                    if !self.address_sets.contains_key(name) {
+                        missing.push((index, field, name.clone()));
+                    }
+                }
+            }
+            for (field, ports) in [("src_port", &rule.src_port), ("dst_port", &rule.dst_port)] {
+                if let Some(PortRef::Set(name)) = ports {
+                    if !self.port_sets.contains_key(name) {
+                        missing.push((index, field, name.clone()));
+                    }
+                }
+            }
+        }
+        missing
+    }
+
+    /// O `Option` externo `None` indica conjunto inexistente
+    fn resolve_addr(&self, addr: Option<&AddrRef>) -> Option<Option<Vec<IpNet>>> {
+        match addr {
+            None => Some(None),
+            Some(AddrRef::Net(net)) => Some(Some(vec![*net])),
+            Some(AddrRef::Set(name)) => self.address_sets.get(name).cloned().map(Some),
+        }
+    }
+
+    fn resolve_ports(&self, ports: Option<&PortRef>) -> Option<Option<PortSpec>> {
+        match ports {
+            None => Some(None),
+            Some(PortRef::Ports(ports)) => Some(Some(ports.clone())),
+            Some(PortRef::Set(name)) => self.port_sets.get(name).cloned().map(Some),
+        }
+    }
+}
@@ -0,0 +1,378 @@
+use std::{fmt, str::FromStr};
+
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// Intervalo fechado de portas (ex.: 8000-8100)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PortRange {
+    pub start: u16,
+    pub end: u16,
+}
+
+impl PortRange {
+    pub fn new(start: u16, end: u16) -> Result<Self, PortParseError> {
+        if start > end {
+            return Err(PortParseError::ReversedRange { start, end });
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn single(port: u16) -> Self {
+        Self { start: port, end: port }
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.start <= port && port <= self.end
+    }
+}
+
+impl fmt::Display for PortRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+impl FromStr for PortRange {
+    type Err = PortParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.split_once('-') {
+            Some((start, end)) => PortRange::new(parse_port(start)?, parse_port(end)?),
+            None => parse_port(s).map(PortRange::single),
+        }
+    }
+}
+
+/// Conjunto de portas aceito em `src_port`/`dst_port`
+///
+/// No JSON pode ser um número (`22`), um intervalo (`"8000-8100"`)
+/// ou uma lista combinando ambos (`[80, 443, "8000-8100"]`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "RawPortSpec", into = "RawPortSpec")]
+pub struct PortSpec {
+    ranges: Vec<PortRange>,
+}
+
+impl PortSpec {
+    pub fn new(ranges: Vec<PortRange>) -> Result<Self, PortParseError> {
+        if ranges.is_empty() {
+            return Err(PortParseError::Empty);
+        }
+        Ok(Self { ranges })
+    }
+
+    pub fn single(port: u16) -> Self {
+        Self {
+            ranges: vec![PortRange::single(port)],
+        }
+    }
+
+    pub fn ranges(&self) -> &[PortRange] {
+        &self.ranges
+    }
+
+    pub fn contains(&self, port: u16) -> bool {
+        self.ranges.iter().any(|range| range.contains(port))
+    }
+
+    /// Verifica se todas as portas de `other` também pertencem a este conjunto
+    pub fn contains_spec(&self, other: &PortSpec) -> bool {
+        let merged = merge_ranges(&self.ranges);
+        other.ranges.iter().all(|range| {
+            merged
+                .iter()
+                .any(|outer| outer.start <= range.start && range.end <= outer.end)
+        })
+    }
+
+    /// Portas presentes nos dois conjuntos, ou `None` se forem disjuntos
+    pub fn intersect(&self, other: &PortSpec) -> Option<PortSpec> {
+        let ranges: Vec<PortRange> = self
+            .ranges
+            .iter()
+            .flat_map(|a| {
+                other.ranges.iter().filter_map(move |b| {
+                    let start = a.start.max(b.start);
+                    let end = a.end.min(b.end);
+                    (start <= end).then_some(PortRange { start, end })
+                })
+            })
+            .collect();
+
+        PortSpec::new(merge_ranges(&ranges)).ok()
+    }
+
+    /// Portas deste conjunto fora de `other`, ou `None` se não sobrar nenhuma
+    pub fn subtract(&self, other: &PortSpec) -> Option<PortSpec> {
+        let mut ranges = merge_ranges(&self.ranges);
+        for removed in merge_ranges(&other.ranges) {
+            ranges = ranges
+                .into_iter()
+                .flat_map(|range| {
+                    if removed.end < range.start || range.end < removed.start {
+                        return vec![range];
+                    }
+                    let mut left = Vec::new();
+                    if range.start < removed.start {
+                        left.push(PortRange { start: range.start, end: removed.start - 1 });
+                    }
+                    if removed.end < range.end {
+                        left.push(PortRange { start: removed.end + 1, end: range.end });
+                    }
+                    left
+                })
+                .collect();
+        }
+        PortSpec::new(ranges).ok()
+    }
+}
+
+impl fmt::Display for PortSpec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let parts: Vec<String> = self.ranges.iter().map(|r| r.to_string()).collect();
+        write!(f, "{}", parts.join(","))
+    }
+}
+
+/// Erro ao interpretar uma especificação de portas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PortParseError {
+    Invalid(String),
+    OutOfRange(u64),
+    ReversedRange { start: u16, end: u16 },
+    Empty,
+}
+
+impl fmt::Display for PortParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PortParseError::Invalid(value) => write!(f, "porta inválida '{}'", value),
+            PortParseError::OutOfRange(port) => {
+                write!(f, "porta {} fora do intervalo 0-65535", port)
+            }
+            PortParseError::ReversedRange { start, end } => {
+                write!(f, "intervalo de portas invertido {}-{}", start, end)
+            }
+            PortParseError::Empty => write!(f, "lista de portas vazia"),
+        }
+    }
+}
+
+impl std::error::Error for PortParseError {}
+
+impl JsonSchema for PortSpec {
+    fn schema_name() -> String {
+        "PortSpec".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        let item = json!([
+            { "type": "integer", "minimum": 0, "maximum": 65535 },
+            { "type": "string", "pattern": "^\\s*\\d+\\s*(-\\s*\\d+\\s*)?$" }
+        ]);
+        serde_json::from_value(json!({
+            "description": "Porta única, intervalo (\"8000-8100\") ou lista ([80, 443, \"8000-8100\"])",
+            "anyOf": [
+                item[0],
+                item[1],
+                { "type": "array", "minItems": 1, "items": { "anyOf": item } }
+            ]
+        }))
+        .expect("schema estático válido")
+    }
+}
+
+/// Representação serde de `PortSpec`, preservando a forma usada no JSON
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawPortSpec {
+    Number(u64),
+    Text(String),
+    List(Vec<RawPortItem>),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum RawPortItem {
+    Number(u64),
+    Text(String),
+}
+
+impl RawPortItem {
+    fn into_range(self) -> Result<PortRange, PortParseError> {
+        match self {
+            RawPortItem::Number(port) => port_from_u64(port).map(PortRange::single),
+            RawPortItem::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl TryFrom<RawPortSpec> for PortSpec {
+    type Error = PortParseError;
+
+    fn try_from(raw: RawPortSpec) -> Result<Self, Self::Error> {
+        match raw {
+            RawPortSpec::Number(port) => Ok(PortSpec::single(port_from_u64(port)?)),
+            RawPortSpec::Text(text) => PortSpec::new(vec![text.parse()?]),
+            RawPortSpec::List(items) => PortSpec::new(
+                items
+                    .into_iter()
+                    .map(RawPortItem::into_range)
+                    .collect::<Result<_, _>>()?,
+            ),
+        }
+    }
+}
+
+impl From<PortSpec> for RawPortSpec {
+    fn from(spec: PortSpec) -> Self {
+        match spec.ranges.as_slice() {
+            [range] if range.start == range.end => RawPortSpec::Number(range.start as u64),
+            [range] => RawPortSpec::Text(range.to_string()),
+            ranges => RawPortSpec::List(
+                ranges
+                    .iter()
+                    .map(|range| {
+                        if range.start == range.end {
+                            RawPortItem::Number(range.start as u64)
+                        } else {
+                            RawPortItem::Text(range.to_string())
+                        }
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn parse_port(s: &str) -> Result<u16, PortParseError> {
+    let port: u64 = s
+        .trim()
+        .parse()
+        .map_err(|_| PortParseError::Invalid(s.to_string()))?;
+    port_from_u64(port)
+}
+
+fn port_from_u64(port: u64) -> Result<u16, PortParseError> {
+    u16::try_from(port).map_err(|_| PortParseError::OutOfRange(port))
+}
+
+/// Ordena e funde intervalos sobrepostos ou adjacentes
+fn merge_ranges(ranges: &[PortRange]) -> Vec<PortRange> {
+    let mut sorted = ranges.to_vec();
+    sorted.sort();
+
+    let mut merged: Vec<PortRange> = Vec::with_capacity(sorted.len());
+    for range in sorted {
+        match merged.last_mut() {
+            Some(last) if range.start as u32 <= last.end as u32 + 1 => {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u16, end: u16) -> PortRange {
+        PortRange { start, end }
+    }
+
+    fn spec(json: &str) -> PortSpec {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn interpreta_formas_do_json() {
+        let cases: &[(&str, Result<Vec<PortRange>, PortParseError>)] = &[
+            ("22", Ok(vec![range(22, 22)])),
+            (r#""8000-8100""#, Ok(vec![range(8000, 8100)])),
+            (r#"" 80 - 81 ""#, Ok(vec![range(80, 81)])),
+            (r#""443""#, Ok(vec![range(443, 443)])),
+            (r#"[80, 443, "8000-8100"]"#, Ok(vec![range(80, 80), range(443, 443), range(8000, 8100)])),
+            (r#""90-80""#, Err(PortParseError::ReversedRange { start: 90, end: 80 })),
+            ("70000", Err(PortParseError::OutOfRange(70000))),
+            (r#""1-70000""#, Err(PortParseError::OutOfRange(70000))),
+            (r#""http""#, Err(PortParseError::Invalid("http".to_string()))),
+            ("[]", Err(PortParseError::Empty)),
+        ];
+        for (json, expected) in cases {
+            let raw: RawPortSpec = serde_json::from_str(json).unwrap();
+            let parsed = PortSpec::try_from(raw).map(|spec| spec.ranges().to_vec());
+            assert_eq!(&parsed, expected, "{}", json);
+        }
+    }
+
+    #[test]
+    fn serializa_na_forma_original() {
+        for json in ["22", r#""8000-8100""#, r#"[80,443,"8000-8100"]"#] {
+            assert_eq!(serde_json::to_string(&spec(json)).unwrap(), json);
+        }
+        assert_eq!(spec(r#"[80, "1000-2000"]"#).to_string(), "80,1000-2000");
+    }
+
+    #[test]
+    fn contains() {
+        let web = spec(r#"[80, 443, "8000-8100"]"#);
+        for (port, expected) in [(80, true), (443, true), (8000, true), (8100, true), (81, false), (8101, false)] {
+            assert_eq!(web.contains(port), expected, "{}", port);
+        }
+    }
+
+    #[test]
+    fn contains_spec_e_intersect() {
+        let cases = [
+            (r#""1-1024""#, r#"[22, 80, 443]"#, true, Some(r#"[22,80,443]"#)),
+            (r#"["1-100", "101-200"]"#, r#""50-150""#, true, Some(r#""50-150""#)),
+            (r#""1-100""#, r#""50-150""#, false, Some(r#""50-100""#)),
+            ("22", "23", false, None),
+            (r#"[80, 443]"#, r#""80-443""#, false, Some(r#"[80,443]"#)),
+        ];
+        for (a, b, contains, intersection) in cases {
+            assert_eq!(spec(a).contains_spec(&spec(b)), contains, "{} ⊇ {}", a, b);
+            assert_eq!(spec(a).intersect(&spec(b)), intersection.map(spec), "{} ∩ {}", a, b);
+        }
+    }
+
+    #[test]
+    fn merge_ranges_funde_sobrepostos_e_adjacentes() {
+        let cases = [
+            (vec![], vec![]),
+            (vec![range(10, 20), range(1, 5)], vec![range(1, 5), range(10, 20)]),
+            (vec![range(1, 10), range(5, 20)], vec![range(1, 20)]),
+            (vec![range(1, 10), range(11, 20)], vec![range(1, 20)]),
+            (vec![range(1, 100), range(20, 30)], vec![range(1, 100)]),
+            (vec![range(0, 65535), range(65535, 65535)], vec![range(0, 65535)]),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(merge_ranges(&input), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn subtract_remove_intervalos() {
+        let spec = |ranges: Vec<PortRange>| PortSpec::new(ranges).unwrap();
+        let cases = [
+            (vec![range(1, 100)], vec![range(20, 30)], Some(vec![range(1, 19), range(31, 100)])),
+            (vec![range(1, 100)], vec![range(1, 50), range(90, 200)], Some(vec![range(51, 89)])),
+            (vec![range(0, 65535)], vec![range(0, 0), range(65535, 65535)], Some(vec![range(1, 65534)])),
+            (vec![range(10, 20)], vec![range(30, 40)], Some(vec![range(10, 20)])),
+            (vec![range(10, 20), range(30, 40)], vec![range(0, 65535)], None),
+        ];
+        for (a, b, expected) in cases {
+            let result = spec(a.clone()).subtract(&spec(b));
+            assert_eq!(result.as_ref().map(PortSpec::ranges), expected.as_deref(), "{:?}", a);
+        }
+    }
+}
@@ -1,24 +1,390 @@
-use std::fs;
+use std::{fmt, fs, io, path::Path};
 
+use serde_json::Value;
+
+use super::ruleSchema::StrictReport;
+use super::schedule::ScheduleError;
+use super::writeRules::{rules, Action, FirewallConfig, Protocol};
+
+/// Problema encontrado em uma regra durante a validação
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleIssue {
+    pub index: usize,         // Posição da regra no array `rules`
+    pub name: Option<String>, // Nome da regra, quando legível
+    pub field: String,        // Campo inválido ("action", "dst_port", ...)
+    pub message: String,
+}
+
+impl fmt::Display for RuleIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(
+                f,
+                "regra #{} ('{}'), campo '{}': {}",
+                self.index, name, self.field, self.message
+            ),
+            None => write!(
+                f,
+                "regra #{}, campo '{}': {}",
+                self.index, self.field, self.message
+            ),
+        }
+    }
+}
+
+/// Relatório com todas as regras inválidas de um arquivo
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    pub issues: Vec<RuleIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} regra(s) inválida(s):", self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "   - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Formato do arquivo de regras, escolhido pela extensão
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleFormat {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl RuleFormat {
+    /// Identifica o formato pela extensão (.json, .yaml/.yml, .toml)
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(RuleFormat::Json),
+            "yaml" | "yml" => Some(RuleFormat::Yaml),
+            "toml" => Some(RuleFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Erro ao carregar o arquivo de regras
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    UnsupportedFormat(String), // Extensão não reconhecida
+    Parse(serde_json::Error),  // Arquivo não é um JSON válido
+    Yaml(serde_yaml::Error),
+    Toml(toml::de::Error),
+    Invalid(ValidationReport), // Sintaxe válida, mas com regras inválidas
+    Schedule(ScheduleError),   // Fuso horário da configuração inválido
+    Strict(StrictReport),      // Violações da validação estrita, com linha e coluna
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "falha ao ler arquivo de regras: {}", e),
+            LoadError::UnsupportedFormat(path) => write!(
+                f,
+                "formato de '{}' não suportado (use .json, .yaml, .yml ou .toml)",
+                path
+            ),
+            LoadError::Parse(e) => write!(f, "falha ao parsear regras: {}", e),
+            LoadError::Yaml(e) => write!(f, "falha ao parsear regras YAML: {}", e),
+            LoadError::Toml(e) => write!(f, "falha ao parsear regras TOML: {}", e),
+            LoadError::Invalid(report) => write!(f, "{}", report),
+            LoadError::Schedule(e) => write!(f, "{}", e),
+            LoadError::Strict(report) => write!(f, "{}", report),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(e: serde_json::Error) -> Self {
+        LoadError::Parse(e)
+    }
+}
+
+/// Carrega e valida o arquivo de regras (JSON, YAML ou TOML)
+pub fn carregar_regras(path: &str) -> Result<FirewallConfig, LoadError> {
+    let format = RuleFormat::from_path(path)
+        .ok_or_else(|| LoadError::UnsupportedFormat(path.to_string()))?;
+    let data = fs::read_to_string(path)?;
+    validar_regras_formato(&data, format)
+}
+
+/// Valida o conteúdo de um rules.json, coletando os erros de todas as regras
+pub fn validar_regras(json_data: &str) -> Result<FirewallConfig, LoadError> {
+    validar_regras_formato(json_data, RuleFormat::Json)
+}
+
+/// Valida o conteúdo de um arquivo de regras no formato informado
+///
+/// YAML e TOML são convertidos para a mesma árvore JSON antes da
+/// validação, produzindo exatamente o mesmo `FirewallConfig`.
+pub fn validar_regras_formato(data: &str, format: RuleFormat) -> Result<FirewallConfig, LoadError> {
+    let value: Value = match format {
+        RuleFormat::Json => serde_json::from_str(data)?,
+        RuleFormat::Yaml => serde_yaml::from_str(data).map_err(LoadError::Yaml)?,
+        RuleFormat::Toml => toml::from_str(data).map_err(LoadError::Toml)?,
+    };
+    validar_config(value)
+}
+
+/// Valida uma configuração já parseada
+///
+/// Cada regra é verificada isoladamente para que o relatório liste todas
+/// as regras inválidas, e não apenas a primeira encontrada pelo serde.
+pub fn validar_config(mut value: Value) -> Result<FirewallConfig, LoadError> {
+    let mut report = ValidationReport::default();
+
+    if let Some(entries) = value.get_mut("rules").and_then(Value::as_array_mut) {
+        for (index, entry) in entries.iter_mut().enumerate() {
+            normalizar_regra(entry);
+            if let Err(issue) = validar_regra(index, entry) {
+                report.issues.push(issue);
+            }
+        }
+    }
+
+    if !report.is_valid() {
+        return Err(LoadError::Invalid(report));
+    }
+
+    let config: FirewallConfig = serde_json::from_value(value)?;
+
+    // Referências "@conjunto" precisam estar declaradas em address_sets/port_sets
+    for (index, field, set) in config.unresolved_references() {
+        report.issues.push(RuleIssue {
+            index,
+            name: Some(config.rules[index].name.clone()),
+            field: field.to_string(),
+            message: format!("conjunto '@{}' não declarado", set),
+        });
+    }
+    if !report.is_valid() {
+        return Err(LoadError::Invalid(report));
+    }
+    config.timezone().map_err(LoadError::Schedule)?;
+
+    Ok(config)
+}
+
+/// Deriva `direction` a partir de `chain` (INPUT/OUTPUT) quando ausente,
+/// como no formato YAML documentado no README principal
+fn normalizar_regra(entry: &mut Value) {
+    let Some(rule) = entry.as_object_mut() else {
+        return;
+    };
+    if rule.contains_key("direction") {
+        return;
+    }
+
+    let direction = match rule.get("chain").and_then(Value::as_str) {
+        Some(chain) if chain.eq_ignore_ascii_case("INPUT") => "inbound",
+        Some(chain) if chain.eq_ignore_ascii_case("OUTPUT") => "outbound",
+        _ => return,
+    };
+    rule.insert("direction".to_string(), Value::from(direction));
+}
+
+fn validar_regra(index: usize, entry: &Value) -> Result<rules, RuleIssue> {
+    let rule: rules = serde_path_to_error::deserialize(entry).map_err(|e| {
+        let path = e.path().to_string();
+        let message = e.into_inner().to_string();
+
+        // O caminho "." indica erro na regra como um todo (ex.: campo obrigatório ausente
+        // ou desconhecido)
+        let field = if path == "." {
+            error_field(&message).unwrap_or("(regra)").to_string()
+        } else {
+            path
+        };
+
+        RuleIssue {
+            index,
+            name: entry.get("name").and_then(Value::as_str).map(String::from),
+            field,
+            message,
+        }
+    })?;
+
+    // ICMP não tem portas: a regra nunca casaria e os exportadores não a representam
+    if rule.protocol == Protocol::Icmp {
+        for (field, present) in [("src_port", rule.src_port.is_some()), ("dst_port", rule.dst_port.is_some())] {
+            if present {
+                return Err(RuleIssue {
+                    index,
+                    name: Some(rule.name.clone()),
+                    field: field.to_string(),
+                    message: "protocolo icmp não tem portas; use tcp, udp ou any".to_string(),
+                });
+            }
+        }
+    }
+    Ok(rule)
+}
+
+/// Extrai o nome do campo de mensagens "missing field `x`" ou "unknown field `x`" do serde
+fn error_field(message: &str) -> Option<&str> {
+    message
+        .strip_prefix("missing field `")
+        .or_else(|| message.strip_prefix("unknown field `"))
+        .and_then(|rest| rest.split('`').next())
+}
+
+#[allow(dead_code)] // Exemplo de uso; a CLI fica em simulateRules.rs
 fn main() {
-    // Lê o arquivo JSON
-    let json_data = fs::read_to_string("rules.json")
-        .expect("Falha ao ler o arquivo JSON");
+    // Lê e desserializa o arquivo JSON
+    let config = match carregar_regras("rules.json") {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            std::process::exit(1);
+        }
+    };
 
-    // Desserializa para a struct FirewallConfig
-    let config: FirewallConfig = serde_json::from_str(&json_data)
-        .expect("Falha ao parsear JSON");
+    for (index, rule) in config.expired_rules(chrono::Utc::now()) {
+        println!("⌛ Regra #{} ('{}') expirada, será ignorada", index, rule.name);
+    }
 
     // Itera sobre as regras
     for rule in config.rules {
-        println!("Regra: {:?}", rules);
-        
+        println!("Regra: {:?}", rule);
+
         // Exemplo: Aplicar regra (simulação)
-        match rule.action.as_str() {
-            "allow" => println!("✅ Permitir: {}", rule.name),
-            "deny" => println!("❌ Bloquear: {}", rule.name),
-            "log" => println!("📝 Logar: {}", rule.name),
-            _ => println!("⚡ Ação desconhecida"),
+        match rule.action {
+            Action::Allow => println!("✅ Permitir: {}", rule.name),
+            Action::Deny => println!("❌ Bloquear: {}", rule.name),
+            Action::Log => println!("📝 Logar: {}", rule.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn issues(json: &str) -> Vec<RuleIssue> {
+        match validar_regras(json) {
+            Err(LoadError::Invalid(report)) => report.issues,
+            other => panic!("esperado relatório de validação, obtido {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn relata_indice_e_campo_de_cada_regra_invalida() {
+        let json = r#"{ "rules": [
+            { "name": "ok", "action": "allow", "direction": "inbound", "protocol": "tcp" },
+            { "name": "typo", "action": "alow", "direction": "inbound", "protocol": "tcp" },
+            { "name": "porta", "action": "deny", "direction": "inbound", "protocol": "udp", "dst_port": "90-80" },
+            { "action": "deny", "direction": "inbound", "protocol": "tcp" }
+        ] }"#;
+        let found: Vec<(usize, Option<String>, String)> = issues(json)
+            .into_iter()
+            .map(|issue| (issue.index, issue.name, issue.field))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, Some("typo".to_string()), "action".to_string()),
+                (2, Some("porta".to_string()), "dst_port".to_string()),
+                (3, None, "name".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn campo_invalido_localizado_por_linha_no_modo_estrito() {
+        let json = "{ \"rules\": [\n  { \"name\": \"typo\",\n    \"action\": \"alow\",\n    \"direction\": \"inbound\", \"protocol\": \"tcp\" }\n] }";
+        assert_eq!(issues(json)[0].field, "action");
+        match super::super::ruleSchema::validar_regras_estrito(json) {
+            Err(LoadError::Strict(report)) => {
+                let issue = &report.issues[0];
+                assert_eq!((issue.line, issue.column, issue.path.as_str()), (3, 5, "rules[0].action"));
+            }
+            other => panic!("esperado relatório estrito, obtido {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn mensagem_inclui_caminho_do_campo() {
+        let json = r#"{ "rules": [
+            { "name": "janela", "action": "allow", "direction": "inbound", "protocol": "tcp",
+              "schedule": { "days": ["mon", "xyz"] } }
+        ] }"#;
+        let issue = &issues(json)[0];
+        assert_eq!(issue.field, "schedule.days[1]");
+        assert!(issue.to_string().starts_with("regra #0 ('janela'), campo 'schedule.days[1]': "), "{}", issue);
+    }
+
+    #[test]
+    fn conjunto_nao_declarado() {
+        let json = r#"{ "rules": [
+            { "name": "admins", "action": "allow", "direction": "inbound", "protocol": "tcp", "src_ip": "@admins" }
+        ] }"#;
+        let issue = &issues(json)[0];
+        assert_eq!((issue.index, issue.field.as_str()), (0, "src_ip"));
+        assert_eq!(issue.message, "conjunto '@admins' não declarado");
+    }
+
+    #[test]
+    fn erro_de_sintaxe_informa_linha_e_coluna() {
+        let json = "{ \"rules\": [\n  { \"name\": \"a\",\n    \"action\": allow }\n] }";
+        match validar_regras(json) {
+            Err(LoadError::Parse(e)) => assert_eq!((e.line(), e.column()), (3, 15)),
+            other => panic!("esperado erro de sintaxe, obtido {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn campo_desconhecido_rejeitado() {
+        let json = r#"{ "rules": [
+            { "name": "typo", "action": "allow", "direction": "inbound", "protocol": "tcp", "dst_prot": 22 }
+        ] }"#;
+        let issue = &issues(json)[0];
+        assert_eq!((issue.index, issue.field.as_str()), (0, "dst_prot"));
+        assert!(issue.message.starts_with("unknown field `dst_prot`"), "{}", issue.message);
+    }
+
+    #[test]
+    fn chain_define_direction() {
+        let json = r#"{ "rules": [
+            { "name": "entrada", "action": "ACCEPT", "chain": "INPUT", "protocol": "tcp" }
+        ] }"#;
+        let config = validar_regras(json).unwrap();
+        assert_eq!(config.rules[0].direction, super::super::writeRules::Direction::Inbound);
+        assert_eq!(config.rules[0].action, Action::Allow);
+    }
+
+    #[test]
+    fn icmp_com_porta_rejeitado() {
+        let json = "{ \"rules\": [\n  { \"name\": \"ping\", \"action\": \"allow\", \"direction\": \"inbound\",\n    \"protocol\": \"icmp\", \"dst_port\": 7 },\n  { \"name\": \"ok\", \"action\": \"allow\", \"direction\": \"inbound\", \"protocol\": \"icmp\" }\n] }";
+        let found = issues(json);
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].index, found[0].field.as_str()), (0, "dst_port"));
+        match super::super::ruleSchema::validar_regras_estrito(json) {
+            Err(LoadError::Strict(report)) => {
+                let issue = &report.issues[0];
+                assert_eq!((issue.line, issue.path.as_str()), (3, "rules[0].dst_port"));
+            }
+            other => panic!("esperado relatório estrito, obtido {:?}", other.map(|_| ())),
         }
     }
-}
\ No newline at end of file
+}
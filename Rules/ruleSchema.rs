@@ -0,0 +1,503 @@
+use std::{collections::HashMap, fmt, fs};
+
+use serde_json::Value;
+
+use super::readRules::{validar_config, LoadError};
+use super::writeRules::FirewallConfig;
+
+/// JSON Schema (draft-07) de `FirewallConfig`, gerado a partir dos tipos
+///
+/// Descreve o formato canônico gravado por `atualizar_regras`; sinônimos
+/// aceitos pelo carregador (`ACCEPT`, `source`, `port`, ...) não fazem parte dele.
+pub fn gerar_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(FirewallConfig)).unwrap_or(Value::Null)
+}
+
+/// Schema formatado, pronto para gravar como `rules.schema.json`
+pub fn gerar_schema_json() -> String {
+    serde_json::to_string_pretty(&gerar_schema()).unwrap_or_default()
+}
+
+/// Problema encontrado pela validação estrita, com sua posição no arquivo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrictIssue {
+    pub line: usize,   // 1-based
+    pub column: usize, // 1-based, em caracteres
+    pub path: String,  // Ex.: "rules[1].dst_port"
+    pub message: String,
+}
+
+impl fmt::Display for StrictIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "linha {}, coluna {}: {}: {}",
+            self.line, self.column, self.path, self.message
+        )
+    }
+}
+
+/// Relatório da validação estrita, ordenado por posição
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrictReport {
+    pub issues: Vec<StrictIssue>,
+}
+
+impl StrictReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for StrictReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} problema(s) na validação estrita:", self.issues.len())?;
+        for issue in &self.issues {
+            writeln!(f, "   - {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Carrega um rules.json em modo estrito
+pub fn carregar_regras_estrito(path: &str) -> Result<FirewallConfig, LoadError> {
+    validar_regras_estrito(&fs::read_to_string(path)?)
+}
+
+/// Valida um rules.json em modo estrito
+///
+/// Além da validação normal, rejeita campos desconhecidos, valores fora do
+/// schema (enums, portas fora de 0-65535) e nomes de regra duplicados.
+/// Todos os problemas são reportados com linha e coluna.
+pub fn validar_regras_estrito(json_data: &str) -> Result<FirewallConfig, LoadError> {
+    // Erros de sintaxe do serde_json já trazem linha e coluna
+    let value: Value = serde_json::from_str(json_data)?;
+    let positions = JsonPositions::scan(json_data);
+    let schema = gerar_schema();
+
+    let mut found: Vec<(String, String)> = Vec::new();
+    check_schema(&value, &schema, &schema, "", &mut found);
+    check_duplicate_names(&value, &mut found);
+
+    let result = validar_config(value.clone());
+    match &result {
+        Ok(_) => {}
+        Err(LoadError::Invalid(report)) => {
+            for issue in &report.issues {
+                let pointer = format!("/rules/{}{}", issue.index, field_pointer(&issue.field));
+                found.push((pointer, issue.message.clone()));
+            }
+        }
+        Err(LoadError::Schedule(e)) => found.push(("/timezone".to_string(), e.to_string())),
+        Err(_) => {
+            // Erro fora das regras (ex.: address_sets): localiza pelo caminho
+            if let Err(e) = serde_path_to_error::deserialize::<_, FirewallConfig>(value) {
+                let pointer = field_pointer(&e.path().to_string());
+                found.push((pointer, e.into_inner().to_string()));
+            }
+        }
+    }
+
+    // Um mesmo valor pode ser apontado pelo schema e pela validação normal
+    let mut report = StrictReport::default();
+    let mut seen = std::collections::HashSet::new();
+    for (pointer, message) in found {
+        if !seen.insert(pointer.clone()) {
+            continue;
+        }
+        let (line, column) = positions.locate(&pointer);
+        report.issues.push(StrictIssue {
+            line,
+            column,
+            path: display_pointer(&pointer),
+            message,
+        });
+    }
+    report.issues.sort_by_key(|issue| (issue.line, issue.column));
+
+    if !report.is_valid() {
+        return Err(LoadError::Strict(report));
+    }
+    result
+}
+
+/// Percorre o valor junto com o schema gerado, coletando campos desconhecidos,
+/// valores fora de `enum` e números fora de `minimum`/`maximum`
+fn check_schema(value: &Value, schema: &Value, root: &Value, pointer: &str, found: &mut Vec<(String, String)>) {
+    let Some(schema) = pick_schema(value, schema, root) else {
+        return;
+    };
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+        if !allowed.contains(value) {
+            let names: Vec<String> = allowed.iter().map(|v| v.to_string()).collect();
+            found.push((
+                pointer.to_string(),
+                format!("valor {} não permitido, esperado um de {}", value, names.join(", ")),
+            ));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        let minimum = schema.get("minimum").and_then(Value::as_f64);
+        let maximum = schema.get("maximum").and_then(Value::as_f64);
+        if minimum.is_some_and(|min| number < min) || maximum.is_some_and(|max| number > max) {
+            found.push((
+                pointer.to_string(),
+                format!(
+                    "valor {} fora do intervalo {}-{}",
+                    value,
+                    minimum.map_or("*".to_string(), |min| min.to_string()),
+                    maximum.map_or("*".to_string(), |max| max.to_string())
+                ),
+            ));
+        }
+    }
+
+    match value {
+        Value::Object(map) => {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            let additional = schema.get("additionalProperties");
+            for (key, child) in map {
+                let child_pointer = format!("{}/{}", pointer, escape_pointer(key));
+                match (properties.and_then(|p| p.get(key)), additional) {
+                    (Some(property), _) => check_schema(child, property, root, &child_pointer, found),
+                    (None, Some(Value::Bool(false))) => found.push((
+                        child_pointer,
+                        format!("campo desconhecido '{}'", key),
+                    )),
+                    (None, Some(additional @ Value::Object(_))) => {
+                        check_schema(child, additional, root, &child_pointer, found)
+                    }
+                    (None, _) => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check_schema(item, item_schema, root, &format!("{}/{}", pointer, index), found);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolve `$ref` e escolhe o ramo de `anyOf`/`allOf` compatível com o tipo do valor
+fn pick_schema<'a>(value: &Value, schema: &'a Value, root: &'a Value) -> Option<&'a Value> {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        let target = root.pointer(reference.trim_start_matches('#'))?;
+        return pick_schema(value, target, root);
+    }
+
+    for key in ["anyOf", "oneOf", "allOf"] {
+        if let Some(branches) = schema.get(key).and_then(Value::as_array) {
+            return branches.iter().find_map(|branch| {
+                let branch = pick_schema(value, branch, root)?;
+                type_matches(value, branch).then_some(branch)
+            });
+        }
+    }
+    Some(schema)
+}
+
+fn type_matches(value: &Value, schema: &Value) -> bool {
+    let name = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let accepts = |t: &Value| t == name || (t == "number" && name == "integer");
+    match schema.get("type") {
+        None => true,
+        Some(Value::Array(types)) => types.iter().any(accepts),
+        Some(t) => accepts(t),
+    }
+}
+
+fn check_duplicate_names(value: &Value, found: &mut Vec<(String, String)>) {
+    let Some(entries) = value.get("rules").and_then(Value::as_array) else {
+        return;
+    };
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let Some(name) = entry.get("name").and_then(Value::as_str) else {
+            continue;
+        };
+        match first_seen.get(name) {
+            Some(first) => found.push((
+                format!("/rules/{}/name", index),
+                format!("nome '{}' duplicado (já usado pela regra #{})", name, first),
+            )),
+            None => {
+                first_seen.insert(name, index);
+            }
+        }
+    }
+}
+
+/// Converte "schedule.hours[0]" (serde_path_to_error) em "/schedule/hours/0"
+fn field_pointer(field: &str) -> String {
+    if field == "." || field.starts_with('(') {
+        return String::new();
+    }
+    let mut pointer = String::new();
+    for part in field.split('.') {
+        let (name, indexes) = part.split_once('[').unwrap_or((part, ""));
+        if !name.is_empty() {
+            pointer.push('/');
+            pointer.push_str(&escape_pointer(name));
+        }
+        for index in indexes.split('[').filter(|index| !index.is_empty()) {
+            pointer.push('/');
+            pointer.push_str(index.trim_end_matches(']'));
+        }
+    }
+    pointer
+}
+
+/// Converte "/rules/1/dst_port" em "rules[1].dst_port"
+fn display_pointer(pointer: &str) -> String {
+    let mut path = String::new();
+    for segment in pointer.split('/').skip(1) {
+        let segment = segment.replace("~1", "/").replace("~0", "~");
+        if segment.parse::<usize>().is_ok() {
+            path.push_str(&format!("[{}]", segment));
+        } else {
+            if !path.is_empty() {
+                path.push('.');
+            }
+            path.push_str(&segment);
+        }
+    }
+    if path.is_empty() {
+        "(raiz)".to_string()
+    } else {
+        path
+    }
+}
+
+fn escape_pointer(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Linha e coluna de cada chave e valor de um documento JSON, por JSON pointer
+#[derive(Debug, Default)]
+struct JsonPositions {
+    values: HashMap<String, (usize, usize)>,
+    keys: HashMap<String, (usize, usize)>,
+}
+
+impl JsonPositions {
+    /// Indexa um documento já validado pelo serde_json
+    fn scan(text: &str) -> Self {
+        let mut scanner = Scanner {
+            chars: text.chars().collect(),
+            index: 0,
+            line: 1,
+            column: 1,
+            positions: JsonPositions::default(),
+        };
+        scanner.value(String::new());
+        scanner.positions
+    }
+
+    /// Posição da chave (ou do valor) apontado; cai para o ancestral mais
+    /// próximo quando o campo não existe (ex.: campo obrigatório ausente)
+    fn locate(&self, pointer: &str) -> (usize, usize) {
+        let mut pointer = pointer;
+        loop {
+            if let Some(position) = self.keys.get(pointer).or_else(|| self.values.get(pointer)) {
+                return *position;
+            }
+            match pointer.rfind('/') {
+                Some(end) => pointer = &pointer[..end],
+                None => return (1, 1),
+            }
+        }
+    }
+}
+
+struct Scanner {
+    chars: Vec<char>,
+    index: usize,
+    line: usize,
+    column: usize,
+    positions: JsonPositions,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.index += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.advance();
+        }
+    }
+
+    fn value(&mut self, pointer: String) {
+        self.skip_whitespace();
+        self.positions
+            .values
+            .insert(pointer.clone(), (self.line, self.column));
+
+        match self.peek() {
+            Some('{') => {
+                self.advance();
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some('"') {
+                        self.advance(); // '}' de objeto vazio
+                        return;
+                    }
+                    let position = (self.line, self.column);
+                    let child = format!("{}/{}", pointer, escape_pointer(&self.string()));
+                    self.positions.keys.insert(child.clone(), position);
+                    self.skip_whitespace();
+                    self.advance(); // ':'
+                    self.value(child);
+                    self.skip_whitespace();
+                    if self.advance() != Some(',') {
+                        return;
+                    }
+                }
+            }
+            Some('[') => {
+                self.advance();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.advance();
+                    return;
+                }
+                let mut index = 0;
+                loop {
+                    self.value(format!("{}/{}", pointer, index));
+                    self.skip_whitespace();
+                    if self.advance() != Some(',') {
+                        return;
+                    }
+                    index += 1;
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | ']' | '}'))
+                {
+                    self.advance();
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut text = String::new();
+        self.advance(); // '"'
+        while let Some(c) = self.advance() {
+            match c {
+                '"' => break,
+                '\\' => match self.advance() {
+                    Some('u') => {
+                        let hex: String = (0..4).filter_map(|_| self.advance()).collect();
+                        let code = u32::from_str_radix(&hex, 16).unwrap_or(0xFFFD);
+                        text.push(char::from_u32(code).unwrap_or('\u{FFFD}'));
+                    }
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some('b') => text.push('\u{8}'),
+                    Some('f') => text.push('\u{c}'),
+                    Some(other) => text.push(other),
+                    None => break,
+                },
+                _ => text.push(c),
+            }
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_issues(json: &str) -> Vec<(usize, usize, String, String)> {
+        match validar_regras_estrito(json) {
+            Err(LoadError::Strict(report)) => report
+                .issues
+                .into_iter()
+                .map(|issue| (issue.line, issue.column, issue.path, issue.message))
+                .collect(),
+            other => panic!("esperado relatório estrito, obtido {:?}", other.map(|_| ())),
+        }
+    }
+
+    const DOCUMENTO: &str = r#"{
+  "rules": [
+    { "name": "SSH", "action": "allow", "direction": "inbound", "protocol": "tcp",
+      "dst_prot": 22 },
+    { "name": "DNS", "action": "allow", "direction": "inbound",
+      "protocol": "udpp" },
+    { "name": "SSH", "action": "deny", "direction": "inbound", "protocol": "tcp" }
+  ]
+}"#;
+
+    #[test]
+    fn localiza_cada_problema_por_linha_e_coluna() {
+        let found: Vec<(usize, usize, String)> = strict_issues(DOCUMENTO)
+            .into_iter()
+            .map(|(line, column, path, _)| (line, column, path))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (4, 7, "rules[0].dst_prot".to_string()),
+                (6, 7, "rules[1].protocol".to_string()),
+                (7, 7, "rules[2].name".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mensagens_descrevem_o_problema() {
+        let messages: Vec<String> = strict_issues(DOCUMENTO)
+            .into_iter()
+            .map(|(.., message)| message)
+            .collect();
+        assert_eq!(messages[0], "campo desconhecido 'dst_prot'");
+        assert!(messages[1].starts_with("valor \"udpp\" não permitido"), "{}", messages[1]);
+        assert_eq!(messages[2], "nome 'SSH' duplicado (já usado pela regra #0)");
+    }
+
+    #[test]
+    fn documento_valido_passa() {
+        let json = DOCUMENTO
+            .replace(",\n      \"dst_prot\": 22", "")
+            .replace("udpp", "udp")
+            .replace("\"SSH\", \"action\": \"deny\"", "\"Bloquear\", \"action\": \"deny\"");
+        let config = validar_regras_estrito(&json).unwrap();
+        assert_eq!(config.rules.len(), 3);
+    }
+}
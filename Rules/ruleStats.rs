@@ -0,0 +1,291 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    sync::{
+        atomic::{AtomicI64, AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::writeRules::FirewallConfig;
+
+// Valor de `last_matched` para regras que nunca casaram
+const NEVER: i64 = i64::MIN;
+
+/// Contadores de uma regra, atualizados sem lock durante a avaliação
+#[derive(Debug)]
+pub struct RuleCounter {
+    hits: AtomicU64,
+    bytes: AtomicU64,
+    last_matched: AtomicI64, // Milissegundos desde a época Unix
+}
+
+impl Default for RuleCounter {
+    fn default() -> Self {
+        Self {
+            hits: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            last_matched: AtomicI64::new(NEVER),
+        }
+    }
+}
+
+impl RuleCounter {
+    pub fn record(&self, bytes: u64, now: DateTime<Utc>) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.last_matched
+            .fetch_max(now.timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn reset(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.bytes.store(0, Ordering::Relaxed);
+        self.last_matched.store(NEVER, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> RuleCounters {
+        let last_matched = match self.last_matched.load(Ordering::Relaxed) {
+            NEVER => None,
+            millis => Utc.timestamp_millis_opt(millis).single(),
+        };
+        RuleCounters {
+            hits: self.hits.load(Ordering::Relaxed),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            last_matched,
+        }
+    }
+}
+
+/// Valores dos contadores em um instante
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleCounters {
+    pub hits: u64,
+    pub bytes: u64,
+    pub last_matched: Option<DateTime<Utc>>,
+}
+
+/// Contadores de uma regra no relatório
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleHits {
+    pub index: usize, // Posição da regra em `FirewallConfig::rules`
+    pub name: String,
+    #[serde(flatten)]
+    pub counters: RuleCounters,
+}
+
+/// Estatísticas de todas as regras, na ordem do arquivo
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub rules: Vec<RuleHits>,
+    pub default_policy: RuleCounters, // Fluxos que não casaram com nenhuma regra
+}
+
+impl StatsSnapshot {
+    /// Contadores da primeira regra com o nome; use `rules[index]` se houver nomes repetidos
+    pub fn get(&self, name: &str) -> Option<&RuleCounters> {
+        self.rules
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| &entry.counters)
+    }
+
+    /// Regras que nunca casaram desde o início da contagem
+    pub fn unused(&self) -> impl Iterator<Item = &str> {
+        self.rules
+            .iter()
+            .filter(|entry| entry.counters.hits == 0)
+            .map(|entry| entry.name.as_str())
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Tabela de contadores indexada pela posição da regra no arquivo
+///
+/// Os contadores são compartilhados por `Arc`, de modo que uma nova versão
+/// do conjunto de regras (recarga) continua somando nas regras que manteve.
+/// Regras com o mesmo nome têm contadores separados.
+#[derive(Debug, Default)]
+pub struct RuleStats {
+    names: Vec<String>,
+    counters: Vec<Arc<RuleCounter>>, // Um por regra, na ordem de `FirewallConfig::rules`
+    default_policy: Arc<RuleCounter>,
+}
+
+impl RuleStats {
+    /// Cria contadores zerados para cada regra do conjunto
+    pub fn new(config: &FirewallConfig) -> Self {
+        Self::inherit(config, &RuleStats::default())
+    }
+
+    /// Cria a tabela para `config`, reaproveitando os contadores de
+    /// `previous` para as regras com o mesmo nome
+    ///
+    /// Com nomes repetidos, a n-ésima regra de um nome herda da n-ésima
+    /// regra com esse nome em `previous`.
+    pub fn inherit(config: &FirewallConfig, previous: &RuleStats) -> Self {
+        let mut previous_by_name: HashMap<&str, Vec<&Arc<RuleCounter>>> = HashMap::new();
+        for (name, counter) in previous.names.iter().zip(&previous.counters).rev() {
+            previous_by_name.entry(name.as_str()).or_default().push(counter);
+        }
+
+        let names: Vec<String> = config.rules.iter().map(|rule| rule.name.clone()).collect();
+        let counters = names
+            .iter()
+            .map(|name| {
+                previous_by_name
+                    .get_mut(name.as_str())
+                    .and_then(|counters| counters.pop())
+                    .cloned()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        Self {
+            names,
+            counters,
+            default_policy: Arc::clone(&previous.default_policy),
+        }
+    }
+
+    /// Contadores da regra na posição `index` de `FirewallConfig::rules`
+    pub fn counter(&self, index: usize) -> Option<&Arc<RuleCounter>> {
+        self.counters.get(index)
+    }
+
+    pub fn default_policy(&self) -> &Arc<RuleCounter> {
+        &self.default_policy
+    }
+
+    pub fn snapshot(&self) -> StatsSnapshot {
+        StatsSnapshot {
+            rules: self
+                .names
+                .iter()
+                .zip(&self.counters)
+                .enumerate()
+                .map(|(index, (name, counter))| RuleHits {
+                    index,
+                    name: name.clone(),
+                    counters: counter.snapshot(),
+                })
+                .collect(),
+            default_policy: self.default_policy.snapshot(),
+        }
+    }
+
+    pub fn reset(&self) {
+        for counter in &self.counters {
+            counter.reset();
+        }
+        self.default_policy.reset();
+    }
+}
+
+/// Grava as estatísticas em JSON (ex.: para auditoria ou busca de regras mortas)
+pub fn gravar_estatisticas(path: &str, snapshot: &StatsSnapshot) -> io::Result<()> {
+    fs::write(path, snapshot.to_json()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(names: &[&str]) -> FirewallConfig {
+        let rules: Vec<_> = names
+            .iter()
+            .map(|name| json!({ "name": name, "action": "allow", "direction": "inbound", "protocol": "tcp" }))
+            .collect();
+        serde_json::from_value(json!({ "rules": rules })).unwrap()
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    fn hits(stats: &RuleStats) -> Vec<u64> {
+        stats.snapshot().rules.iter().map(|entry| entry.counters.hits).collect()
+    }
+
+    #[test]
+    fn nomes_repetidos_herdam_pela_ordem_de_ocorrencia() {
+        let previous = RuleStats::new(&config(&["a", "dup", "dup"]));
+        let now = at("2026-10-18T12:00:00Z");
+        previous.counter(1).unwrap().record(10, now);
+        for _ in 0..2 {
+            previous.counter(2).unwrap().record(20, now);
+        }
+
+        // Reordenadas: a primeira "dup" continua sendo a primeira, mesmo em outra posição
+        let stats = RuleStats::inherit(&config(&["dup", "b", "a", "dup", "dup"]), &previous);
+        assert_eq!(hits(&stats), vec![1, 0, 0, 2, 0]);
+        assert!(Arc::ptr_eq(stats.counter(0).unwrap(), previous.counter(1).unwrap()));
+        assert!(Arc::ptr_eq(stats.counter(3).unwrap(), previous.counter(2).unwrap()));
+        assert!(Arc::ptr_eq(stats.counter(2).unwrap(), previous.counter(0).unwrap()));
+    }
+
+    #[test]
+    fn regra_renomeada_comeca_do_zero() {
+        let previous = RuleStats::new(&config(&["ssh"]));
+        previous.counter(0).unwrap().record(100, at("2026-10-18T12:00:00Z"));
+        previous.default_policy().record(50, at("2026-10-18T12:00:01Z"));
+
+        let stats = RuleStats::inherit(&config(&["ssh-admin"]), &previous);
+        assert_eq!(stats.snapshot().rules[0].counters, RuleCounters::default());
+        // A política padrão não depende de nomes e sempre continua
+        assert!(Arc::ptr_eq(stats.default_policy(), previous.default_policy()));
+        assert_eq!(stats.snapshot().default_policy.hits, 1);
+    }
+
+    #[test]
+    fn reset_zera_regras_e_politica_padrao() {
+        let stats = RuleStats::new(&config(&["a", "b"]));
+        stats.counter(0).unwrap().record(10, at("2026-10-18T12:00:00Z"));
+        stats.default_policy().record(5, at("2026-10-18T12:00:00Z"));
+
+        stats.reset();
+        let snapshot = stats.snapshot();
+        assert!(snapshot.rules.iter().all(|entry| entry.counters == RuleCounters::default()));
+        assert_eq!(snapshot.default_policy, RuleCounters::default());
+
+        // Após o reset, um casamento mais antigo que o anterior ainda é registrado
+        let earlier = at("2026-10-18T08:00:00Z");
+        stats.counter(0).unwrap().record(1, earlier);
+        assert_eq!(
+            stats.snapshot().rules[0].counters,
+            RuleCounters { hits: 1, bytes: 1, last_matched: Some(earlier) }
+        );
+    }
+
+    #[test]
+    fn unused_e_json() {
+        let stats = RuleStats::new(&config(&["a", "b", "c"]));
+        stats.counter(1).unwrap().record(1500, at("2026-10-18T12:00:00.250Z"));
+        let snapshot = stats.snapshot();
+
+        assert_eq!(snapshot.unused().collect::<Vec<_>>(), vec!["a", "c"]);
+        assert_eq!(snapshot.get("b").map(|counters| counters.hits), Some(1));
+
+        let text = snapshot.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "rules": [
+                    { "index": 0, "name": "a", "hits": 0, "bytes": 0, "last_matched": null },
+                    { "index": 1, "name": "b", "hits": 1, "bytes": 1500, "last_matched": "2026-10-18T12:00:00.250Z" },
+                    { "index": 2, "name": "c", "hits": 0, "bytes": 0, "last_matched": null }
+                ],
+                "default_policy": { "hits": 0, "bytes": 0, "last_matched": null }
+            })
+        );
+        assert_eq!(serde_json::from_str::<StatsSnapshot>(&text).unwrap(), snapshot);
+    }
+}
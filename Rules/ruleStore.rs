@@ -0,0 +1,442 @@
+use std::{
+    fmt, fs,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use chrono::Utc;
+
+use super::evalRules::{RuleEngine, Verdict};
+use super::objectSets::ResolvedRule;
+use super::readRules::{carregar_regras, LoadError};
+use super::writeRules::FirewallConfig;
+
+/// Diferença entre duas versões do conjunto de regras (por nome de regra)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>, // Mesmo nome, conteúdo ou efeito diferente (grupo, conjuntos)
+    pub moved: Vec<String>,   // Mesmo conteúdo, posição relativa diferente
+}
+
+impl RuleDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.moved.is_empty()
+    }
+}
+
+impl fmt::Display for RuleDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "nenhuma alteração nas regras");
+        }
+        let sections = [
+            ("➕ Adicionadas", &self.added),
+            ("➖ Removidas", &self.removed),
+            ("✏️ Alteradas", &self.changed),
+            ("↕️ Reordenadas", &self.moved),
+        ];
+        let mut first = true;
+        for (title, names) in sections {
+            if names.is_empty() {
+                continue;
+            }
+            if !first {
+                writeln!(f)?;
+            }
+            first = false;
+            write!(f, "{}: {}", title, names.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Compara duas versões do conjunto de regras
+pub fn diff_regras(old: &FirewallConfig, new: &FirewallConfig) -> RuleDiff {
+    let mut diff = RuleDiff::default();
+
+    // Regra efetiva: muda também quando o grupo é desabilitado ou um conjunto é editado
+    let effective = |config: &FirewallConfig, index: usize| {
+        config
+            .resolve_rule(index)
+            .map(|resolved| ResolvedRule { index: 0, ..resolved })
+    };
+    for (index, rule) in new.rules.iter().enumerate() {
+        match old.position(&rule.name) {
+            None => diff.added.push(rule.name.clone()),
+            Some(previous)
+                if old.rules[previous] != *rule
+                    || effective(old, previous) != effective(new, index) =>
+            {
+                diff.changed.push(rule.name.clone())
+            }
+            Some(_) => {}
+        }
+    }
+    for rule in &old.rules {
+        if new.find(&rule.name).is_none() {
+            diff.removed.push(rule.name.clone());
+        }
+    }
+
+    // Ordem relativa das regras presentes nas duas versões
+    let common = |config: &FirewallConfig, other: &FirewallConfig| -> Vec<String> {
+        config
+            .rules
+            .iter()
+            .filter(|rule| other.find(&rule.name).is_some())
+            .map(|rule| rule.name.clone())
+            .collect()
+    };
+    let old_order = common(old, new);
+    let new_order = common(new, old);
+    let stable = longest_common_subsequence(&old_order, &new_order);
+    for name in new_order {
+        if !stable.contains(&name) && !diff.changed.contains(&name) {
+            diff.moved.push(name);
+        }
+    }
+
+    diff
+}
+
+/// Regras que mantiveram a ordem relativa; as demais foram movidas
+fn longest_common_subsequence(a: &[String], b: &[String]) -> Vec<String> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut result = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            result.push(a[i].clone());
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    result
+}
+
+/// Conjunto de regras ativo com recarga a quente
+///
+/// Avaliadores obtêm o motor atual com `engine()`; uma recarga bem-sucedida
+/// troca o `Arc` de uma vez, então avaliações em andamento terminam com a
+/// versão anterior e as seguintes já usam a nova.
+pub struct RuleStore {
+    path: String,
+    default_policy: Verdict,
+    active: RwLock<Arc<RuleEngine>>,
+    last_modified: Mutex<Option<SystemTime>>,
+    watching: AtomicBool,
+}
+
+impl RuleStore {
+    /// Carrega o arquivo inicial; falha se ele for inválido
+    pub fn open(path: &str, default_policy: Verdict) -> Result<Self, LoadError> {
+        let last_modified = modified_time(path);
+        let config = carregar_regras(path)?;
+
+        Ok(Self {
+            path: path.to_string(),
+            default_policy,
+            active: RwLock::new(Arc::new(
+                RuleEngine::new(config).with_default_policy(default_policy),
+            )),
+            last_modified: Mutex::new(last_modified),
+            watching: AtomicBool::new(false),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Motor de avaliação ativo no momento
+    pub fn engine(&self) -> Arc<RuleEngine> {
+        Arc::clone(&self.active.read().unwrap())
+    }
+
+    /// Recarrega o arquivo e troca o conjunto ativo
+    ///
+    /// Se a nova versão for inválida, o conjunto anterior continua ativo e o
+    /// erro é retornado. Retorna a diferença aplicada.
+    pub fn reload(&self) -> Result<RuleDiff, LoadError> {
+        let modified = modified_time(&self.path);
+        let config = carregar_regras(&self.path)?;
+
+        let diff = {
+            let mut active = self.active.write().unwrap();
+            // Os contadores das regras mantidas continuam de onde estavam e as
+            // conexões já rastreadas sobrevivem à recarga
+            let new_engine = RuleEngine::new(config)
+                .with_default_policy(self.default_policy)
+                .with_stats_from(&active)
+                .with_conntrack(Arc::clone(active.conntrack()));
+            let diff = diff_regras(active.config(), new_engine.config());
+            *active = Arc::new(new_engine);
+            diff
+        };
+        *self.last_modified.lock().unwrap() = modified;
+
+        Ok(diff)
+    }
+
+    /// Recarrega apenas se o arquivo foi modificado desde a última leitura
+    pub fn reload_if_changed(&self) -> Option<Result<RuleDiff, LoadError>> {
+        let modified = modified_time(&self.path);
+        let mut last_modified = self.last_modified.lock().unwrap();
+        if modified.is_none() || modified == *last_modified {
+            return None;
+        }
+        // Registra a tentativa para não repetir o erro a cada verificação
+        *last_modified = modified;
+        drop(last_modified);
+
+        Some(self.reload())
+    }
+
+    /// Inicia uma thread que verifica o arquivo a cada `interval`
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> thread::JoinHandle<()> {
+        let store = Arc::clone(self);
+        store.watching.store(true, Ordering::SeqCst);
+
+        thread::spawn(move || {
+            println!("👀 Observando {} (intervalo {:?})", store.path, interval);
+            while store.watching.load(Ordering::SeqCst) {
+                thread::sleep(interval);
+                match store.reload_if_changed() {
+                    None => {}
+                    Some(Ok(diff)) => {
+                        println!("🔄 Regras recarregadas de {}:\n{}", store.path, diff);
+                        let engine = store.engine();
+                        for (index, rule) in engine.config().expired_rules(Utc::now()) {
+                            println!("⌛ Regra #{} ('{}') expirada, ignorada", index, rule.name);
+                        }
+                    }
+                    Some(Err(e)) => eprintln!(
+                        "❌ Nova versão de {} rejeitada, mantendo regras anteriores:\n{}",
+                        store.path, e
+                    ),
+                }
+            }
+        })
+    }
+
+    /// Sinaliza a thread de observação para encerrar
+    pub fn stop_watching(&self) {
+        self.watching.store(false, Ordering::SeqCst);
+    }
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evalRules::Flow;
+    use crate::writeRules::{atualizar_regras, Direction, Protocol};
+    use serde_json::json;
+    use std::{path::PathBuf, sync::atomic::AtomicUsize};
+
+    /// Diretório temporário removido ao sair do teste
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "rule_store_{}_{}",
+                std::process::id(),
+                NEXT.fetch_add(1, Ordering::Relaxed)
+            ));
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        fn file(&self, name: &str) -> String {
+            self.0.join(name).to_string_lossy().into_owned()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn config(rules: serde_json::Value) -> FirewallConfig {
+        serde_json::from_value(json!({ "rules": rules })).unwrap()
+    }
+
+    fn rule(name: &str, action: &str, port: u16) -> serde_json::Value {
+        json!({
+            "name": name, "action": action, "direction": "inbound", "protocol": "tcp", "dst_port": port
+        })
+    }
+
+    fn ssh(src_port: u16) -> Flow {
+        Flow {
+            direction: Direction::Inbound,
+            protocol: Protocol::Tcp,
+            src_ip: "192.168.1.7".parse().unwrap(),
+            dst_ip: "10.0.0.1".parse().unwrap(),
+            src_port: Some(src_port),
+            dst_port: Some(22),
+            bytes: 60,
+            tcp_flags: None,
+        }
+    }
+
+    /// Regrava o arquivo mantendo o mtime, como uma edição dentro da mesma resolução do relógio
+    fn rewrite_keeping_mtime(path: &str, config: &FirewallConfig) {
+        let modified = modified_time(path).unwrap();
+        atualizar_regras(path, config).unwrap();
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    fn bump_mtime(path: &str) {
+        let modified = modified_time(path).unwrap() + Duration::from_secs(5);
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn diff_regras_classifica_alteracoes() {
+        let old = config(json!([
+            rule("A", "allow", 1),
+            rule("B", "allow", 2),
+            rule("C", "allow", 3),
+            rule("D", "allow", 4),
+            rule("F", "deny", 6),
+        ]));
+        let new = config(json!([
+            rule("B", "allow", 2),
+            rule("C", "deny", 3),
+            rule("D", "allow", 4),
+            rule("A", "allow", 1),
+            rule("E", "allow", 5),
+        ]));
+        let diff = diff_regras(&old, &new);
+        assert_eq!(
+            diff,
+            RuleDiff {
+                added: vec!["E".to_string()],
+                removed: vec!["F".to_string()],
+                changed: vec!["C".to_string()],
+                moved: vec!["A".to_string()],
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "➕ Adicionadas: E\n➖ Removidas: F\n✏️ Alteradas: C\n↕️ Reordenadas: A"
+        );
+        assert!(diff_regras(&old, &old).is_empty());
+
+        // Desabilitar o grupo muda o efeito sem mudar a regra
+        let mut grouped = old.clone();
+        grouped.rules[1].group = Some("manutenção".to_string());
+        let mut disabled = grouped.clone();
+        disabled.set_group_enabled("manutenção", false);
+        assert_eq!(diff_regras(&grouped, &disabled).changed, vec!["B".to_string()]);
+    }
+
+    #[test]
+    fn reload_invalido_mantem_motor_anterior() {
+        let dir = TempDir::new();
+        let path = dir.file("rules.json");
+        atualizar_regras(&path, &config(json!([rule("SSH", "allow", 22)]))).unwrap();
+        let store = RuleStore::open(&path, Verdict::Deny).unwrap();
+        let before = store.engine();
+
+        fs::write(&path, r#"{ "rules": [ { "name": "SSH", "action": "talvez" } ] }"#).unwrap();
+        assert!(store.reload().is_err());
+        assert!(Arc::ptr_eq(&before, &store.engine()));
+        assert_eq!(store.engine().evaluate(&ssh(40000)).verdict, Verdict::Allow);
+
+        // A tentativa registrada evita repetir o erro a cada verificação
+        bump_mtime(&path);
+        assert!(matches!(store.reload_if_changed(), Some(Err(_))));
+        assert!(store.reload_if_changed().is_none());
+    }
+
+    #[test]
+    fn reload_if_changed_so_recarrega_com_mtime_novo() {
+        let dir = TempDir::new();
+        let path = dir.file("rules.json");
+        atualizar_regras(&path, &config(json!([rule("SSH", "allow", 22)]))).unwrap();
+        let store = RuleStore::open(&path, Verdict::Deny).unwrap();
+        let before = store.engine();
+        assert!(store.reload_if_changed().is_none());
+
+        // Conteúdo novo com o mesmo mtime passa despercebido
+        let blocked = config(json!([rule("SSH", "deny", 22)]));
+        rewrite_keeping_mtime(&path, &blocked);
+        assert!(store.reload_if_changed().is_none());
+        assert!(Arc::ptr_eq(&before, &store.engine()));
+
+        bump_mtime(&path);
+        let diff = store.reload_if_changed().unwrap().unwrap();
+        assert_eq!(diff.changed, vec!["SSH".to_string()]);
+        assert_eq!(store.engine().evaluate(&ssh(40000)).verdict, Verdict::Deny);
+        assert!(store.reload_if_changed().is_none());
+    }
+
+    #[test]
+    fn contadores_e_conexoes_sobrevivem_a_recarga() {
+        let dir = TempDir::new();
+        let path = dir.file("rules.json");
+        atualizar_regras(&path, &config(json!([rule("SSH", "allow", 22)]))).unwrap();
+        let store = RuleStore::open(&path, Verdict::Deny).unwrap();
+        store.engine().evaluate(&ssh(40000));
+        store.engine().evaluate(&ssh(40001));
+        let conntrack = Arc::clone(store.engine().conntrack());
+        assert_eq!(conntrack.len(), 2);
+
+        let retorno = json!({
+            "name": "Retorno", "action": "allow", "direction": "outbound", "protocol": "any",
+            "state": ["established"]
+        });
+        let rules = json!([rule("Web", "allow", 80), rule("SSH", "allow", 22), retorno]);
+        atualizar_regras(&path, &config(rules)).unwrap();
+        let diff = store.reload().unwrap();
+        assert_eq!(diff.added, vec!["Web".to_string(), "Retorno".to_string()]);
+
+        let engine = store.engine();
+        assert!(Arc::ptr_eq(&conntrack, engine.conntrack()));
+        let stats = engine.stats();
+        assert_eq!(stats.get("SSH").unwrap().hits, 2);
+        assert_eq!(stats.get("Web").unwrap().hits, 0);
+
+        // A resposta de uma conexão rastreada antes da recarga é reconhecida como estabelecida
+        let reply = |src_port| {
+            let mut reply = ssh(src_port);
+            reply.direction = Direction::Outbound;
+            std::mem::swap(&mut reply.src_ip, &mut reply.dst_ip);
+            std::mem::swap(&mut reply.src_port, &mut reply.dst_port);
+            reply
+        };
+        let decision = engine.evaluate(&reply(40000));
+        assert_eq!((decision.verdict, decision.rule.as_deref()), (Verdict::Allow, Some("Retorno")));
+        assert_eq!(engine.evaluate(&reply(40002)).verdict, Verdict::Deny);
+    }
+}
@@ -0,0 +1,345 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, FixedOffset, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use super::writeRules::{rules, FirewallConfig};
+
+pub const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Dia da semana aceito em `schedule.days`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DayOfWeek {
+    #[serde(alias = "monday")]
+    Mon,
+    #[serde(alias = "tuesday")]
+    Tue,
+    #[serde(alias = "wednesday")]
+    Wed,
+    #[serde(alias = "thursday")]
+    Thu,
+    #[serde(alias = "friday")]
+    Fri,
+    #[serde(alias = "saturday")]
+    Sat,
+    #[serde(alias = "sunday")]
+    Sun,
+}
+
+impl DayOfWeek {
+    pub fn weekday(self) -> Weekday {
+        match self {
+            DayOfWeek::Mon => Weekday::Mon,
+            DayOfWeek::Tue => Weekday::Tue,
+            DayOfWeek::Wed => Weekday::Wed,
+            DayOfWeek::Thu => Weekday::Thu,
+            DayOfWeek::Fri => Weekday::Fri,
+            DayOfWeek::Sat => Weekday::Sat,
+            DayOfWeek::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// Faixa de horário "HH:MM-HH:MM" (fim exclusivo)
+///
+/// Se o fim for anterior ao início a faixa atravessa a meia-noite
+/// (ex.: "22:00-06:00"); "24:00" é aceito como fim do dia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeRange {
+    start: u16, // Minutos desde a meia-noite
+    end: u16,
+}
+
+impl TimeRange {
+    pub fn new(start: u16, end: u16) -> Result<Self, ScheduleError> {
+        if start >= MINUTES_PER_DAY || end > MINUTES_PER_DAY || start == end {
+            return Err(ScheduleError::InvalidRange(format!(
+                "{}-{}",
+                format_minutes(start),
+                format_minutes(end)
+            )));
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn start(&self) -> u16 {
+        self.start
+    }
+
+    pub fn end(&self) -> u16 {
+        self.end
+    }
+
+    /// Faixa atravessa a meia-noite
+    pub fn wraps(&self) -> bool {
+        self.end < self.start
+    }
+}
+
+impl fmt::Display for TimeRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}-{}", format_minutes(self.start), format_minutes(self.end))
+    }
+}
+
+impl FromStr for TimeRange {
+    type Err = ScheduleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| ScheduleError::InvalidRange(s.to_string()))?;
+        TimeRange::new(parse_minutes(start)?, parse_minutes(end)?)
+    }
+}
+
+impl TryFrom<String> for TimeRange {
+    type Error = ScheduleError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<TimeRange> for String {
+    fn from(range: TimeRange) -> Self {
+        range.to_string()
+    }
+}
+
+impl JsonSchema for TimeRange {
+    fn schema_name() -> String {
+        "TimeRange".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        serde_json::from_value(json!({
+            "description": "Faixa \"HH:MM-HH:MM\" (fim exclusivo; pode atravessar a meia-noite)",
+            "type": "string",
+            "pattern": "^\\s*\\d{1,2}:\\d{2}\\s*-\\s*\\d{1,2}:\\d{2}\\s*$"
+        }))
+        .expect("schema estático válido")
+    }
+}
+
+/// Janela de horário em que a regra vale, no fuso `timezone` da configuração
+///
+/// Listas vazias ou ausentes não restringem: sem `days` a regra vale todos
+/// os dias, sem `hours` vale o dia inteiro.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct Schedule {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<DayOfWeek>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hours: Vec<TimeRange>,
+}
+
+impl Schedule {
+    fn allows_day(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.iter().any(|d| d.weekday() == day)
+    }
+
+    /// Verifica se o horário local está dentro da janela
+    ///
+    /// A parte de uma faixa que passa da meia-noite pertence ao dia em que
+    /// a faixa começou (sexta 22:00-02:00 inclui a madrugada de sábado).
+    pub fn contains<Z: TimeZone>(&self, local: &DateTime<Z>) -> bool {
+        let day = local.weekday();
+        let minute = (local.hour() * 60 + local.minute()) as u16;
+
+        if self.hours.is_empty() {
+            return self.allows_day(day);
+        }
+        self.hours.iter().any(|range| {
+            if range.wraps() {
+                (self.allows_day(day) && minute >= range.start)
+                    || (self.allows_day(day.pred()) && minute < range.end)
+            } else {
+                self.allows_day(day) && range.start <= minute && minute < range.end
+            }
+        })
+    }
+}
+
+/// Erro ao interpretar uma condição de horário
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    InvalidTime(String),
+    InvalidRange(String),
+    UnknownTimezone(String),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScheduleError::InvalidTime(time) => {
+                write!(f, "horário inválido '{}' (esperado HH:MM)", time)
+            }
+            ScheduleError::InvalidRange(range) => {
+                write!(f, "faixa de horário inválida '{}' (esperado HH:MM-HH:MM)", range)
+            }
+            ScheduleError::UnknownTimezone(name) => write!(
+                f,
+                "fuso horário desconhecido '{}' (use nomes IANA, ex.: America/Sao_Paulo)",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+impl FirewallConfig {
+    /// Fuso usado pelas janelas de horário (UTC quando ausente)
+    pub fn timezone(&self) -> Result<Tz, ScheduleError> {
+        match &self.timezone {
+            None => Ok(Tz::UTC),
+            Some(name) => name
+                .parse()
+                .map_err(|_| ScheduleError::UnknownTimezone(name.clone())),
+        }
+    }
+
+    /// Regras cujo `expires_at` já passou, com suas posições
+    pub fn expired_rules(&self, now: DateTime<Utc>) -> Vec<(usize, &rules)> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| is_expired(rule.expires_at.as_ref(), now))
+            .collect()
+    }
+
+    /// Remove as regras expiradas e as retorna
+    pub fn prune_expired(&mut self, now: DateTime<Utc>) -> Vec<rules> {
+        let (expired, active) = std::mem::take(&mut self.rules)
+            .into_iter()
+            .partition(|rule| is_expired(rule.expires_at.as_ref(), now));
+        self.rules = active;
+        expired
+    }
+}
+
+/// A regra deixa de valer no instante `expires_at`
+pub fn is_expired(expires_at: Option<&DateTime<FixedOffset>>, now: DateTime<Utc>) -> bool {
+    expires_at.is_some_and(|expires_at| *expires_at <= now)
+}
+
+fn parse_minutes(s: &str) -> Result<u16, ScheduleError> {
+    let invalid = || ScheduleError::InvalidTime(s.trim().to_string());
+    let (hour, minute) = s.trim().split_once(':').ok_or_else(invalid)?;
+    let hour: u16 = hour.parse().map_err(|_| invalid())?;
+    let minute: u16 = minute.parse().map_err(|_| invalid())?;
+
+    if minute >= 60 || hour > 24 || (hour == 24 && minute != 0) {
+        return Err(invalid());
+    }
+    Ok(hour * 60 + minute)
+}
+
+fn format_minutes(minutes: u16) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schedule(days: &[DayOfWeek], hours: &[&str]) -> Schedule {
+        Schedule {
+            days: days.to_vec(),
+            hours: hours.iter().map(|h| h.parse().unwrap()).collect(),
+        }
+    }
+
+    fn berlin(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+        Tz::Europe__Berlin
+            .with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn faixa_que_atravessa_meia_noite_pertence_ao_dia_de_inicio() {
+        let range: TimeRange = "22:00-06:00".parse().unwrap();
+        assert!(range.wraps());
+
+        // 2026-10-16 é sexta-feira
+        let sexta = schedule(&[DayOfWeek::Fri], &["22:00-06:00"]);
+        let cases = [
+            (berlin(2026, 10, 16, 21, 59), false),
+            (berlin(2026, 10, 16, 22, 0), true),
+            (berlin(2026, 10, 16, 23, 30), true),
+            (berlin(2026, 10, 17, 1, 0), true), // Madrugada de sábado
+            (berlin(2026, 10, 17, 6, 0), false), // Fim exclusivo
+            (berlin(2026, 10, 17, 23, 0), false),
+            (berlin(2026, 10, 16, 5, 0), false), // Madrugada de sexta vem de quinta
+        ];
+        for (local, expected) in cases {
+            assert_eq!(sexta.contains(&local), expected, "{}", local);
+        }
+
+        let todo_dia = schedule(&[], &["22:00-06:00"]);
+        assert!(todo_dia.contains(&berlin(2026, 10, 16, 5, 0)));
+        assert!(!todo_dia.contains(&berlin(2026, 10, 16, 12, 0)));
+    }
+
+    #[test]
+    fn janela_segue_relogio_local_na_mudanca_de_horario() {
+        let tz = Tz::Europe__Berlin;
+        let at = |utc: &str| utc.parse::<DateTime<Utc>>().unwrap().with_timezone(&tz);
+        let manha = schedule(&[], &["08:00-09:00"]);
+
+        // 08:30 local é 07:30 UTC no inverno (+01:00) e 06:30 UTC no verão (+02:00)
+        assert!(manha.contains(&at("2026-03-28T07:30:00Z")));
+        assert!(!manha.contains(&at("2026-03-29T07:30:00Z")));
+        assert!(manha.contains(&at("2026-03-29T06:30:00Z")));
+
+        // 29/03: o relógio pula de 02:00 para 03:00; a faixa 02:00-03:00 não ocorre
+        let madrugada = schedule(&[], &["02:00-03:00"]);
+        assert!(!madrugada.contains(&at("2026-03-29T00:59:00Z")));
+        assert!(!madrugada.contains(&at("2026-03-29T01:00:00Z")));
+
+        // 25/10: 02:00-03:00 acontece duas vezes e ambas casam
+        assert!(madrugada.contains(&at("2026-10-25T00:30:00Z")));
+        assert!(madrugada.contains(&at("2026-10-25T01:30:00Z")));
+        assert!(!madrugada.contains(&at("2026-10-25T02:30:00Z")));
+    }
+
+    #[test]
+    fn prune_expired_remove_apenas_regras_vencidas() {
+        let rule = |name: &str, expires_at: Option<&str>| {
+            let mut rule = json!({ "name": name, "action": "allow", "direction": "inbound", "protocol": "tcp" });
+            if let Some(expires_at) = expires_at {
+                rule["expires_at"] = json!(expires_at);
+            }
+            rule
+        };
+        let mut config: FirewallConfig = serde_json::from_value(json!({ "rules": [
+            rule("permanente", None),
+            rule("vencida", Some("2026-10-01T00:00:00Z")),
+            rule("vence agora", Some("2026-10-18T09:00:00-03:00")),
+            rule("futura", Some("2026-10-18T12:00:01Z")),
+        ]}))
+        .unwrap();
+        let now: DateTime<Utc> = "2026-10-18T12:00:00Z".parse().unwrap();
+
+        let expired: Vec<(usize, &str)> = config
+            .expired_rules(now)
+            .into_iter()
+            .map(|(index, rule)| (index, rule.name.as_str()))
+            .collect();
+        assert_eq!(expired, vec![(1, "vencida"), (2, "vence agora")]);
+
+        let removed: Vec<String> = config.prune_expired(now).into_iter().map(|rule| rule.name).collect();
+        assert_eq!(removed, vec!["vencida", "vence agora"]);
+        let kept: Vec<&str> = config.rules.iter().map(|rule| rule.name.as_str()).collect();
+        assert_eq!(kept, vec!["permanente", "futura"]);
+        assert!(config.prune_expired(now).is_empty());
+    }
+}
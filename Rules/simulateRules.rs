@@ -0,0 +1,498 @@
+use std::{
+    env, fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    process,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use firewall_rules::connTrack::ConnState;
+use firewall_rules::evalRules::{Decision, Flow, RuleEngine, Verdict};
+use firewall_rules::readRules::carregar_regras;
+use firewall_rules::ruleSchema::{carregar_regras_estrito, gerar_schema_json};
+use firewall_rules::writeRules::{Direction, Protocol};
+
+const USAGE: &str = "\
+Uso: simular_regras --rules <arquivo> [opções] (<fluxo> | --csv <arquivo>)
+       simular_regras --check-schema <arquivo>
+       simular_regras --emit-schema
+
+Fluxo:
+  --proto <tcp|udp|icmp>    Protocolo (obrigatório)
+  --src <ip>                Endereço de origem (obrigatório)
+  --dst <ip>                Endereço de destino (padrão 0.0.0.0 ou ::)
+  --sport <porta>           Porta de origem
+  --dport <porta>           Porta de destino
+  --dir <inbound|outbound>  Sentido (padrão inbound)
+  --bytes <n>               Tamanho do fluxo (padrão 0)
+
+Opções:
+  --csv <arquivo>           Avalia cada linha (colunas proto,src,dst,sport,dport,dir,bytes)
+  --stateful                Linhas do CSV compartilham a tabela de conexões
+  --policy <allow|deny>     Política padrão (padrão deny)
+  --at <RFC 3339>           Avalia como se fosse esse instante (padrão agora)
+
+Schema:
+  --check-schema <arquivo>  Valida o arquivo de regras em modo estrito
+  --emit-schema             Imprime o JSON Schema do rules.json
+  -h, --help                Mostra esta ajuda";
+
+/// Linha do CSV de fluxos; colunas vazias ou ausentes usam o padrão
+#[derive(Debug, Default, Deserialize)]
+struct FlowRecord {
+    proto: Option<String>,
+    src: Option<String>,
+    dst: Option<String>,
+    sport: Option<u16>,
+    dport: Option<u16>,
+    dir: Option<String>,
+    bytes: Option<u64>,
+}
+
+/// Opções da linha de comando já interpretadas
+#[derive(Debug)]
+struct Options {
+    rules_path: String,
+    csv_path: Option<String>,
+    flow: FlowRecord, // Fluxo único, quando não há --csv
+    policy: Verdict,
+    at: DateTime<Utc>,
+    stateful: bool,
+}
+
+/// Resultado de uma linha do CSV: o fluxo e a decisão, ou por que a linha é inválida
+type RowResult = Result<(Flow, Decision), CliError>;
+
+/// Erro de uso da linha de comando ou de um fluxo do CSV
+#[derive(Debug)]
+struct CliError(String);
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+fn main() {
+    process::exit(run(env::args().skip(1).collect()));
+}
+
+/// Executa a simulação e retorna o código de saída
+/// (0 = sucesso, 1 = falha ao carregar/avaliar, 2 = uso incorreto)
+fn run(args: Vec<String>) -> i32 {
+    if args.is_empty() || args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return if args.is_empty() { 2 } else { 0 };
+    }
+
+    if let Some(code) = run_schema(&args) {
+        return code;
+    }
+
+    let options = match parse_args(args, Utc::now()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("❌ {}\n\n{}", e, USAGE);
+            return 2;
+        }
+    };
+    let engine = match carregar_regras(&options.rules_path) {
+        Ok(config) => RuleEngine::new(config).with_default_policy(options.policy),
+        Err(e) => {
+            eprintln!("❌ {}", e);
+            return 1;
+        }
+    };
+
+    match options.csv_path {
+        Some(path) => simulate_csv(&engine, &path, options.at, options.stateful),
+        None => match build_flow(options.flow) {
+            Ok(flow) => {
+                print_decision(1, &flow, &engine.evaluate_at(&flow, options.at));
+                0
+            }
+            Err(e) => {
+                eprintln!("❌ {}\n\n{}", e, USAGE);
+                2
+            }
+        },
+    }
+}
+
+/// Executa `--emit-schema` ou `--check-schema`; `None` se não é um comando de schema
+fn run_schema(args: &[String]) -> Option<i32> {
+    match args {
+        [flag] if flag == "--emit-schema" => {
+            println!("{}", gerar_schema_json());
+            Some(0)
+        }
+        [flag, path] if flag == "--check-schema" => Some(check_schema(path)),
+        _ if args.iter().any(|arg| arg == "--emit-schema" || arg == "--check-schema") => {
+            eprintln!("❌ use '--emit-schema' ou '--check-schema <arquivo>' sem outras opções\n\n{}", USAGE);
+            Some(2)
+        }
+        _ => None,
+    }
+}
+
+/// Valida o arquivo em modo estrito (0 = válido, 1 = inválido ou ilegível)
+fn check_schema(path: &str) -> i32 {
+    match carregar_regras_estrito(path) {
+        Ok(config) => {
+            println!("✅ {}: {} regra(s) seguem o schema", path, config.rules.len());
+            0
+        }
+        Err(e) => {
+            eprintln!("❌ {}: {}", path, e.to_string().trim_end());
+            1
+        }
+    }
+}
+
+/// Interpreta os argumentos (sem o nome do programa); `now` vale sem `--at`
+fn parse_args(args: Vec<String>, now: DateTime<Utc>) -> Result<Options, CliError> {
+    let mut flow = FlowRecord::default();
+    let mut rules_path = None;
+    let mut csv_path = None;
+    let mut policy = Verdict::Deny;
+    let mut at = now;
+    let mut stateful = false;
+
+    let mut iter = args.into_iter();
+    while let Some(flag) = iter.next() {
+        if flag == "--stateful" {
+            stateful = true;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| CliError(format!("opção '{}' sem valor", flag)))?;
+        match flag.as_str() {
+            "--rules" => set(&mut rules_path, value),
+            "--csv" => set(&mut csv_path, value),
+            "--policy" => parse_policy(&value).map(|verdict| policy = verdict),
+            "--at" => DateTime::parse_from_rfc3339(&value)
+                .map(|time| at = time.with_timezone(&Utc))
+                .map_err(|e| CliError(format!("--at '{}': {}", value, e))),
+            "--proto" => set(&mut flow.proto, value),
+            "--src" => set(&mut flow.src, value),
+            "--dst" => set(&mut flow.dst, value),
+            "--dir" => set(&mut flow.dir, value),
+            "--sport" => parse_number("--sport", &value).and_then(|port| set(&mut flow.sport, port)),
+            "--dport" => parse_number("--dport", &value).and_then(|port| set(&mut flow.dport, port)),
+            "--bytes" => parse_number("--bytes", &value).and_then(|bytes| set(&mut flow.bytes, bytes)),
+            _ => Err(CliError(format!("opção desconhecida '{}'", flag))),
+        }?;
+    }
+
+    let rules_path =
+        rules_path.ok_or_else(|| CliError("informe o arquivo de regras com --rules".to_string()))?;
+    Ok(Options {
+        rules_path,
+        csv_path,
+        flow,
+        policy,
+        at,
+        stateful,
+    })
+}
+
+/// A política padrão é um veredito final: `log` não decide nada
+fn parse_policy(value: &str) -> Result<Verdict, CliError> {
+    match value.to_ascii_lowercase().as_str() {
+        "allow" => Ok(Verdict::Allow),
+        "deny" => Ok(Verdict::Deny),
+        _ => Err(CliError(format!("--policy '{}': use allow ou deny", value))),
+    }
+}
+
+/// Avalia cada linha do CSV; linhas inválidas são reportadas e puladas
+fn simulate_csv(engine: &RuleEngine, path: &str, at: DateTime<Utc>, stateful: bool) -> i32 {
+    let file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("❌ falha ao abrir '{}': {}", path, e);
+            return 1;
+        }
+    };
+
+    let mut failures = 0;
+    for (line, result) in evaluate_csv(engine, file, at, stateful) {
+        match result {
+            Ok((flow, decision)) => print_decision(line, &flow, &decision),
+            Err(e) => {
+                eprintln!("❌ linha {}: {}", line, e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Avalia as linhas do CSV em ordem, cada uma com o número da linha no arquivo
+///
+/// Sem `stateful` a tabela de conexões é esvaziada antes de cada linha, então
+/// uma linha nunca depende das anteriores; com ele, respostas encontram as
+/// conexões abertas pelas linhas de cima.
+fn evaluate_csv<R: io::Read>(
+    engine: &RuleEngine,
+    input: R,
+    at: DateTime<Utc>,
+    stateful: bool,
+) -> Vec<(usize, RowResult)> {
+    // Linhas podem omitir as colunas finais (ex.: "icmp,10.0.0.1")
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(input);
+
+    reader
+        .deserialize::<FlowRecord>()
+        .enumerate()
+        .map(|(index, record)| {
+            let line = index + 2; // Linha 1 é o cabeçalho
+            let result = record
+                .map_err(|e| CliError(e.to_string()))
+                .and_then(build_flow)
+                .map(|flow| {
+                    if !stateful {
+                        engine.conntrack().clear();
+                    }
+                    let decision = engine.evaluate_at(&flow, at);
+                    (flow, decision)
+                });
+            (line, result)
+        })
+        .collect()
+}
+
+fn build_flow(record: FlowRecord) -> Result<Flow, CliError> {
+    let proto = record.proto.as_deref().ok_or_else(|| missing("proto"))?;
+    let protocol: Protocol = parse_enum("proto", proto)?;
+    let direction: Direction = match record.dir.as_deref() {
+        Some(dir) => parse_enum("dir", dir)?,
+        None => Direction::Inbound,
+    };
+    let src_ip = parse_ip("src", record.src.as_deref().ok_or_else(|| missing("src"))?)?;
+    let dst_ip = match record.dst.as_deref() {
+        Some(dst) => parse_ip("dst", dst)?,
+        None if src_ip.is_ipv4() => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        None => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+
+    if protocol == Protocol::Any || direction == Direction::Any {
+        return Err(CliError(
+            "o fluxo precisa de protocolo e sentido concretos (não 'any')".to_string(),
+        ));
+    }
+
+    Ok(Flow {
+        direction,
+        protocol,
+        src_ip,
+        dst_ip,
+        src_port: record.sport,
+        dst_port: record.dport,
+        bytes: record.bytes.unwrap_or(0),
+        tcp_flags: None,
+    })
+}
+
+fn print_decision(line: usize, flow: &Flow, decision: &Decision) {
+    let (icon, verdict) = match decision.verdict {
+        Verdict::Allow => ("✅", "allow"),
+        Verdict::Deny => ("❌", "deny"),
+        Verdict::Log => ("📝", "log"),
+    };
+    let port = |port: Option<u16>| port.map_or_else(|| "*".to_string(), |port| port.to_string());
+    let matched = match &decision.rule {
+        Some(rule) => format!("regra '{}'", rule),
+        None => "política padrão".to_string(),
+    };
+    // Com --stateful, respostas no CSV já encontram a conexão das linhas anteriores
+    let state = match decision.state {
+        ConnState::New => String::new(),
+        state => format!(" [{}]", state),
+    };
+
+    println!(
+        "{} #{} {} {} {}:{} -> {}:{} => {} ({}){}{}",
+        icon,
+        line,
+        flow.direction,
+        flow.protocol,
+        flow.src_ip,
+        port(flow.src_port),
+        flow.dst_ip,
+        port(flow.dst_port),
+        verdict,
+        matched,
+        state,
+        if decision.log { " [log]" } else { "" }
+    );
+}
+
+fn set<T>(slot: &mut Option<T>, value: T) -> Result<(), CliError> {
+    *slot = Some(value);
+    Ok(())
+}
+
+/// Interpreta valores de enums serde (`tcp`, `inbound`, `allow`, ...)
+fn parse_enum<T: DeserializeOwned>(field: &str, value: &str) -> Result<T, CliError> {
+    serde_json::from_value(Value::from(value.to_ascii_lowercase()))
+        .map_err(|e| CliError(format!("{} '{}': {}", field, value, e)))
+}
+
+fn parse_number<T: std::str::FromStr>(field: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("{} '{}': número inválido", field, value)))
+}
+
+fn parse_ip(field: &str, value: &str) -> Result<IpAddr, CliError> {
+    value
+        .parse()
+        .map_err(|_| CliError(format!("{} '{}': endereço IP inválido", field, value)))
+}
+
+fn missing(field: &str) -> CliError {
+    CliError(format!("campo '{}' obrigatório", field))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-17T12:00:00Z".parse().unwrap()
+    }
+
+    fn engine(rules: serde_json::Value) -> RuleEngine {
+        RuleEngine::new(serde_json::from_value(json!({ "rules": rules })).unwrap())
+    }
+
+    /// Veredito de cada linha, ou o erro da linha
+    fn verdicts(rows: Vec<(usize, RowResult)>) -> Vec<(usize, Result<Verdict, String>)> {
+        rows.into_iter()
+            .map(|(line, result)| (line, result.map(|(_, decision)| decision.verdict).map_err(|e| e.0)))
+            .collect()
+    }
+
+    #[test]
+    fn argumentos_validos() {
+        let options = parse_args(
+            args("--rules r.json --proto tcp --src 10.0.0.5 --dport 22 --policy ALLOW --stateful"),
+            now(),
+        )
+        .unwrap();
+        assert_eq!(options.rules_path, "r.json");
+        assert_eq!((options.flow.proto.as_deref(), options.flow.dport), (Some("tcp"), Some(22)));
+        assert_eq!((options.policy, options.at, options.stateful), (Verdict::Allow, now(), true));
+
+        let options = parse_args(args("--rules r.json --csv f.csv --at 2026-10-17T23:30:00-03:00"), now()).unwrap();
+        assert_eq!(options.csv_path.as_deref(), Some("f.csv"));
+        assert_eq!(options.at, "2026-10-18T02:30:00Z".parse::<DateTime<Utc>>().unwrap());
+        assert_eq!((options.policy, options.stateful), (Verdict::Deny, false));
+    }
+
+    #[test]
+    fn argumentos_invalidos() {
+        let cases = [
+            ("--rules r.json --policy log", "--policy 'log': use allow ou deny"),
+            ("--rules r.json --policy", "opção '--policy' sem valor"),
+            ("--rules r.json --porta 22", "opção desconhecida '--porta'"),
+            ("--rules r.json --dport 70000", "--dport '70000': número inválido"),
+            ("--proto tcp --src 10.0.0.5", "informe o arquivo de regras com --rules"),
+        ];
+        for (line, expected) in cases {
+            assert_eq!(parse_args(args(line), now()).unwrap_err().0, expected, "{}", line);
+        }
+        let error = parse_args(args("--rules r.json --at amanhã"), now()).unwrap_err();
+        assert!(error.0.starts_with("--at 'amanhã': "), "{}", error);
+    }
+
+    #[test]
+    fn csv_com_linha_invalida_segue_avaliando() {
+        let engine = engine(json!([
+            { "name": "SSH", "action": "allow", "direction": "inbound", "protocol": "tcp", "dst_port": 22 }
+        ]));
+        let csv = "proto,src,dst,sport,dport,dir,bytes\n\
+                   tcp,10.0.0.5,,40000,22\n\
+                   gre,10.0.0.5\n\
+                   tcp,10.0.0.999,,,22\n\
+                   udp,10.0.0.5,,,porta\n\
+                   tcp,10.0.0.5,10.0.0.1,40000,80,inbound,60\n";
+        let rows = verdicts(evaluate_csv(&engine, csv.as_bytes(), now(), false));
+
+        assert_eq!(rows[0], (2, Ok(Verdict::Allow)));
+        assert_eq!(rows[1].0, 3);
+        assert!(rows[1].1.as_ref().unwrap_err().starts_with("proto 'gre'"));
+        assert_eq!(rows[2], (4, Err("src '10.0.0.999': endereço IP inválido".to_string())));
+        assert_eq!(rows[3].0, 5);
+        assert!(rows[3].1.is_err());
+        assert_eq!(rows[4], (6, Ok(Verdict::Deny)));
+    }
+
+    #[test]
+    fn linhas_independentes_sem_stateful() {
+        let engine = || {
+            engine(json!([
+                { "name": "Saída", "action": "allow", "direction": "outbound", "protocol": "any" },
+                { "name": "Retorno", "action": "allow", "direction": "inbound", "protocol": "any",
+                  "state": ["established"] }
+            ]))
+        };
+        // Requisição e a resposta dela
+        let csv = "proto,src,dst,sport,dport,dir\n\
+                   udp,10.0.0.5,8.8.8.8,5353,53,outbound\n\
+                   udp,8.8.8.8,10.0.0.5,53,5353,inbound\n";
+
+        let rows = verdicts(evaluate_csv(&engine(), csv.as_bytes(), now(), false));
+        assert_eq!(rows, vec![(2, Ok(Verdict::Allow)), (3, Ok(Verdict::Deny))]);
+        let rows = verdicts(evaluate_csv(&engine(), csv.as_bytes(), now(), true));
+        assert_eq!(rows, vec![(2, Ok(Verdict::Allow)), (3, Ok(Verdict::Allow))]);
+    }
+
+    #[test]
+    fn at_avalia_no_instante_informado() {
+        let engine = engine(json!([
+            { "name": "Expediente", "action": "allow", "direction": "inbound", "protocol": "tcp",
+              "schedule": { "hours": ["08:00-18:00"] } }
+        ]));
+        let csv = "proto,src,dport\ntcp,10.0.0.5,443\n";
+        for (at, expected) in [("2026-10-17T10:00:00Z", Verdict::Allow), ("2026-10-17T23:30:00Z", Verdict::Deny)] {
+            let options = parse_args(args(&format!("--rules r.json --at {}", at)), now()).unwrap();
+            let rows = verdicts(evaluate_csv(&engine, csv.as_bytes(), options.at, false));
+            assert_eq!(rows, vec![(2, Ok(expected))], "{}", at);
+        }
+    }
+
+    #[test]
+    fn comandos_de_schema() {
+        assert_eq!(run_schema(&args("--rules r.json --proto tcp")), None);
+        assert_eq!(run_schema(&args("--check-schema")), Some(2));
+        assert_eq!(run_schema(&args("--rules r.json --emit-schema")), Some(2));
+
+        let rules = concat!(env!("CARGO_MANIFEST_DIR"), "/rules.json");
+        assert_eq!(check_schema(rules), 0);
+        assert_eq!(check_schema(concat!(env!("CARGO_MANIFEST_DIR"), "/inexistente.json")), 1);
+    }
+
+    #[test]
+    fn schema_versionado_igual_ao_gerado() {
+        // Regenere com `simular_regras --emit-schema > rules.schema.json`
+        assert_eq!(include_str!("rules.schema.json").trim_end(), gerar_schema_json());
+    }
+}
@@ -1,17 +1,417 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use chrono::{DateTime, FixedOffset, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+use super::connTrack::ConnState;
+use super::ipNet::IpNet;
+use super::objectSets::{AddrRef, PortRef, RuleGroup};
+use super::portSpec::PortSpec;
+use super::readRules::RuleFormat;
+use super::schedule::Schedule;
+
+/// Ação executada quando a regra casa com o fluxo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    #[serde(alias = "ACCEPT")]
+    Allow,
+    #[serde(alias = "DROP", alias = "REJECT")]
+    Deny,
+    #[serde(alias = "LOG")]
+    Log,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Allow => "allow",
+            Action::Deny => "deny",
+            Action::Log => "log",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Sentido do tráfego em relação ao host
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Inbound,
+    Outbound,
+    Any,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Inbound => "inbound",
+            Direction::Outbound => "outbound",
+            Direction::Any => "any",
+        }
+    }
+
+    /// `Any` na regra casa com qualquer sentido
+    pub fn matches(self, other: Direction) -> bool {
+        self == Direction::Any || self == other
+    }
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Protocolo de transporte (ou ICMP)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Icmp,
+    Any,
+}
+
+impl Protocol {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Udp => "udp",
+            Protocol::Icmp => "icmp",
+            Protocol::Any => "any",
+        }
+    }
+
+    /// `Any` na regra casa com qualquer protocolo
+    pub fn matches(self, other: Protocol) -> bool {
+        self == Protocol::Any || self == other
+    }
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Severidade informativa da regra (formato YAML do README)
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// Regra do firewall, avaliada em ordem de prioridade (first-match)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct rules {
-    name: String,
-    action: String,       // "allow", "deny", "log"
-    direction: String,    // "inbound", "outbound"
-    protocol: String,     // "tcp", "udp", "icmp"
-    src_ip: Option<String>,  // Opcional (pode ser null no JSON)
-    dst_ip: Option<String>,
-    dst_port: Option<u16>,
+    pub name: String,
+    pub action: Action,
+    pub direction: Direction,
+    pub protocol: Protocol,
+    #[serde(alias = "source", skip_serializing_if = "Option::is_none")]
+    pub src_ip: Option<AddrRef>,   // CIDR, IP único ou conjunto ("@admins"); opcional
+    #[serde(alias = "destination", skip_serializing_if = "Option::is_none")]
+    pub dst_ip: Option<AddrRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src_port: Option<PortRef>, // Porta, intervalo ("8000-8100"), lista ([80, 443]) ou "@web"
+    #[serde(alias = "port", skip_serializing_if = "Option::is_none")]
+    pub dst_port: Option<PortRef>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state: Option<Vec<ConnState>>, // Estados de conexão ("established", "related", "new")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<String>,      // "INPUT", "OUTPUT", "FORWARD"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub log: bool,                  // Registra o fluxo além de aplicar a ação
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<i32>,      // Menor valor é avaliado primeiro (ausente = 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,      // Grupo nomeado ("ssh-admin", "dns")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<Schedule>, // Dias/horários em que a regra vale
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<FixedOffset>>, // RFC 3339; a regra deixa de valer após
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Conjunto de regras do firewall (rules.json)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct FirewallConfig {
-    rules: Vec<rules>,
-}
\ No newline at end of file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>, // Fuso IANA das janelas de horário (padrão UTC)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, RuleGroup>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub address_sets: BTreeMap<String, Vec<IpNet>>, // Referenciados como "@nome" em src_ip/dst_ip
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub port_sets: BTreeMap<String, PortSpec>,      // Referenciados como "@nome" em src_port/dst_port
+    pub rules: Vec<rules>,
+}
+
+/// Erro ao editar o conjunto de regras
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleEditError {
+    DuplicateName(String),
+    NotFound(String),
+    IndexOutOfBounds { index: usize, len: usize },
+}
+
+impl fmt::Display for RuleEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleEditError::DuplicateName(name) => write!(f, "já existe uma regra '{}'", name),
+            RuleEditError::NotFound(name) => write!(f, "regra '{}' não encontrada", name),
+            RuleEditError::IndexOutOfBounds { index, len } => write!(
+                f,
+                "posição {} inválida para um conjunto de {} regra(s)",
+                index, len
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RuleEditError {}
+
+impl FirewallConfig {
+    /// Posição da regra com o nome informado
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.rules.iter().position(|rule| rule.name == name)
+    }
+
+    pub fn find(&self, name: &str) -> Option<&rules> {
+        self.rules.iter().find(|rule| rule.name == name)
+    }
+
+    /// Adiciona a regra ao final do conjunto (menor prioridade)
+    pub fn add_rule(&mut self, rule: rules) -> Result<(), RuleEditError> {
+        self.insert_rule(self.rules.len(), rule)
+    }
+
+    /// Insere a regra na posição informada, deslocando as seguintes
+    pub fn insert_rule(&mut self, index: usize, rule: rules) -> Result<(), RuleEditError> {
+        if self.position(&rule.name).is_some() {
+            return Err(RuleEditError::DuplicateName(rule.name));
+        }
+        if index > self.rules.len() {
+            return Err(RuleEditError::IndexOutOfBounds {
+                index,
+                len: self.rules.len(),
+            });
+        }
+        self.rules.insert(index, rule);
+        Ok(())
+    }
+
+    /// Substitui a regra `name` mantendo sua posição; retorna a versão anterior
+    pub fn update_rule(&mut self, name: &str, rule: rules) -> Result<rules, RuleEditError> {
+        let index = self
+            .position(name)
+            .ok_or_else(|| RuleEditError::NotFound(name.to_string()))?;
+
+        // Renomear para um nome já usado por outra regra não é permitido
+        if rule.name != name && self.position(&rule.name).is_some() {
+            return Err(RuleEditError::DuplicateName(rule.name));
+        }
+        Ok(std::mem::replace(&mut self.rules[index], rule))
+    }
+
+    pub fn remove_rule(&mut self, name: &str) -> Result<rules, RuleEditError> {
+        let index = self
+            .position(name)
+            .ok_or_else(|| RuleEditError::NotFound(name.to_string()))?;
+        Ok(self.rules.remove(index))
+    }
+
+    /// Move a regra `name` para a posição `new_index`
+    pub fn move_rule(&mut self, name: &str, new_index: usize) -> Result<(), RuleEditError> {
+        let index = self
+            .position(name)
+            .ok_or_else(|| RuleEditError::NotFound(name.to_string()))?;
+        if new_index >= self.rules.len() {
+            return Err(RuleEditError::IndexOutOfBounds {
+                index: new_index,
+                len: self.rules.len(),
+            });
+        }
+        let rule = self.rules.remove(index);
+        self.rules.insert(new_index, rule);
+        Ok(())
+    }
+
+    /// Habilita ou desabilita todas as regras do grupo de uma vez
+    pub fn set_group_enabled(&mut self, group: &str, enabled: bool) {
+        self.groups.entry(group.to_string()).or_default().enabled = enabled;
+    }
+
+    /// Regras pertencentes ao grupo, na ordem do arquivo
+    pub fn group_rules<'a>(&'a self, group: &'a str) -> impl Iterator<Item = &'a rules> + 'a {
+        self.rules
+            .iter()
+            .filter(move |rule| rule.group.as_deref() == Some(group))
+    }
+}
+
+/// Distingue os temporários de gravações simultâneas no mesmo processo
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Grava o conjunto de regras de forma atômica
+///
+/// O conteúdo é escrito em um arquivo temporário no mesmo diretório e
+/// depois renomeado sobre `path`, de modo que leitores nunca vejam um
+/// rules.json parcialmente escrito. O formato segue a extensão do arquivo.
+/// O diretório é sincronizado após o rename para que a troca sobreviva a
+/// uma queda de energia.
+pub fn atualizar_regras(path: &str, config: &FirewallConfig) -> io::Result<()> {
+    let format = RuleFormat::from_path(path).unwrap_or(RuleFormat::Json);
+    let data = serializar_regras(config, format)?;
+
+    let target = Path::new(path);
+    let file_name = target
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "caminho sem nome de arquivo"))?;
+    let tmp_path = target.with_file_name(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = fs::File::create(&tmp_path)?;
+        io::Write::write_all(&mut file, data.as_bytes())?;
+        file.sync_all()?;
+
+        // Mantém as permissões do arquivo original (ex.: chmod 600)
+        if let Ok(metadata) = fs::metadata(target) {
+            fs::set_permissions(&tmp_path, metadata.permissions())?;
+        }
+        fs::rename(&tmp_path, target)?;
+        sync_parent_dir(target)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Grava em disco a entrada de diretório criada pelo rename
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Fora do Unix diretórios não podem ser abertos para sincronização
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Grava o conjunto de regras omitindo as regras já expiradas
+///
+/// O `config` em memória não é alterado; retorna as regras removidas.
+pub fn atualizar_regras_podando(
+    path: &str,
+    config: &FirewallConfig,
+    now: DateTime<Utc>,
+) -> io::Result<Vec<rules>> {
+    let mut pruned = config.clone();
+    let expired = pruned.prune_expired(now);
+    atualizar_regras(path, &pruned)?;
+    Ok(expired)
+}
+
+/// Serializa o conjunto de regras no formato informado
+pub fn serializar_regras(config: &FirewallConfig, format: RuleFormat) -> io::Result<String> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+
+    match format {
+        RuleFormat::Json => Ok(serde_json::to_string_pretty(config)?),
+        RuleFormat::Yaml => serde_yaml::to_string(config).map_err(|e| invalid(e.to_string())),
+        // Via `toml::Value`, que grava valores antes de tabelas (ex.: `expires_at` após `schedule`)
+        RuleFormat::Toml => toml::Value::try_from(config)
+            .and_then(|value| toml::to_string_pretty(&value))
+            .map_err(|e| invalid(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::readRules::validar_regras_formato;
+
+    const COMPLETO: &str = r#"{
+        "timezone": "America/Sao_Paulo",
+        "groups": { "ssh-admin": { "enabled": false, "description": "Acesso de manutenção" } },
+        "address_sets": { "admins": ["10.0.0.0/24", "2001:db8::1"] },
+        "port_sets": { "web": [80, 443, "8000-8100"] },
+        "rules": [
+            { "name": "SSH admin", "action": "allow", "direction": "inbound", "protocol": "tcp",
+              "src_ip": "@admins", "dst_port": 22, "group": "ssh-admin", "priority": -10,
+              "schedule": { "days": ["mon", "fri"], "hours": ["08:00-18:00"] },
+              "expires_at": "2027-01-01T00:00:00-03:00" },
+            { "name": "Web", "action": "allow", "direction": "any", "protocol": "tcp",
+              "dst_port": "@web", "state": ["new", "established"], "log": true },
+            { "name": "Bloquear ICMP", "action": "deny", "direction": "inbound", "protocol": "icmp",
+              "src_ip": "fe80::/10", "chain": "INPUT", "severity": "high" },
+            { "name": "Portas altas", "action": "log", "direction": "outbound", "protocol": "udp",
+              "src_port": "1024-65535", "dst_ip": "8.8.8.8" }
+        ]
+    }"#;
+
+    #[test]
+    fn json_yaml_toml_json_preserva_as_regras() {
+        let original = validar_regras_formato(COMPLETO, RuleFormat::Json).unwrap();
+
+        let yaml = serializar_regras(&original, RuleFormat::Yaml).unwrap();
+        let from_yaml = validar_regras_formato(&yaml, RuleFormat::Yaml).unwrap();
+        assert_eq!(from_yaml, original, "YAML:\n{}", yaml);
+
+        let toml = serializar_regras(&from_yaml, RuleFormat::Toml).unwrap();
+        let from_toml = validar_regras_formato(&toml, RuleFormat::Toml).unwrap();
+        assert_eq!(from_toml, original, "TOML:\n{}", toml);
+
+        let json = serializar_regras(&from_toml, RuleFormat::Json).unwrap();
+        assert_eq!(validar_regras_formato(&json, RuleFormat::Json).unwrap(), original);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+            serde_json::to_value(&original).unwrap()
+        );
+    }
+
+    #[test]
+    fn rules_json_do_repositorio_sobrevive_a_conversao() {
+        let original = validar_regras_formato(include_str!("rules.json"), RuleFormat::Json).unwrap();
+        for format in [RuleFormat::Yaml, RuleFormat::Toml] {
+            let data = serializar_regras(&original, format).unwrap();
+            assert_eq!(validar_regras_formato(&data, format).unwrap(), original, "{:?}", format);
+        }
+    }
+}